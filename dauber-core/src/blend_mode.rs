@@ -0,0 +1,67 @@
+/// How the color of a paint combines with the pixels it is drawn over.
+///
+/// The Porter-Duff modes come first, followed by the separable modes, which blend
+/// every channel on its own, and the non-separable modes, which blend hue,
+/// saturation and luminosity.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// Nothing is kept.
+    Clear,
+    /// Only the source is kept.
+    Src,
+    /// Only the destination is kept.
+    Dst,
+    /// The source is drawn over the destination.
+    #[default]
+    SrcOver,
+    /// The destination is drawn over the source.
+    DstOver,
+    /// The source where the destination is.
+    SrcIn,
+    /// The destination where the source is.
+    DstIn,
+    /// The source where the destination is not.
+    SrcOut,
+    /// The destination where the source is not.
+    DstOut,
+    /// The source inside the destination, drawn over it.
+    SrcATop,
+    /// The destination inside the source, drawn over it.
+    DstATop,
+    /// The source where the destination is not and the other way around.
+    Xor,
+    /// The sum of both, clamped.
+    Plus,
+    /// The product of both, including alpha.
+    Modulate,
+    /// The inverse of the product of both inverses.
+    Screen,
+    /// Multiplies or screens, depending on the destination.
+    Overlay,
+    /// The darker of both.
+    Darken,
+    /// The lighter of both.
+    Lighten,
+    /// Brightens the destination to reflect the source.
+    ColorDodge,
+    /// Darkens the destination to reflect the source.
+    ColorBurn,
+    /// Multiplies or screens, depending on the source.
+    HardLight,
+    /// Darkens or lightens, depending on the source.
+    SoftLight,
+    /// The larger minus the smaller of both.
+    Difference,
+    /// Like `Difference` with less contrast.
+    Exclusion,
+    /// The product of both, drawn over the destination.
+    Multiply,
+    /// The hue of the source with the saturation and luminosity of the destination.
+    Hue,
+    /// The saturation of the source with the hue and luminosity of the destination.
+    Saturation,
+    /// The hue and saturation of the source with the luminosity of the destination.
+    Color,
+    /// The luminosity of the source with the hue and saturation of the destination.
+    Luminosity,
+}
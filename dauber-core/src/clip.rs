@@ -0,0 +1,9 @@
+/// How a new clip combines with the current one.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ClipOp {
+    /// Keep drawing only where both the current clip and the new shape allow it.
+    #[default]
+    Intersect,
+    /// Cut the new shape out of the current clip.
+    Difference,
+}
@@ -1,26 +1,219 @@
+use crate::clip::ClipOp;
+use crate::error::Result;
+use crate::geom::matrix::Matrix;
+use crate::geom::point::Point;
+use crate::geom::rect::Rect;
+use crate::glyph::GlyphRun;
+use crate::image::Image;
 use crate::image_info::ImageInfo;
 use crate::paint::Paint;
 use crate::path::Path;
+use crate::pixmap::PixmapMut;
+use crate::sampling::SamplingOptions;
 
+/// Something to draw on, usable as `dyn Device` to pick the backend at runtime.
 pub trait Device {
-    fn new(info: ImageInfo) -> Self;
-
     fn image_info(&self) -> &ImageInfo;
 
-    fn draw_path(&mut self, path: &Path, paint: &Paint);
+    /// The transform applied to everything drawn.
+    fn matrix(&self) -> &Matrix;
+
+    fn set_matrix(&mut self, matrix: Matrix);
+
+    /// Push the current matrix and clip onto the state stack.
+    fn save(&mut self);
+
+    /// Pop the state pushed by the last [`save`](Device::save), if any.
+    fn restore(&mut self);
+
+    /// The number of saved states plus one for the current state.
+    fn save_count(&self) -> usize;
+
+    /// Combine the clip with `path`, transformed by the current matrix.
+    fn clip_path(&mut self, path: &Path, op: ClipOp, anti_alias: bool) -> Result<()>;
+
+    fn draw_path(&mut self, path: &Path, paint: &Paint) -> Result<()>;
+
+    /// Draw the `src` part of `image` scaled into `dst`, transformed by the current matrix.
+    ///
+    /// Samples never reach outside of `src`. The alpha of the paint color, its blend mode
+    /// and anti-aliasing apply, everything else about `paint` is ignored.
+    fn draw_image_rect(
+        &mut self,
+        image: &Image,
+        src: &Rect,
+        dst: &Rect,
+        sampling: &SamplingOptions,
+        paint: &Paint,
+    ) -> Result<()>;
+
+    /// Draw the glyphs of `run` with the run placed at `origin`, transformed by the current matrix.
+    ///
+    /// By default the outlines are drawn as one path.
+    fn draw_glyph_run(&mut self, run: &GlyphRun<'_>, origin: Point, paint: &Paint) -> Result<()> {
+        self.draw_path(&run.to_path(origin), paint)
+    }
+
+    /// Copy the pixels starting at (`src_x`, `src_y`) into `dst`, converting them to its format.
+    ///
+    /// Everything drawn so far is included. `false` if `dst` does not overlap the device.
+    fn read_pixels(&mut self, dst: &mut PixmapMut<'_>, src_x: i32, src_y: i32) -> Result<bool>;
 }
 
-struct Sink;
+/// Devices that can be created from nothing but the image they render.
+pub trait DeviceFactory: Device + Sized {
+    fn try_new(info: ImageInfo) -> Result<Self>;
 
-impl Device for Sink {
-    fn new(_: ImageInfo) -> Self {
-        Self
+    /// Like [`try_new`](DeviceFactory::try_new), panicking on failure.
+    fn new(info: ImageInfo) -> Self {
+        match Self::try_new(info) {
+            Ok(device) => device,
+            Err(e) => panic!("failed to create the device: {e}"),
+        }
+    }
+}
+
+macro_rules! forward_device {
+    () => {
+        #[inline]
+        fn image_info(&self) -> &ImageInfo {
+            (**self).image_info()
+        }
+
+        #[inline]
+        fn matrix(&self) -> &Matrix {
+            (**self).matrix()
+        }
+
+        #[inline]
+        fn set_matrix(&mut self, matrix: Matrix) {
+            (**self).set_matrix(matrix)
+        }
+
+        #[inline]
+        fn save(&mut self) {
+            (**self).save()
+        }
+
+        #[inline]
+        fn restore(&mut self) {
+            (**self).restore()
+        }
+
+        #[inline]
+        fn save_count(&self) -> usize {
+            (**self).save_count()
+        }
+
+        #[inline]
+        fn clip_path(&mut self, path: &Path, op: ClipOp, anti_alias: bool) -> Result<()> {
+            (**self).clip_path(path, op, anti_alias)
+        }
+
+        #[inline]
+        fn draw_path(&mut self, path: &Path, paint: &Paint) -> Result<()> {
+            (**self).draw_path(path, paint)
+        }
+
+        #[inline]
+        fn draw_image_rect(
+            &mut self,
+            image: &Image,
+            src: &Rect,
+            dst: &Rect,
+            sampling: &SamplingOptions,
+            paint: &Paint,
+        ) -> Result<()> {
+            (**self).draw_image_rect(image, src, dst, sampling, paint)
+        }
+
+        #[inline]
+        fn draw_glyph_run(
+            &mut self,
+            run: &GlyphRun<'_>,
+            origin: Point,
+            paint: &Paint,
+        ) -> Result<()> {
+            (**self).draw_glyph_run(run, origin, paint)
+        }
+
+        #[inline]
+        fn read_pixels(&mut self, dst: &mut PixmapMut<'_>, src_x: i32, src_y: i32) -> Result<bool> {
+            (**self).read_pixels(dst, src_x, src_y)
+        }
+    };
+}
+
+impl<D: Device + ?Sized> Device for Box<D> {
+    forward_device!();
+}
+
+impl<D: Device + ?Sized> Device for &mut D {
+    forward_device!();
+}
+
+pub struct Sink {
+    matrix: Matrix,
+    saved: Vec<Matrix>,
+}
+
+impl DeviceFactory for Sink {
+    fn try_new(_: ImageInfo) -> Result<Self> {
+        Ok(Self {
+            matrix: Matrix::IDENTITY,
+            saved: vec![],
+        })
     }
+}
 
+impl Device for Sink {
     fn image_info(&self) -> &ImageInfo {
         static SINK_INFO: ImageInfo = ImageInfo::new_wh(0, 0);
         &SINK_INFO
     }
 
-    fn draw_path(&mut self, path: &Path, paint: &Paint) {}
+    fn matrix(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    fn set_matrix(&mut self, matrix: Matrix) {
+        self.matrix = matrix;
+    }
+
+    fn save(&mut self) {
+        self.saved.push(self.matrix);
+    }
+
+    fn restore(&mut self) {
+        if let Some(matrix) = self.saved.pop() {
+            self.matrix = matrix;
+        }
+    }
+
+    fn save_count(&self) -> usize {
+        self.saved.len() + 1
+    }
+
+    fn clip_path(&mut self, _path: &Path, _op: ClipOp, _anti_alias: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn draw_path(&mut self, _path: &Path, _paint: &Paint) -> Result<()> {
+        Ok(())
+    }
+
+    fn draw_image_rect(
+        &mut self,
+        _image: &Image,
+        _src: &Rect,
+        _dst: &Rect,
+        _sampling: &SamplingOptions,
+        _paint: &Paint,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn read_pixels(&mut self, _dst: &mut PixmapMut<'_>, _src_x: i32, _src_y: i32) -> Result<bool> {
+        Ok(false)
+    }
 }
@@ -0,0 +1,69 @@
+use crate::image_info::{ColorType, ImageInfo};
+use std::fmt;
+
+/// The error a backend reports.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Everything that can go wrong while creating a device or drawing with it.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The image is empty or too large for the device.
+    InvalidImageInfo(ImageInfo),
+    /// The device cannot render to pixels of this color type.
+    UnsupportedColorType(ColorType),
+    /// No GPU adapter is available.
+    NoAdapter,
+    /// The adapter refused to create a device.
+    RequestDevice(BoxError),
+    /// The GPU device stopped responding.
+    DeviceLost,
+    /// A path could not be broken into triangles.
+    Tessellation(BoxError),
+    /// The pixels could not be encoded.
+    Encode(BoxError),
+    /// An image file could not be decoded.
+    Decode(BoxError),
+    /// Font data could not be parsed.
+    Font(BoxError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidImageInfo(info) => write!(
+                f,
+                "invalid image size {}x{} for the device",
+                info.width, info.height
+            ),
+            Self::UnsupportedColorType(color_type) => {
+                write!(f, "the device cannot render to {color_type:?} pixels")
+            }
+            Self::NoAdapter => f.write_str("no GPU adapter available"),
+            Self::RequestDevice(e) => write!(f, "failed to request a GPU device: {e}"),
+            Self::DeviceLost => f.write_str("the GPU device was lost"),
+            Self::Tessellation(e) => write!(f, "failed to tessellate a path: {e}"),
+            Self::Encode(e) => write!(f, "failed to encode the image: {e}"),
+            Self::Decode(e) => write!(f, "failed to decode the image: {e}"),
+            Self::Font(e) => write!(f, "failed to parse the font: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::RequestDevice(e)
+            | Self::Tessellation(e)
+            | Self::Encode(e)
+            | Self::Decode(e)
+            | Self::Font(e) => Some(&**e),
+            Self::InvalidImageInfo(_)
+            | Self::UnsupportedColorType(_)
+            | Self::NoAdapter
+            | Self::DeviceLost => None,
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use crate::geom::point::Point;
+use crate::geom::rect::Rect;
+use std::ops::Mul;
+
+/// A 3x3 transformation matrix mapping `(x, y, 1)` column vectors.
+///
+/// ```text
+/// | sx kx tx |
+/// | ky sy ty |
+/// | p0 p1 p2 |
+/// ```
+///
+/// The last row is `0 0 1` unless the matrix has perspective.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Matrix {
+    /// horizontal scale
+    pub sx: f32,
+    /// horizontal skew
+    pub kx: f32,
+    /// horizontal translation
+    pub tx: f32,
+    /// vertical skew
+    pub ky: f32,
+    /// vertical scale
+    pub sy: f32,
+    /// vertical translation
+    pub ty: f32,
+    /// input x perspective
+    pub p0: f32,
+    /// input y perspective
+    pub p1: f32,
+    /// perspective scale
+    pub p2: f32,
+}
+
+impl Matrix {
+    pub const IDENTITY: Self = Self::from_row(1., 0., 0., 0., 1., 0.);
+
+    /// An affine matrix, parameters are given row by row.
+    #[inline]
+    pub const fn from_row(sx: f32, kx: f32, tx: f32, ky: f32, sy: f32, ty: f32) -> Self {
+        Self::from_row_persp(sx, kx, tx, ky, sy, ty, 0., 0., 1.)
+    }
+
+    /// A matrix with all nine values, given row by row.
+    #[allow(clippy::too_many_arguments)]
+    #[inline]
+    pub const fn from_row_persp(
+        sx: f32,
+        kx: f32,
+        tx: f32,
+        ky: f32,
+        sy: f32,
+        ty: f32,
+        p0: f32,
+        p1: f32,
+        p2: f32,
+    ) -> Self {
+        Self {
+            sx,
+            kx,
+            tx,
+            ky,
+            sy,
+            ty,
+            p0,
+            p1,
+            p2,
+        }
+    }
+
+    #[inline]
+    pub const fn translate(dx: f32, dy: f32) -> Self {
+        Self::from_row(1., 0., dx, 0., 1., dy)
+    }
+
+    #[inline]
+    pub const fn scale(sx: f32, sy: f32) -> Self {
+        Self::from_row(sx, 0., 0., 0., sy, 0.)
+    }
+
+    /// Rotate clockwise (with y pointing down) around the origin.
+    pub fn rotate(degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Self::from_row(cos, -sin, 0., sin, cos, 0.)
+    }
+
+    /// Rotate around `pivot`.
+    pub fn rotate_around(degrees: f32, pivot: Point) -> Self {
+        Self::translate(pivot.x, pivot.y)
+            .concat(&Self::rotate(degrees))
+            .concat(&Self::translate(-pivot.x, -pivot.y))
+    }
+
+    /// Scale and translate `src` onto `dst`, `None` if `src` is empty.
+    pub fn rect_to_rect(src: &Rect, dst: &Rect) -> Option<Self> {
+        if src.is_empty() {
+            return None;
+        }
+
+        let sx = dst.width() / src.width();
+        let sy = dst.height() / src.height();
+
+        Some(Self::from_row(
+            sx,
+            0.,
+            dst.l - src.l * sx,
+            0.,
+            sy,
+            dst.t - src.t * sy,
+        ))
+    }
+
+    /// `kx` shifts x by `kx * y`, `ky` shifts y by `ky * x`.
+    #[inline]
+    pub const fn skew(kx: f32, ky: f32) -> Self {
+        Self::from_row(1., kx, 0., ky, 1., 0.)
+    }
+
+    #[inline]
+    pub fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+
+    #[inline]
+    pub fn has_perspective(&self) -> bool {
+        self.p0 != 0. || self.p1 != 0. || self.p2 != 1.
+    }
+
+    /// `self * other`, the result maps points through `other` first.
+    pub fn concat(&self, other: &Self) -> Self {
+        let a = self;
+        let b = other;
+
+        Self {
+            sx: a.sx * b.sx + a.kx * b.ky + a.tx * b.p0,
+            kx: a.sx * b.kx + a.kx * b.sy + a.tx * b.p1,
+            tx: a.sx * b.tx + a.kx * b.ty + a.tx * b.p2,
+            ky: a.ky * b.sx + a.sy * b.ky + a.ty * b.p0,
+            sy: a.ky * b.kx + a.sy * b.sy + a.ty * b.p1,
+            ty: a.ky * b.tx + a.sy * b.ty + a.ty * b.p2,
+            p0: a.p0 * b.sx + a.p1 * b.ky + a.p2 * b.p0,
+            p1: a.p0 * b.kx + a.p1 * b.sy + a.p2 * b.p1,
+            p2: a.p0 * b.tx + a.p1 * b.ty + a.p2 * b.p2,
+        }
+    }
+
+    /// `self * other`
+    #[inline]
+    pub fn pre_concat(&mut self, other: &Self) {
+        *self = self.concat(other);
+    }
+
+    /// `other * self`
+    #[inline]
+    pub fn post_concat(&mut self, other: &Self) {
+        *self = other.concat(self);
+    }
+
+    #[inline]
+    pub fn determinant(&self) -> f32 {
+        self.sx * (self.sy * self.p2 - self.ty * self.p1)
+            - self.kx * (self.ky * self.p2 - self.ty * self.p0)
+            + self.tx * (self.ky * self.p1 - self.sy * self.p0)
+    }
+
+    /// `None` if the matrix is singular.
+    pub fn invert(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det == 0. || !det.is_finite() {
+            return None;
+        }
+
+        let inv = 1. / det;
+        let m = self;
+
+        let inverse = Self {
+            sx: (m.sy * m.p2 - m.ty * m.p1) * inv,
+            kx: (m.tx * m.p1 - m.kx * m.p2) * inv,
+            tx: (m.kx * m.ty - m.tx * m.sy) * inv,
+            ky: (m.ty * m.p0 - m.ky * m.p2) * inv,
+            sy: (m.sx * m.p2 - m.tx * m.p0) * inv,
+            ty: (m.tx * m.ky - m.sx * m.ty) * inv,
+            p0: (m.ky * m.p1 - m.sy * m.p0) * inv,
+            p1: (m.kx * m.p0 - m.sx * m.p1) * inv,
+            p2: (m.sx * m.sy - m.kx * m.ky) * inv,
+        };
+
+        if self.has_perspective() {
+            Some(inverse)
+        } else {
+            // keep affine matrices exactly affine
+            Some(Self {
+                p0: 0.,
+                p1: 0.,
+                p2: 1.,
+                ..inverse
+            })
+        }
+    }
+
+    pub fn map_point(&self, Point { x, y }: Point) -> Point {
+        let px = self.sx * x + self.kx * y + self.tx;
+        let py = self.ky * x + self.sy * y + self.ty;
+
+        if self.has_perspective() {
+            let w = self.p0 * x + self.p1 * y + self.p2;
+            let w = if w != 0. { 1. / w } else { 0. };
+            Point::new(px * w, py * w)
+        } else {
+            Point::new(px, py)
+        }
+    }
+
+    pub fn map_points(&self, points: &mut [Point]) {
+        for pt in points {
+            *pt = self.map_point(*pt);
+        }
+    }
+
+    /// The bounds of the four mapped corners.
+    pub fn map_rect(&self, rect: &Rect) -> Rect {
+        let corners = [
+            Point::new(rect.l, rect.t),
+            Point::new(rect.r, rect.t),
+            Point::new(rect.r, rect.b),
+            Point::new(rect.l, rect.b),
+        ]
+        .map(|pt| self.map_point(pt));
+
+        Rect::from_points(&corners)
+    }
+
+    /// The largest factor a unit length can be stretched by, ignoring perspective.
+    pub fn max_scale(&self) -> f32 {
+        // largest singular value of the upper left 2x2
+        let a = self.sx * self.sx + self.ky * self.ky;
+        let b = self.sx * self.kx + self.ky * self.sy;
+        let c = self.kx * self.kx + self.sy * self.sy;
+
+        let mean = (a + c) * 0.5;
+        let dev = (((a - c) * 0.5).powi(2) + b * b).sqrt();
+
+        (mean + dev).sqrt()
+    }
+}
+
+impl Default for Matrix {
+    #[inline]
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul for Matrix {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.concat(&rhs)
+    }
+}
@@ -1,5 +1,7 @@
+pub mod matrix;
 pub mod point;
 pub mod rect;
+pub mod rrect;
 pub mod vector;
 
 #[inline]
@@ -1,7 +1,7 @@
 use crate::geom::vector::Vector;
-use std::ops::Add;
+use std::ops::{Add, Sub};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub struct Point {
     pub x: f32,
     pub y: f32,
@@ -12,6 +12,12 @@ impl Point {
     pub const fn new(x: f32, y: f32) -> Self {
         Self { x, y }
     }
+
+    /// Linear interpolation between `self` and `to`, `t` in `0..=1`.
+    #[inline]
+    pub fn lerp(self, to: Self, t: f32) -> Self {
+        Self::new(self.x + (to.x - self.x) * t, self.y + (to.y - self.y) * t)
+    }
 }
 
 impl Add<Vector<f32>> for Point {
@@ -23,6 +29,24 @@ impl Add<Vector<f32>> for Point {
     }
 }
 
+impl Sub<Vector<f32>> for Point {
+    type Output = Self;
+
+    #[inline]
+    fn sub(self, rhs: Vector<f32>) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Vector<f32>;
+
+    #[inline]
+    fn sub(self, rhs: Self) -> Self::Output {
+        Vector::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
 impl From<(f32, f32)> for Point {
     #[inline]
     fn from((x, y): (f32, f32)) -> Self {
@@ -1,4 +1,7 @@
+use crate::geom::point::Point;
+
 /// A Rectangle
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub struct Rect {
     /// left
     pub l: f32,
@@ -15,4 +18,55 @@ impl Rect {
     pub fn from_ltrb(l: f32, t: f32, r: f32, b: f32) -> Self {
         Self { l, t, r, b }
     }
+
+    #[inline]
+    pub fn from_xywh(x: f32, y: f32, w: f32, h: f32) -> Self {
+        Self::from_ltrb(x, y, x + w, y + h)
+    }
+
+    /// The smallest rectangle containing every point, empty if there is none.
+    pub fn from_points(points: &[Point]) -> Self {
+        let Some(first) = points.first() else {
+            return Self::default();
+        };
+
+        points.iter().fold(
+            Self::from_ltrb(first.x, first.y, first.x, first.y),
+            |rect, pt| {
+                Self::from_ltrb(
+                    rect.l.min(pt.x),
+                    rect.t.min(pt.y),
+                    rect.r.max(pt.x),
+                    rect.b.max(pt.y),
+                )
+            },
+        )
+    }
+
+    #[inline]
+    pub fn width(&self) -> f32 {
+        self.r - self.l
+    }
+
+    #[inline]
+    pub fn height(&self) -> f32 {
+        self.b - self.t
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        !(self.l < self.r && self.t < self.b)
+    }
+
+    /// The area inside both rectangles, `None` if it is empty.
+    pub fn intersect(&self, other: &Self) -> Option<Self> {
+        let rect = Self::from_ltrb(
+            self.l.max(other.l),
+            self.t.max(other.t),
+            self.r.min(other.r),
+            self.b.min(other.b),
+        );
+
+        (!rect.is_empty()).then_some(rect)
+    }
 }
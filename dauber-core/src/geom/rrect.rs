@@ -0,0 +1,43 @@
+use crate::geom::rect::Rect;
+use crate::geom::vector::Vector;
+
+/// A rectangle with an elliptical arc at every corner.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct RRect {
+    pub rect: Rect,
+    /// x and y radii of the top left, top right, bottom right and bottom left corners.
+    pub radii: [Vector<f32>; 4],
+}
+
+impl RRect {
+    /// All corners share the same radii.
+    #[inline]
+    pub fn from_rect_xy(rect: Rect, rx: f32, ry: f32) -> Self {
+        Self::from_rect_radii(rect, [Vector::new(rx, ry); 4])
+    }
+
+    /// Radii are scaled down uniformly if adjacent corners would overlap.
+    pub fn from_rect_radii(rect: Rect, radii: [Vector<f32>; 4]) -> Self {
+        let mut radii = radii.map(|r| Vector::new(r.x.max(0.), r.y.max(0.)));
+
+        let (w, h) = (rect.width().abs(), rect.height().abs());
+        let [tl, tr, br, bl] = radii;
+        let scale = [
+            w / (tl.x + tr.x),
+            w / (bl.x + br.x),
+            h / (tl.y + bl.y),
+            h / (tr.y + br.y),
+        ]
+        .into_iter()
+        .filter(|s| s.is_finite())
+        .fold(1f32, f32::min);
+
+        if scale < 1. {
+            for r in &mut radii {
+                *r = *r * scale;
+            }
+        }
+
+        Self { rect, radii }
+    }
+}
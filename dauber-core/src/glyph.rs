@@ -0,0 +1,80 @@
+//! Glyphs positioned for drawing, independent of where their outlines come from.
+
+use crate::geom::point::Point;
+use crate::path::Path;
+use std::fmt;
+
+/// Index of a glyph within a typeface.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GlyphId(pub u16);
+
+impl GlyphId {
+    /// Drawn for characters the typeface does not cover.
+    pub const NOTDEF: Self = Self(0);
+}
+
+/// Outlines glyphs of a typeface at a size, implemented by fonts.
+///
+/// Coordinates follow the canvas: y points down and glyphs sit on the baseline at y = 0.
+pub trait GlyphSource: fmt::Debug + Send + Sync {
+    /// Identifies the typeface the glyphs come from.
+    fn typeface_id(&self) -> u32;
+
+    /// Pixels per em.
+    fn size(&self) -> f32;
+
+    /// Append the outline of `glyph` with its origin at `origin` to `path`,
+    /// `false` if the glyph has none.
+    fn append_outline(&self, glyph: GlyphId, origin: Point, path: &mut Path) -> bool;
+}
+
+/// Glyphs of one font with their origins, relative to the origin of the run.
+#[derive(Copy, Clone, Debug)]
+pub struct GlyphRun<'a> {
+    font: &'a dyn GlyphSource,
+    glyphs: &'a [GlyphId],
+    positions: &'a [Point],
+}
+
+impl<'a> GlyphRun<'a> {
+    /// Panics if `glyphs` and `positions` differ in length.
+    pub fn new(font: &'a dyn GlyphSource, glyphs: &'a [GlyphId], positions: &'a [Point]) -> Self {
+        assert_eq!(
+            glyphs.len(),
+            positions.len(),
+            "every glyph needs a position"
+        );
+
+        Self {
+            font,
+            glyphs,
+            positions,
+        }
+    }
+
+    #[inline]
+    pub fn font(&self) -> &'a dyn GlyphSource {
+        self.font
+    }
+
+    #[inline]
+    pub fn glyphs(&self) -> &'a [GlyphId] {
+        self.glyphs
+    }
+
+    #[inline]
+    pub fn positions(&self) -> &'a [Point] {
+        self.positions
+    }
+
+    /// The outlines of all glyphs with the run placed at `origin`.
+    pub fn to_path(&self, origin: Point) -> Path {
+        let mut path = Path::new();
+        for (&glyph, p) in self.glyphs.iter().zip(self.positions) {
+            self.font
+                .append_outline(glyph, Point::new(origin.x + p.x, origin.y + p.y), &mut path);
+        }
+
+        path
+    }
+}
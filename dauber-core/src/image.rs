@@ -0,0 +1,185 @@
+use crate::error::{Error, Result};
+use crate::image_info::{AlphaType, ColorType, ImageInfo};
+use crate::pixel;
+use crate::pixmap::{Pixmap, PixmapMut};
+use half::f16;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// Immutable pixels, cheap to clone.
+#[derive(Clone, Debug)]
+pub struct Image {
+    info: ImageInfo,
+    /// Rows without padding.
+    pixels: Arc<[u8]>,
+    id: u32,
+    /// Built on first use, shared by every clone.
+    mipmap: Arc<OnceLock<Vec<Image>>>,
+}
+
+impl Image {
+    fn new(info: ImageInfo, pixels: Arc<[u8]>) -> Self {
+        static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+
+        Self {
+            info,
+            pixels,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            mipmap: Arc::default(),
+        }
+    }
+
+    /// Copy the pixels of `pixmap` into a new image.
+    pub fn from_pixmap(pixmap: &Pixmap<'_>) -> Self {
+        let info = *pixmap.info();
+        let row_bytes = info.min_row_bytes();
+
+        let pixels = pixmap
+            .pixels()
+            .chunks(pixmap.row_bytes())
+            .take(info.height as usize)
+            .flat_map(|row| &row[..row_bytes])
+            .copied()
+            .collect();
+
+        Self::new(info, pixels)
+    }
+
+    /// Take `pixels` laid out by `info` without padding between rows.
+    pub fn from_pixels(info: ImageInfo, pixels: Vec<u8>) -> Result<Self> {
+        Pixmap::new(info, &pixels, info.min_row_bytes())?;
+
+        Ok(Self::new(info, pixels.into()))
+    }
+
+    /// Decode a PNG or JPEG file.
+    ///
+    /// The pixels are unpremultiplied sRGB, 16 bit images decode to [`ColorType::RgbaF16`].
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let decoded = image::load_from_memory(data).map_err(|e| Error::Decode(e.into()))?;
+        let (width, height) = (decoded.width(), decoded.height());
+        let alpha_type = if decoded.color().has_alpha() {
+            AlphaType::Unpremul
+        } else {
+            AlphaType::Opaque
+        };
+
+        // more than a byte per channel
+        let wide = decoded.color().bytes_per_pixel() > decoded.color().channel_count();
+        let (color_type, pixels) = if wide {
+            let pixels = decoded
+                .to_rgba32f()
+                .into_raw()
+                .into_iter()
+                .flat_map(|v| f16::from_f32(v).to_le_bytes())
+                .collect();
+            (ColorType::RgbaF16, pixels)
+        } else {
+            (ColorType::Rgba8888, decoded.to_rgba8().into_raw())
+        };
+
+        let info = ImageInfo::new_wh(width, height)
+            .with_color_type(color_type)
+            .with_alpha_type(alpha_type);
+        Self::from_pixels(info, pixels)
+    }
+
+    #[inline]
+    pub fn info(&self) -> &ImageInfo {
+        &self.info
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.info.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.info.height
+    }
+
+    /// Identifies the pixels, clones share it.
+    #[inline]
+    pub fn unique_id(&self) -> u32 {
+        self.id
+    }
+
+    /// Rows without padding.
+    #[inline]
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    #[inline]
+    pub fn pixmap(&self) -> Pixmap<'_> {
+        Pixmap::new(self.info, &self.pixels, self.info.min_row_bytes())
+            .expect("images always hold all of their pixels")
+    }
+
+    /// See [`Pixmap::read_pixels`].
+    #[inline]
+    pub fn read_pixels(&self, dst: &mut PixmapMut<'_>, src_x: i32, src_y: i32) -> bool {
+        self.pixmap().read_pixels(dst, src_x, src_y)
+    }
+
+    /// The levels below the full image, each half the size of the one before
+    /// down to a single pixel, averaging 2x2 pixels.
+    pub fn mipmap(&self) -> &[Image] {
+        self.mipmap.get_or_init(|| {
+            let mut levels: Vec<Image> = vec![];
+            while let Some(level) = levels.last().unwrap_or(self).downsample() {
+                levels.push(level);
+            }
+            levels
+        })
+    }
+
+    /// Half the size, `None` once the image is a single pixel.
+    fn downsample(&self) -> Option<Self> {
+        let ImageInfo {
+            width,
+            height,
+            color_type,
+            alpha_type,
+            ..
+        } = self.info;
+        if width == 1 && height == 1 {
+            return None;
+        }
+
+        let info = ImageInfo {
+            width: (width / 2).max(1),
+            height: (height / 2).max(1),
+            ..self.info
+        };
+        let bpp = info.bytes_per_pixel();
+        let load = |x: u32, y: u32| {
+            let at = (y.min(height - 1) * width + x.min(width - 1)) as usize * bpp;
+            pixel::load(color_type, alpha_type, &self.pixels[at..])
+        };
+
+        let mut pixels = vec![0; info.min_row_bytes() * info.height as usize];
+        for (i, px) in pixels.chunks_exact_mut(bpp).enumerate() {
+            let (x, y) = (i as u32 % info.width * 2, i as u32 / info.width * 2);
+            let quad = [
+                load(x, y),
+                load(x + 1, y),
+                load(x, y + 1),
+                load(x + 1, y + 1),
+            ];
+            let average = [0, 1, 2, 3].map(|c| quad.iter().map(|p| p[c]).sum::<f32>() * 0.25);
+            pixel::store(color_type, alpha_type, average, px);
+        }
+
+        Some(Self::new(info, pixels.into()))
+    }
+}
+
+/// Images are equal when they share their pixels.
+impl PartialEq for Image {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
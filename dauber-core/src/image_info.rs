@@ -1,16 +1,212 @@
-#[derive(Copy, Clone, Debug)]
+use crate::color::Color;
+
+/// Size and pixel format of an image.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct ImageInfo {
     pub width: u32,
     pub height: u32,
-    // color type?
+    pub color_type: ColorType,
+    pub alpha_type: AlphaType,
+    pub color_space: ColorSpace,
 }
 
 impl ImageInfo {
+    /// Premultiplied RGBA8 in sRGB.
     #[inline]
     pub const fn new_wh(w: u32, h: u32) -> Self {
+        Self::new(
+            w,
+            h,
+            ColorType::Rgba8888,
+            AlphaType::Premul,
+            ColorSpace::Srgb,
+        )
+    }
+
+    #[inline]
+    pub const fn new(
+        width: u32,
+        height: u32,
+        color_type: ColorType,
+        alpha_type: AlphaType,
+        color_space: ColorSpace,
+    ) -> Self {
         Self {
-            width: w,
-            height: h,
+            width,
+            height,
+            color_type,
+            alpha_type,
+            color_space,
         }
     }
+
+    #[inline]
+    pub const fn with_color_type(mut self, color_type: ColorType) -> Self {
+        self.color_type = color_type;
+        self
+    }
+
+    #[inline]
+    pub const fn with_alpha_type(mut self, alpha_type: AlphaType) -> Self {
+        self.alpha_type = alpha_type;
+        self
+    }
+
+    #[inline]
+    pub const fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    #[inline]
+    pub const fn bytes_per_pixel(&self) -> usize {
+        self.color_type.bytes_per_pixel()
+    }
+
+    /// Bytes of a row without padding.
+    #[inline]
+    pub const fn min_row_bytes(&self) -> usize {
+        self.width as usize * self.bytes_per_pixel()
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+}
+
+/// How the channels of a pixel are laid out in memory.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorType {
+    /// 8 bits each for red, green, blue and alpha, in that order.
+    #[default]
+    Rgba8888,
+    /// 8 bits each for blue, green, red and alpha, in that order.
+    Bgra8888,
+    /// A little endian 16 bit word of 5 bits red, 6 bits green and 5 bits blue, from high to low.
+    Rgb565,
+    /// 8 bits of luminance.
+    Gray8,
+    /// 8 bits of alpha, without color.
+    Alpha8,
+    /// A half float each for red, green, blue and alpha.
+    RgbaF16,
+    /// A float each for red, green, blue and alpha.
+    RgbaF32,
+}
+
+impl ColorType {
+    #[inline]
+    pub const fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Gray8 | Self::Alpha8 => 1,
+            Self::Rgb565 => 2,
+            Self::Rgba8888 | Self::Bgra8888 => 4,
+            Self::RgbaF16 => 8,
+            Self::RgbaF32 => 16,
+        }
+    }
+
+    /// Whether pixels of this type cannot be translucent.
+    #[inline]
+    pub const fn is_always_opaque(self) -> bool {
+        matches!(self, Self::Rgb565 | Self::Gray8)
+    }
+}
+
+/// How the color channels of a pixel relate to its alpha.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AlphaType {
+    /// The color channels are multiplied by alpha.
+    #[default]
+    Premul,
+    /// The color channels are independent of alpha.
+    Unpremul,
+    /// Every pixel is fully opaque, stored like `Premul`.
+    Opaque,
+}
+
+/// What the channel values of a pixel mean.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// sRGB primaries with the sRGB transfer function.
+    #[default]
+    Srgb,
+    /// sRGB primaries without a transfer function.
+    LinearSrgb,
+    /// Display P3 primaries with the sRGB transfer function.
+    DisplayP3,
+}
+
+impl ColorSpace {
+    /// Convert the unpremultiplied `color` from this color space to `to`, keeping alpha.
+    pub fn convert(self, color: Color, to: ColorSpace) -> Color {
+        if self == to {
+            return color;
+        }
+
+        let mut rgb = [color.r, color.g, color.b];
+
+        if self != Self::LinearSrgb {
+            rgb = rgb.map(srgb_to_linear);
+        }
+        match (self, to) {
+            (Self::DisplayP3, Self::Srgb | Self::LinearSrgb) => rgb = mul(&P3_TO_SRGB, rgb),
+            (Self::Srgb | Self::LinearSrgb, Self::DisplayP3) => rgb = mul(&SRGB_TO_P3, rgb),
+            _ => {}
+        }
+        if to != Self::LinearSrgb {
+            rgb = rgb.map(linear_to_srgb);
+        }
+
+        let [r, g, b] = rgb;
+        Color {
+            r,
+            g,
+            b,
+            a: color.a,
+        }
+    }
+}
+
+/// Linear sRGB to linear Display P3.
+const SRGB_TO_P3: [[f32; 3]; 3] = [
+    [0.822_462_1, 0.177_538, 0.0],
+    [0.033_194_1, 0.966_805_8, 0.0],
+    [0.017_082_7, 0.072_397_4, 0.910_519_9],
+];
+
+/// Linear Display P3 to linear sRGB.
+const P3_TO_SRGB: [[f32; 3]; 3] = [
+    [1.224_940_1, -0.224_940_4, 0.0],
+    [-0.042_056_9, 1.042_057_1, 0.0],
+    [-0.019_637_6, -0.078_636_1, 1.098_273_5],
+];
+
+#[inline]
+fn mul(m: &[[f32; 3]; 3], [r, g, b]: [f32; 3]) -> [f32; 3] {
+    m.map(|row| row[0] * r + row[1] * g + row[2] * b)
+}
+
+/// The sRGB transfer function, mirrored for negative values.
+#[inline]
+fn srgb_to_linear(v: f32) -> f32 {
+    let a = v.abs();
+    let linear = if a <= 0.040_45 {
+        a / 12.92
+    } else {
+        ((a + 0.055) / 1.055).powf(2.4)
+    };
+    linear.copysign(v)
+}
+
+#[inline]
+fn linear_to_srgb(v: f32) -> f32 {
+    let a = v.abs();
+    let encoded = if a <= 0.003_130_8 {
+        a * 12.92
+    } else {
+        1.055 * a.powf(1.0 / 2.4) - 0.055
+    };
+    encoded.copysign(v)
 }
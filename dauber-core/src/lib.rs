@@ -1,6 +1,18 @@
+pub mod blend_mode;
+pub mod clip;
 pub mod color;
 pub mod device;
+pub mod error;
 pub mod geom;
+pub mod glyph;
+pub mod image;
 pub mod image_info;
 pub mod paint;
 pub mod path;
+pub mod path_effect;
+pub mod pixel;
+pub mod pixmap;
+pub mod sampling;
+pub mod shader;
+
+pub use error::{Error, Result};
@@ -1,10 +1,21 @@
+use crate::blend_mode::BlendMode;
 use crate::color::Color;
+use crate::path_effect::PathEffect;
+use crate::shader::Shader;
 
 #[derive(Clone, Debug)]
 pub struct Paint {
     pub color: Color,
     pub style: PaintStyle,
     pub anti_alias: bool,
+    /// Colors the paint instead of `color` when set, modulated by the alpha of `color`.
+    pub shader: Option<Shader>,
+    pub blend_mode: BlendMode,
+    pub stroke_cap: StrokeCap,
+    pub stroke_join: StrokeJoin,
+    /// Miter joins longer than `miter_limit` times the stroke width are beveled.
+    pub miter_limit: f32,
+    pub path_effect: Option<PathEffect>,
 }
 
 impl Paint {
@@ -14,9 +25,17 @@ impl Paint {
             color: Color::BLACK,
             style: PaintStyle::Fill,
             anti_alias: false,
+            shader: None,
+            blend_mode: BlendMode::SrcOver,
+            stroke_cap: StrokeCap::Butt,
+            stroke_join: StrokeJoin::Miter,
+            miter_limit: Self::DEFAULT_MITER_LIMIT,
+            path_effect: None,
         }
     }
 
+    pub const DEFAULT_MITER_LIMIT: f32 = 4.0;
+
     #[inline]
     pub fn set_color<C: Into<Color>>(&mut self, color: C) {
         self.color = color.into();
@@ -32,6 +51,36 @@ impl Paint {
         self.anti_alias = anti_alias;
     }
 
+    #[inline]
+    pub fn set_shader(&mut self, shader: Option<Shader>) {
+        self.shader = shader;
+    }
+
+    #[inline]
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    #[inline]
+    pub fn set_stroke_cap(&mut self, cap: StrokeCap) {
+        self.stroke_cap = cap;
+    }
+
+    #[inline]
+    pub fn set_stroke_join(&mut self, join: StrokeJoin) {
+        self.stroke_join = join;
+    }
+
+    #[inline]
+    pub fn set_miter_limit(&mut self, limit: f32) {
+        self.miter_limit = limit;
+    }
+
+    #[inline]
+    pub fn set_path_effect(&mut self, path_effect: Option<PathEffect>) {
+        self.path_effect = path_effect;
+    }
+
     pub fn style(&self) -> PaintStyle {
         self.style
     }
@@ -39,6 +88,43 @@ impl Paint {
     pub fn color(&self) -> Color {
         self.color
     }
+
+    pub fn shader(&self) -> Option<&Shader> {
+        self.shader.as_ref()
+    }
+
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    pub fn stroke_cap(&self) -> StrokeCap {
+        self.stroke_cap
+    }
+
+    pub fn stroke_join(&self) -> StrokeJoin {
+        self.stroke_join
+    }
+
+    pub fn miter_limit(&self) -> f32 {
+        self.miter_limit
+    }
+
+    pub fn path_effect(&self) -> Option<&PathEffect> {
+        self.path_effect.as_ref()
+    }
+
+    /// The outline the paint strokes with, if its style strokes at all.
+    pub fn stroke_style(&self) -> Option<StrokeStyle> {
+        match self.style {
+            PaintStyle::Fill => None,
+            PaintStyle::Stroke(width) | PaintStyle::FillAndStroke(width) => Some(StrokeStyle {
+                width,
+                cap: self.stroke_cap,
+                join: self.stroke_join,
+                miter_limit: self.miter_limit,
+            }),
+        }
+    }
 }
 
 impl Default for Paint {
@@ -54,3 +140,56 @@ pub enum PaintStyle {
     Stroke(f32),
     FillAndStroke(f32),
 }
+
+/// Everything that decides the outline of a stroke.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StrokeStyle {
+    pub width: f32,
+    pub cap: StrokeCap,
+    pub join: StrokeJoin,
+    /// Miter joins longer than `miter_limit` times the width are beveled.
+    pub miter_limit: f32,
+}
+
+impl StrokeStyle {
+    #[inline]
+    pub const fn new(width: f32) -> Self {
+        Self {
+            width,
+            cap: StrokeCap::Butt,
+            join: StrokeJoin::Miter,
+            miter_limit: Paint::DEFAULT_MITER_LIMIT,
+        }
+    }
+}
+
+impl Default for StrokeStyle {
+    #[inline]
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+/// How the ends of open contours are drawn.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum StrokeCap {
+    /// End exactly at the end point.
+    #[default]
+    Butt,
+    /// A half circle centered on the end point.
+    Round,
+    /// A half square centered on the end point.
+    Square,
+}
+
+/// How the corners between segments are drawn.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum StrokeJoin {
+    /// Extend the outer edges until they meet, beveling past the miter limit.
+    #[default]
+    Miter,
+    /// A circular arc around the corner.
+    Round,
+    /// Connect the outer edges with a straight line.
+    Bevel,
+}
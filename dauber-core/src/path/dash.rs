@@ -0,0 +1,424 @@
+use crate::geom::point::Point;
+use crate::geom::vector::Vector;
+use crate::path::{Path, PathSegment};
+
+/// Equal parameter steps each curve is measured in.
+const PIECES: usize = 16;
+
+/// Upper bound of dashes per path, longer patterns are left undashed.
+const MAX_DASH_COUNT: f32 = 1_000_000.0;
+
+/// Gauss-Legendre abscissae on [0, 1] and their weights.
+const GAUSS: [(f32, f32); 5] = [
+    (0.046_910_077, 0.118_463_44),
+    (0.230_765_34, 0.239_314_34),
+    (0.5, 0.284_444_45),
+    (0.769_234_64, 0.239_314_34),
+    (0.953_089_9, 0.118_463_44),
+];
+
+pub(super) fn dash(path: &Path, intervals: &[f32], phase: f32) -> Path {
+    let total: f32 = intervals.iter().sum();
+    let valid = intervals.len() >= 2
+        && intervals.len().is_multiple_of(2)
+        && intervals.iter().all(|&i| i >= 0.0 && i.is_finite())
+        && total > 0.0
+        && phase.is_finite();
+
+    if !valid {
+        return path.clone();
+    }
+
+    let mut contours = vec![];
+    let mut segments = vec![];
+    let mut start = None;
+
+    for seg in path.iter() {
+        let segment = match seg {
+            PathSegment::Move { to } => {
+                contours.push((std::mem::take(&mut segments), false));
+                start = Some(to);
+                continue;
+            }
+            PathSegment::Line { from, to } => Segment::Line { from, to },
+            PathSegment::Quadratic { from, ctrl, to } => Segment::Quad { from, ctrl, to },
+            PathSegment::Cubic {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            } => Segment::Cubic {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            },
+            PathSegment::Close => {
+                let last = segments.last().map(|m: &Measured| m.seg.end());
+                if let (Some(from), Some(to)) = (last, start.take()) {
+                    segments.push(Measured::new(Segment::Line { from, to }));
+                }
+
+                contours.push((std::mem::take(&mut segments), true));
+                continue;
+            }
+        };
+
+        start.get_or_insert(segment.start());
+        segments.push(Measured::new(segment));
+    }
+
+    contours.push((segments, false));
+
+    // too many dashes to be worth drawing
+    let length: f32 = contours
+        .iter()
+        .flat_map(|(segments, _)| segments)
+        .map(Measured::length)
+        .sum();
+    if length / total > MAX_DASH_COUNT {
+        return path.clone();
+    }
+
+    let mut dasher = Dasher {
+        intervals,
+        start: Pattern::new(intervals, phase.rem_euclid(total)),
+        out: Path::new(),
+        first: Path::new(),
+    };
+    dasher.out.set_fill_type(path.fill_type());
+
+    for (segments, closed) in &contours {
+        dasher.contour(segments, *closed);
+    }
+
+    dasher.out
+}
+
+/// Position inside the dash intervals.
+#[derive(Copy, Clone)]
+struct Pattern {
+    index: usize,
+    /// Length left in the current interval.
+    remaining: f32,
+}
+
+impl Pattern {
+    fn new(intervals: &[f32], mut phase: f32) -> Self {
+        let mut index = 0;
+        // a phase at the end of an interval starts the next one, unless it is empty
+        while index + 1 < intervals.len()
+            && (phase > intervals[index] || (phase == intervals[index] && phase > 0.0))
+        {
+            phase -= intervals[index];
+            index += 1;
+        }
+
+        Self {
+            index,
+            remaining: (intervals[index] - phase).max(0.0),
+        }
+    }
+
+    #[inline]
+    fn is_on(self) -> bool {
+        self.index.is_multiple_of(2)
+    }
+}
+
+struct Dasher<'a> {
+    intervals: &'a [f32],
+    /// Every contour starts at the same position.
+    start: Pattern,
+    out: Path,
+    /// The first dash of a closed contour, added after the others
+    /// so that the last one can continue with it if that reaches the end.
+    first: Path,
+}
+
+impl<'a> Dasher<'a> {
+    fn contour(&mut self, segments: &[Measured], closed: bool) {
+        let Some(first) = segments.first() else {
+            return;
+        };
+
+        let mut pattern = self.start;
+        // whether a dash is in progress
+        let mut drawing = false;
+        // whether the dash in progress is the first one of a closed contour
+        let mut in_first = closed && pattern.is_on() && pattern.remaining > 0.0;
+
+        if pattern.is_on() && pattern.remaining <= 0.0 {
+            self.dot(first.seg.start());
+        }
+
+        for measured in segments {
+            let length = measured.length();
+            let mut pos = 0.0;
+
+            while pos < length {
+                // snap to the end so that rounding never stalls the walk
+                let (end, step) = if pattern.remaining >= length - pos {
+                    (length, length - pos)
+                } else {
+                    (pos + pattern.remaining, pattern.remaining)
+                };
+
+                if pattern.is_on() && step > 0.0 {
+                    let target = if in_first {
+                        &mut self.first
+                    } else {
+                        &mut self.out
+                    };
+
+                    let piece = measured.segment(pos, end);
+                    if !drawing {
+                        target.move_to(piece.start());
+                        drawing = true;
+                    }
+                    piece.append_to(target);
+                }
+
+                pos = end;
+                pattern.remaining -= step;
+
+                // move on, dotting every zero length dash on the way
+                while pattern.remaining <= 0.0 {
+                    if pattern.is_on() {
+                        drawing = false;
+                        in_first = false;
+                    }
+
+                    pattern.index = (pattern.index + 1) % self.intervals.len();
+                    pattern.remaining = self.intervals[pattern.index];
+
+                    if pattern.is_on() && pattern.remaining <= 0.0 {
+                        self.dot(measured.point_at(pos));
+                    }
+                }
+            }
+        }
+
+        if !self.first.is_empty() {
+            // the last dash runs until the end, continue it with the first one
+            let join = drawing && !in_first;
+
+            let first = std::mem::take(&mut self.first);
+            for seg in first.iter() {
+                match seg {
+                    PathSegment::Move { to } if !join => self.out.move_to(to),
+                    PathSegment::Line { to, .. } => self.out.line_to(to),
+                    PathSegment::Quadratic { ctrl, to, .. } => self.out.quad_to(ctrl, to),
+                    PathSegment::Cubic {
+                        ctrl1, ctrl2, to, ..
+                    } => self.out.cubic_to(ctrl1, ctrl2, to),
+                    PathSegment::Move { .. } | PathSegment::Close => {}
+                }
+            }
+        }
+    }
+
+    /// A zero length dash, visible with round or square caps.
+    fn dot(&mut self, at: Point) {
+        self.out.move_to(at);
+        self.out.line_to(at);
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+enum Segment {
+    Line {
+        from: Point,
+        to: Point,
+    },
+    Quad {
+        from: Point,
+        ctrl: Point,
+        to: Point,
+    },
+    Cubic {
+        from: Point,
+        ctrl1: Point,
+        ctrl2: Point,
+        to: Point,
+    },
+}
+
+impl Segment {
+    fn start(&self) -> Point {
+        match *self {
+            Self::Line { from, .. } | Self::Quad { from, .. } | Self::Cubic { from, .. } => from,
+        }
+    }
+
+    fn end(&self) -> Point {
+        match *self {
+            Self::Line { to, .. } | Self::Quad { to, .. } | Self::Cubic { to, .. } => to,
+        }
+    }
+
+    fn eval(&self, t: f32) -> Point {
+        match *self {
+            Self::Line { from, to } => from.lerp(to, t),
+            Self::Quad { from, ctrl, to } => from.lerp(ctrl, t).lerp(ctrl.lerp(to, t), t),
+            Self::Cubic { .. } => self.blossom(t, t, t),
+        }
+    }
+
+    fn derivative(&self, t: f32) -> Vector<f32> {
+        match *self {
+            Self::Line { from, to } => to - from,
+            Self::Quad { from, ctrl, to } => (ctrl.lerp(to, t) - from.lerp(ctrl, t)) * 2.0,
+            Self::Cubic {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            } => {
+                let a = from.lerp(ctrl1, t);
+                let b = ctrl1.lerp(ctrl2, t);
+                let c = ctrl2.lerp(to, t);
+                (b.lerp(c, t) - a.lerp(b, t)) * 3.0
+            }
+        }
+    }
+
+    /// The polar form of the curve, equal to [`eval`](Self::eval) when all arguments are equal.
+    fn blossom(&self, u: f32, v: f32, w: f32) -> Point {
+        match *self {
+            Self::Line { from, to } => from.lerp(to, u),
+            Self::Quad { from, ctrl, to } => from.lerp(ctrl, u).lerp(ctrl.lerp(to, u), v),
+            Self::Cubic {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            } => {
+                let a = from.lerp(ctrl1, u);
+                let b = ctrl1.lerp(ctrl2, u);
+                let c = ctrl2.lerp(to, u);
+                a.lerp(b, v).lerp(b.lerp(c, v), w)
+            }
+        }
+    }
+
+    /// The part of the segment between `t0` and `t1`.
+    fn sub(&self, t0: f32, t1: f32) -> Self {
+        match *self {
+            Self::Line { .. } => Self::Line {
+                from: self.eval(t0),
+                to: self.eval(t1),
+            },
+            Self::Quad { .. } => Self::Quad {
+                from: self.eval(t0),
+                ctrl: self.blossom(t0, t1, 0.0),
+                to: self.eval(t1),
+            },
+            Self::Cubic { .. } => Self::Cubic {
+                from: self.eval(t0),
+                ctrl1: self.blossom(t0, t0, t1),
+                ctrl2: self.blossom(t0, t1, t1),
+                to: self.eval(t1),
+            },
+        }
+    }
+
+    /// Arc length between `t0` and `t1`.
+    fn length_between(&self, t0: f32, t1: f32) -> f32 {
+        let dt = t1 - t0;
+        GAUSS
+            .iter()
+            .map(|&(x, w)| w * self.derivative(t0 + x * dt).length())
+            .sum::<f32>()
+            * dt
+    }
+
+    fn append_to(&self, path: &mut Path) {
+        match *self {
+            Self::Line { to, .. } => path.line_to(to),
+            Self::Quad { ctrl, to, .. } => path.quad_to(ctrl, to),
+            Self::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => path.cubic_to(ctrl1, ctrl2, to),
+        }
+    }
+}
+
+/// A segment with its arc length at evenly spaced parameters.
+struct Measured {
+    seg: Segment,
+    /// Arc length from the start at `t = i / PIECES`, lines only store their full length.
+    lengths: Vec<f32>,
+}
+
+impl Measured {
+    fn new(seg: Segment) -> Self {
+        let lengths = match seg {
+            Segment::Line { from, to } => vec![(to - from).length()],
+            _ => {
+                let mut sum = 0.0;
+                let mut lengths = Vec::with_capacity(PIECES + 1);
+                lengths.push(0.0);
+                for i in 0..PIECES {
+                    let t0 = i as f32 / PIECES as f32;
+                    let t1 = (i + 1) as f32 / PIECES as f32;
+                    sum += seg.length_between(t0, t1);
+                    lengths.push(sum);
+                }
+                lengths
+            }
+        };
+
+        Self { seg, lengths }
+    }
+
+    #[inline]
+    fn length(&self) -> f32 {
+        self.lengths[self.lengths.len() - 1]
+    }
+
+    /// The parameter at arc length `s`.
+    fn t_at(&self, s: f32) -> f32 {
+        let length = self.length();
+        if s <= 0.0 || length <= 0.0 {
+            return 0.0;
+        }
+        if s >= length {
+            return 1.0;
+        }
+
+        if let Segment::Line { .. } = self.seg {
+            return s / length;
+        }
+
+        let i = self.lengths.partition_point(|&l| l <= s).clamp(1, PIECES) - 1;
+        let (l0, l1) = (self.lengths[i], self.lengths[i + 1]);
+        let t0 = i as f32 / PIECES as f32;
+        let t1 = (i + 1) as f32 / PIECES as f32;
+
+        // refine the linear guess with newton's method
+        let mut t = t0 + (s - l0) / (l1 - l0).max(f32::EPSILON) * (t1 - t0);
+        for _ in 0..3 {
+            let speed = self.seg.derivative(t).length();
+            if speed <= f32::EPSILON {
+                break;
+            }
+
+            let err = l0 + self.seg.length_between(t0, t) - s;
+            t = (t - err / speed).clamp(t0, t1);
+        }
+
+        t
+    }
+
+    #[inline]
+    fn point_at(&self, s: f32) -> Point {
+        self.seg.eval(self.t_at(s))
+    }
+
+    /// The part of the segment between the arc lengths `s0` and `s1`.
+    #[inline]
+    fn segment(&self, s0: f32, s1: f32) -> Segment {
+        self.seg.sub(self.t_at(s0), self.t_at(s1))
+    }
+}
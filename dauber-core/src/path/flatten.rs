@@ -0,0 +1,153 @@
+use crate::geom::point::Point;
+use crate::path::{Iter, PathSegment};
+
+/// Upper bound of line segments a single curve is split into.
+const MAX_SUBDIVISIONS: u32 = 256;
+
+/// Iterator approximating every curve of a path with line segments.
+///
+/// Only [`PathSegment::Move`], [`PathSegment::Line`] and [`PathSegment::Close`] are yielded.
+pub struct Flatten<'a> {
+    iter: Iter<'a>,
+    tolerance: f32,
+    curve: Option<Curve>,
+}
+
+impl<'a> Flatten<'a> {
+    pub(crate) fn new(iter: Iter<'a>, tolerance: f32) -> Self {
+        Self {
+            iter,
+            tolerance: tolerance.max(f32::EPSILON),
+            curve: None,
+        }
+    }
+}
+
+impl<'a> Iterator for Flatten<'a> {
+    type Item = PathSegment;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(curve) = &mut self.curve {
+            let from = curve.last;
+            let to = curve.next_point();
+            if curve.is_done() {
+                self.curve = None;
+            }
+            return Some(PathSegment::Line { from, to });
+        }
+
+        let seg = self.iter.next()?;
+        match Curve::from_segment(&seg, self.tolerance) {
+            Some(curve) => {
+                self.curve = Some(curve);
+                self.next()
+            }
+            None => Some(seg),
+        }
+    }
+}
+
+/// Number of chords needed for a curve whose chord error is `err / n²`.
+fn subdivisions(err: f32, tolerance: f32) -> u32 {
+    let n = (err / tolerance).sqrt().ceil();
+    if n.is_finite() {
+        (n as u32).clamp(1, MAX_SUBDIVISIONS)
+    } else {
+        1
+    }
+}
+
+#[derive(Copy, Clone)]
+enum CurveKind {
+    Quad { ctrl: Point },
+    Cubic { ctrl1: Point, ctrl2: Point },
+}
+
+/// Evenly stepped points along a curve.
+pub(crate) struct Curve {
+    kind: CurveKind,
+    from: Point,
+    to: Point,
+    last: Point,
+    step: u32,
+    count: u32,
+}
+
+impl Curve {
+    fn new(kind: CurveKind, from: Point, to: Point, count: u32) -> Self {
+        Self {
+            kind,
+            from,
+            to,
+            last: from,
+            step: 0,
+            count,
+        }
+    }
+
+    /// Subdivide a quadratic or cubic segment so each chord is within `tolerance`.
+    pub(crate) fn from_segment(seg: &PathSegment, tolerance: f32) -> Option<Self> {
+        match *seg {
+            PathSegment::Quadratic { from, ctrl, to } => {
+                let dd = (from - ctrl) - (ctrl - to);
+                Some(Self::new(
+                    CurveKind::Quad { ctrl },
+                    from,
+                    to,
+                    subdivisions(dd.length() / 4.0, tolerance),
+                ))
+            }
+            PathSegment::Cubic {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            } => {
+                let dd1 = (from - ctrl1) - (ctrl1 - ctrl2);
+                let dd2 = (ctrl1 - ctrl2) - (ctrl2 - to);
+                Some(Self::new(
+                    CurveKind::Cubic { ctrl1, ctrl2 },
+                    from,
+                    to,
+                    subdivisions(dd1.length().max(dd2.length()) * 0.75, tolerance),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_done(&self) -> bool {
+        self.step == self.count
+    }
+
+    /// The end of the next chord, the last one ends exactly at the end of the curve.
+    pub(crate) fn next_point(&mut self) -> Point {
+        self.step += 1;
+
+        let to = if self.step == self.count {
+            self.to
+        } else {
+            let t = self.step as f32 / self.count as f32;
+            match self.kind {
+                CurveKind::Quad { ctrl } => {
+                    let a = self.from.lerp(ctrl, t);
+                    let b = ctrl.lerp(self.to, t);
+                    a.lerp(b, t)
+                }
+                CurveKind::Cubic { ctrl1, ctrl2 } => {
+                    let a = self.from.lerp(ctrl1, t);
+                    let b = ctrl1.lerp(ctrl2, t);
+                    let c = ctrl2.lerp(self.to, t);
+                    let ab = a.lerp(b, t);
+                    let bc = b.lerp(c, t);
+                    ab.lerp(bc, t)
+                }
+            }
+        };
+
+        self.last = to;
+
+        to
+    }
+}
@@ -1,7 +1,11 @@
 use crate::geom::point::Point;
+use crate::geom::rrect::RRect;
 use crate::geom::vector;
 use crate::path::Path;
 
+// https://spencermortensen.com/articles/bezier-circle/
+const CONSTANT_FACTOR: f32 = 0.55191505;
+
 /// Tessellate the stroke for an axis-aligned rounded rectangle.
 pub fn add_circle(
     path: &mut Path,
@@ -12,8 +16,6 @@ pub fn add_circle(
     let radius = radius.abs();
     let dir = 1.0; // negative winding: -1
 
-    // https://spencermortensen.com/articles/bezier-circle/
-    const CONSTANT_FACTOR: f32 = 0.55191505;
     let d = radius * CONSTANT_FACTOR;
 
     path.move_to(center + vector(-radius, 0.0));
@@ -40,3 +42,53 @@ pub fn add_circle(
 
     path.close();
 }
+
+/// Add a clockwise rounded rectangle, starting after the top left corner.
+pub fn add_rrect(path: &mut Path, rrect: &RRect) {
+    let RRect { rect, radii } = rrect;
+    let [tl, tr, br, bl] = *radii;
+    let (l, t, r, b) = (rect.l, rect.t, rect.r, rect.b);
+
+    // control point distances of each corner
+    let k = |v: f32| v * (1.0 - CONSTANT_FACTOR);
+
+    path.move_to(Point::new(l + tl.x, t));
+
+    path.line_to(Point::new(r - tr.x, t));
+    if tr.x > 0.0 && tr.y > 0.0 {
+        path.cubic_to(
+            Point::new(r - k(tr.x), t),
+            Point::new(r, t + k(tr.y)),
+            Point::new(r, t + tr.y),
+        );
+    }
+
+    path.line_to(Point::new(r, b - br.y));
+    if br.x > 0.0 && br.y > 0.0 {
+        path.cubic_to(
+            Point::new(r, b - k(br.y)),
+            Point::new(r - k(br.x), b),
+            Point::new(r - br.x, b),
+        );
+    }
+
+    path.line_to(Point::new(l + bl.x, b));
+    if bl.x > 0.0 && bl.y > 0.0 {
+        path.cubic_to(
+            Point::new(l + k(bl.x), b),
+            Point::new(l, b - k(bl.y)),
+            Point::new(l, b - bl.y),
+        );
+    }
+
+    path.line_to(Point::new(l, t + tl.y));
+    if tl.x > 0.0 && tl.y > 0.0 {
+        path.cubic_to(
+            Point::new(l, t + k(tl.y)),
+            Point::new(l + k(tl.x), t),
+            Point::new(l + tl.x, t),
+        );
+    }
+
+    path.close();
+}
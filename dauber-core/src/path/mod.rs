@@ -1,12 +1,22 @@
+mod dash;
+mod flatten;
 mod math;
+mod stroke;
 
+use crate::geom::matrix::Matrix;
 use crate::geom::point::Point;
-use crate::path::math::add_circle;
+use crate::geom::rect::Rect;
+use crate::geom::rrect::RRect;
+use crate::paint::StrokeStyle;
+use crate::path::math::{add_circle, add_rrect};
 
-#[derive(Clone)]
+pub use flatten::Flatten;
+
+#[derive(Clone, Debug)]
 pub struct Path {
     verb: Vec<PathVerb>,
     points: Vec<Point>,
+    fill_type: PathFillType,
 }
 
 impl Path {
@@ -15,9 +25,20 @@ impl Path {
         Self {
             verb: vec![],
             points: vec![],
+            fill_type: PathFillType::Winding,
         }
     }
 
+    #[inline]
+    pub fn set_fill_type(&mut self, fill_type: PathFillType) {
+        self.fill_type = fill_type;
+    }
+
+    #[inline]
+    pub fn fill_type(&self) -> PathFillType {
+        self.fill_type
+    }
+
     pub fn move_to(&mut self, at: Point) {
         self.verb.push(PathVerb::Move);
         self.points.push(at);
@@ -47,16 +68,108 @@ impl Path {
         add_circle(self, center, radius);
     }
 
-    pub fn iter(&self) -> Iter {
+    /// Add a clockwise rectangle starting at the top left corner.
+    pub fn add_rect(&mut self, rect: &Rect) {
+        self.move_to(Point::new(rect.l, rect.t));
+        self.line_to(Point::new(rect.r, rect.t));
+        self.line_to(Point::new(rect.r, rect.b));
+        self.line_to(Point::new(rect.l, rect.b));
+        self.close();
+    }
+
+    #[inline]
+    pub fn add_rrect(&mut self, rrect: &RRect) {
+        add_rrect(self, rrect);
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
         Iter {
             verb: self.verb.iter(),
             points: self.points.iter(),
             last: Point::new(0., 0.),
         }
     }
+
+    /// Iterate the path with curves replaced by lines,
+    /// each no further than `tolerance` away from the original curve.
+    #[inline]
+    pub fn flatten(&self, tolerance: f32) -> Flatten<'_> {
+        Flatten::new(self.iter(), tolerance)
+    }
+
+    /// Split every contour into dashes, alternating between drawn and skipped
+    /// `intervals` measured along the contour, starting `phase` into the intervals.
+    ///
+    /// `intervals` needs an even number of non-negative lengths with a positive sum,
+    /// the path is returned unchanged otherwise. Zero length dashes are kept as
+    /// degenerate contours, which round or square caps turn into dots.
+    #[inline]
+    pub fn dash(&self, intervals: &[f32], phase: f32) -> Path {
+        dash::dash(self, intervals, phase)
+    }
+
+    /// Outline the stroke described by `style` as a path filled with [`PathFillType::Winding`].
+    ///
+    /// Curves, round caps and round joins are approximated by lines,
+    /// each no further than `tolerance` away from the exact outline.
+    #[inline]
+    pub fn stroke(&self, style: &StrokeStyle, tolerance: f32) -> Path {
+        stroke::stroke(self, style, tolerance)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.verb.is_empty()
+    }
+
+    /// Map every point of the path.
+    ///
+    /// Curves are flattened first if `matrix` has perspective,
+    /// as they would not keep their shape otherwise.
+    pub fn transform(&mut self, matrix: &Matrix) {
+        if matrix.is_identity() {
+            return;
+        }
+
+        if !matrix.has_perspective() {
+            matrix.map_points(&mut self.points);
+            return;
+        }
+
+        let mut path = Self::new();
+        path.fill_type = self.fill_type;
+
+        for seg in self.flatten(PERSPECTIVE_TOLERANCE) {
+            match seg {
+                PathSegment::Move { to } => path.move_to(matrix.map_point(to)),
+                PathSegment::Line { to, .. } => path.line_to(matrix.map_point(to)),
+                PathSegment::Close => path.close(),
+                _ => unreachable!("flattened paths only contain lines"),
+            }
+        }
+
+        *self = path;
+    }
+
+    #[inline]
+    pub fn transformed(&self, matrix: &Matrix) -> Self {
+        let mut path = self.clone();
+        path.transform(matrix);
+        path
+    }
+}
+
+/// Flattening tolerance in local coordinates used before applying perspective.
+const PERSPECTIVE_TOLERANCE: f32 = 0.05;
+
+impl Default for Path {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug)]
 pub enum PathVerb {
     Move,  // 1 point
     Line,  // 2 points
@@ -65,15 +178,44 @@ pub enum PathVerb {
     Close, // 0 points
 }
 
-#[derive(Copy, Clone)]
+/// Decides which areas enclosed by a path are filled.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub enum PathFillType {
+    /// Fill where the number of crossed edges is odd.
     EvenOdd,
+    /// Fill where the winding number is non-zero.
+    #[default]
     Winding,
+    /// Same as [`EvenOdd`](Self::EvenOdd), but fills the outside of the path.
     InverseEvenOdd,
+    /// Same as [`Winding`](Self::Winding), but fills the outside of the path.
     InverseWinding,
 }
 
-#[derive(Clone)]
+impl PathFillType {
+    #[inline]
+    pub const fn is_even_odd(self) -> bool {
+        matches!(self, Self::EvenOdd | Self::InverseEvenOdd)
+    }
+
+    #[inline]
+    pub const fn is_inverse(self) -> bool {
+        matches!(self, Self::InverseEvenOdd | Self::InverseWinding)
+    }
+
+    /// The same rule with the inside and outside swapped.
+    #[inline]
+    pub const fn toggle_inverse(self) -> Self {
+        match self {
+            Self::EvenOdd => Self::InverseEvenOdd,
+            Self::Winding => Self::InverseWinding,
+            Self::InverseEvenOdd => Self::EvenOdd,
+            Self::InverseWinding => Self::Winding,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub enum PathSegment {
     Move {
         to: Point,
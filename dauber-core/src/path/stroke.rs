@@ -0,0 +1,329 @@
+use crate::geom::point::Point;
+use crate::geom::vector;
+use crate::geom::vector::Vector;
+use crate::paint::{StrokeCap, StrokeJoin, StrokeStyle};
+use crate::path::flatten::Curve;
+use crate::path::{Path, PathSegment};
+use std::f32::consts::PI;
+
+/// Points closer than this are merged before stroking.
+const NEARLY_ZERO: f32 = 1.0 / 4096.0;
+
+pub(super) fn stroke(path: &Path, style: &StrokeStyle, tolerance: f32) -> Path {
+    let mut stroker = Stroker {
+        style,
+        half_width: style.width / 2.0,
+        tolerance: tolerance.max(f32::EPSILON),
+        out: Path::new(),
+        side: vec![],
+        dirs: vec![],
+    };
+
+    if stroker.half_width.is_nan() || stroker.half_width <= 0.0 {
+        return stroker.out;
+    }
+
+    let mut contour = Contour::default();
+
+    for seg in path.iter() {
+        match seg {
+            PathSegment::Move { to } => {
+                stroker.contour(&mut contour, false);
+                contour.start(to);
+            }
+            PathSegment::Line { from, to } => {
+                contour.start_at(from);
+                contour.push(to, false);
+            }
+            PathSegment::Quadratic { from, .. } | PathSegment::Cubic { from, .. } => {
+                contour.start_at(from);
+
+                let mut curve =
+                    Curve::from_segment(&seg, stroker.tolerance).expect("segment is a curve");
+                while !curve.is_done() {
+                    let to = curve.next_point();
+                    contour.push(to, !curve.is_done());
+                }
+            }
+            PathSegment::Close => stroker.contour(&mut contour, true),
+        }
+    }
+
+    stroker.contour(&mut contour, false);
+
+    stroker.out
+}
+
+/// The flattened points of a single contour.
+#[derive(Default)]
+struct Contour {
+    points: Vec<Point>,
+    /// Whether a point lies inside a curve, these always get round joins.
+    smooth: Vec<bool>,
+    has_segments: bool,
+}
+
+impl Contour {
+    fn start(&mut self, at: Point) {
+        self.points.clear();
+        self.smooth.clear();
+        self.has_segments = false;
+
+        self.points.push(at);
+        self.smooth.push(false);
+    }
+
+    /// Start a contour at `at` unless one is already started.
+    fn start_at(&mut self, at: Point) {
+        if self.points.is_empty() {
+            self.start(at);
+        }
+    }
+
+    fn push(&mut self, to: Point, smooth: bool) {
+        self.has_segments = true;
+
+        let last = self.points.len() - 1;
+        if nearly_equal(self.points[last], to) {
+            self.smooth[last] &= smooth;
+            return;
+        }
+
+        self.points.push(to);
+        self.smooth.push(smooth);
+    }
+}
+
+struct Stroker<'a> {
+    style: &'a StrokeStyle,
+    half_width: f32,
+    tolerance: f32,
+    out: Path,
+    /// One offset side of the contour being stroked.
+    side: Vec<Point>,
+    /// Unit direction and length of every segment of the contour.
+    dirs: Vec<(Vector<f32>, f32)>,
+}
+
+impl<'a> Stroker<'a> {
+    /// Outline `contour` and clear it.
+    fn contour(&mut self, contour: &mut Contour, closed: bool) {
+        let Contour {
+            points,
+            smooth,
+            has_segments,
+        } = contour;
+
+        if closed && points.len() > 2 && nearly_equal(points[0], points[points.len() - 1]) {
+            points.pop();
+            smooth.pop();
+            smooth[0] = false;
+        }
+
+        match points.len() {
+            0 => {}
+            1 if *has_segments => self.dot(points[0]),
+            1 => {}
+            _ if closed => {
+                // two rings with opposite directions, the area between them is filled
+                self.side(points, smooth, true);
+                self.emit_side();
+
+                points.reverse();
+                smooth.reverse();
+                self.side(points, smooth, true);
+                self.emit_side();
+            }
+            _ => {
+                self.side(points, smooth, false);
+                let (dir, _) = self.dirs[self.dirs.len() - 1];
+                self.cap(points[points.len() - 1], dir);
+
+                // the other side is the same side of the reversed contour
+                points.reverse();
+                smooth.reverse();
+                self.side(points, smooth, false);
+
+                let (dir, _) = self.dirs[self.dirs.len() - 1];
+                self.cap(points[points.len() - 1], dir);
+                self.emit_side();
+            }
+        }
+
+        points.clear();
+        smooth.clear();
+        *has_segments = false;
+    }
+
+    /// Offset the contour to its right hand side, in y-down coordinates, appending to `self.side`.
+    fn side(&mut self, points: &[Point], smooth: &[bool], closed: bool) {
+        let len = points.len();
+        let segments = if closed { len } else { len - 1 };
+
+        self.dirs.clear();
+        self.dirs.extend((0..segments).map(|i| {
+            let v = points[(i + 1) % len] - points[i];
+            let length = v.length();
+            (v * (1.0 / length), length)
+        }));
+
+        if closed {
+            for i in 0..len {
+                let prev = self.dirs[(i + len - 1) % len];
+                self.join(points[i], prev, self.dirs[i], smooth[i]);
+            }
+        } else {
+            let first = points[0] + self.normal(self.dirs[0].0);
+            self.push(first);
+
+            for i in 1..len - 1 {
+                self.join(points[i], self.dirs[i - 1], self.dirs[i], smooth[i]);
+            }
+
+            let last = points[len - 1] + self.normal(self.dirs[segments - 1].0);
+            self.push(last);
+        }
+    }
+
+    fn join(
+        &mut self,
+        pivot: Point,
+        (d0, len0): (Vector<f32>, f32),
+        (d1, len1): (Vector<f32>, f32),
+        smooth: bool,
+    ) {
+        let n0 = self.normal(d0);
+        let n1 = self.normal(d1);
+        let cross = d0.cross(d1);
+        let dot = d0.dot(d1);
+
+        // a turn moving the offset less than the tolerance is as good as straight, which
+        // also keeps rounding noise of short segments from folding the inner side
+        if cross.abs() * self.half_width <= self.tolerance && dot > 0.0 {
+            self.push(pivot + n0);
+            return;
+        }
+
+        // the normals point to the side being built, it is the outer one when turning away from it
+        let outer = cross < 0.0 || (cross <= NEARLY_ZERO && dot < 0.0);
+
+        if outer {
+            self.push(pivot + n0);
+
+            let join = if smooth {
+                StrokeJoin::Round
+            } else {
+                self.style.join
+            };
+
+            match join {
+                StrokeJoin::Miter => {
+                    // 1 / cos(turn / 2) <= limit
+                    let limit = self.style.miter_limit;
+                    if 2.0 <= limit * limit * (1.0 + dot) {
+                        self.push(pivot + (n0 + n1) * (1.0 / (1.0 + dot)));
+                    }
+                }
+                StrokeJoin::Round => {
+                    let angle = cross.atan2(dot).abs();
+                    self.arc(pivot, n0, -angle);
+                }
+                StrokeJoin::Bevel => {}
+            }
+
+            self.push(pivot + n1);
+        } else {
+            // connect where both offset segments cross, if they cross at all
+            let cos_half = ((1.0 + dot) / 2.0).sqrt();
+            let sin_half = ((1.0 - dot) / 2.0).sqrt();
+
+            if cos_half > NEARLY_ZERO && self.half_width * sin_half / cos_half <= len0.min(len1) {
+                self.push(pivot + (n0 + n1) * (1.0 / (1.0 + dot)));
+            } else {
+                // going through the pivot keeps the winding of the overlap positive
+                self.push(pivot + n0);
+                self.push(pivot);
+                self.push(pivot + n1);
+            }
+        }
+    }
+
+    /// Connect the end of one side at `at`, heading to `dir`, with the start of the other.
+    fn cap(&mut self, at: Point, dir: Vector<f32>) {
+        let n = self.normal(dir);
+
+        match self.style.cap {
+            StrokeCap::Butt => {}
+            StrokeCap::Round => self.arc(at, n, -PI),
+            StrokeCap::Square => {
+                let d = dir * self.half_width;
+                self.push(at + n + d);
+                self.push(at - n + d);
+            }
+        }
+    }
+
+    /// A zero length contour, only visible with round or square caps.
+    fn dot(&mut self, at: Point) {
+        let r = self.half_width;
+
+        match self.style.cap {
+            StrokeCap::Butt => {}
+            StrokeCap::Round => {
+                self.push(at + vector(r, 0.0));
+                self.arc(at, vector(r, 0.0), -2.0 * PI);
+            }
+            StrokeCap::Square => {
+                for (x, y) in [(r, r), (r, -r), (-r, -r), (-r, r)] {
+                    self.push(at + vector(x, y));
+                }
+            }
+        }
+
+        self.emit_side();
+    }
+
+    /// Rotate `from` around `center` by `angle` radians, excluding the start point.
+    fn arc(&mut self, center: Point, from: Vector<f32>, angle: f32) {
+        // the largest step whose chord stays within tolerance
+        let step = 2.0 * (1.0 - self.tolerance / self.half_width).max(0.0).acos();
+        let count = (angle.abs() / step).ceil().clamp(1.0, 1024.0) as u32;
+
+        for i in 1..=count {
+            let (sin, cos) = (angle * i as f32 / count as f32).sin_cos();
+            let v = vector(from.x * cos - from.y * sin, from.x * sin + from.y * cos);
+            self.push(center + v);
+        }
+    }
+
+    /// The offset to the right hand side of `dir`.
+    #[inline]
+    fn normal(&self, dir: Vector<f32>) -> Vector<f32> {
+        vector(-dir.y, dir.x) * self.half_width
+    }
+
+    #[inline]
+    fn push(&mut self, pt: Point) {
+        if !self.side.last().is_some_and(|&last| nearly_equal(last, pt)) {
+            self.side.push(pt);
+        }
+    }
+
+    /// Add the collected points as a closed polygon.
+    fn emit_side(&mut self) {
+        if let Some((&first, rest)) = self.side.split_first() {
+            self.out.move_to(first);
+            for &pt in rest {
+                self.out.line_to(pt);
+            }
+            self.out.close();
+        }
+
+        self.side.clear();
+    }
+}
+
+#[inline]
+fn nearly_equal(a: Point, b: Point) -> bool {
+    (a - b).length() <= NEARLY_ZERO
+}
@@ -0,0 +1,24 @@
+use crate::path::Path;
+
+/// Changes the geometry of a path before it is drawn.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathEffect {
+    /// Dash every contour, see [`Path::dash`].
+    Dash { intervals: Vec<f32>, phase: f32 },
+}
+
+impl PathEffect {
+    #[inline]
+    pub fn dash<I: Into<Vec<f32>>>(intervals: I, phase: f32) -> Self {
+        Self::Dash {
+            intervals: intervals.into(),
+            phase,
+        }
+    }
+
+    pub fn apply(&self, path: &Path) -> Path {
+        match self {
+            Self::Dash { intervals, phase } => path.dash(intervals, *phase),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Reading and writing single pixels of any [`ColorType`].
+
+use crate::image_info::{AlphaType, ColorType};
+use half::f16;
+
+/// Read the pixel at the start of `src` as premultiplied RGBA.
+///
+/// Color types without alpha load as opaque, `Alpha8` loads as black.
+pub fn load(color_type: ColorType, alpha_type: AlphaType, src: &[u8]) -> [f32; 4] {
+    let unorm = |v: u8| v as f32 * (1.0 / 255.0);
+
+    let rgba = match color_type {
+        ColorType::Rgba8888 => [0, 1, 2, 3].map(|i| unorm(src[i])),
+        ColorType::Bgra8888 => [2, 1, 0, 3].map(|i| unorm(src[i])),
+        ColorType::Rgb565 => {
+            let v = u16::from_le_bytes([src[0], src[1]]);
+            return [
+                (v >> 11) as f32 * (1.0 / 31.0),
+                ((v >> 5) & 0x3f) as f32 * (1.0 / 63.0),
+                (v & 0x1f) as f32 * (1.0 / 31.0),
+                1.0,
+            ];
+        }
+        ColorType::Gray8 => {
+            let l = unorm(src[0]);
+            return [l, l, l, 1.0];
+        }
+        ColorType::Alpha8 => return [0.0, 0.0, 0.0, unorm(src[0])],
+        ColorType::RgbaF16 => {
+            [0, 1, 2, 3].map(|i| f16::from_le_bytes([src[2 * i], src[2 * i + 1]]).to_f32())
+        }
+        ColorType::RgbaF32 => [0, 1, 2, 3].map(|i| {
+            let at = 4 * i;
+            f32::from_le_bytes([src[at], src[at + 1], src[at + 2], src[at + 3]])
+        }),
+    };
+
+    match alpha_type {
+        AlphaType::Unpremul => premultiply(rgba),
+        AlphaType::Premul | AlphaType::Opaque => rgba,
+    }
+}
+
+/// Write the premultiplied `color` to the start of `dst`.
+///
+/// Color types without alpha drop it as if the color was drawn over black.
+pub fn store(color_type: ColorType, alpha_type: AlphaType, color: [f32; 4], dst: &mut [u8]) {
+    let unorm = |v: f32| (v.clamp(0.0, 1.0) * 255.0 + 0.5) as u8;
+    let rgba = match alpha_type {
+        AlphaType::Unpremul if !color_type.is_always_opaque() => unpremultiply(color),
+        _ => color,
+    };
+
+    match color_type {
+        ColorType::Rgba8888 => {
+            for (d, v) in dst.iter_mut().zip(rgba) {
+                *d = unorm(v);
+            }
+        }
+        ColorType::Bgra8888 => {
+            let [r, g, b, a] = rgba;
+            for (d, v) in dst.iter_mut().zip([b, g, r, a]) {
+                *d = unorm(v);
+            }
+        }
+        ColorType::Rgb565 => {
+            let quantize = |v: f32, max: f32| (v.clamp(0.0, 1.0) * max + 0.5) as u16;
+            let v = quantize(rgba[0], 31.0) << 11
+                | quantize(rgba[1], 63.0) << 5
+                | quantize(rgba[2], 31.0);
+            dst[..2].copy_from_slice(&v.to_le_bytes());
+        }
+        ColorType::Gray8 => dst[0] = unorm(luma(rgba)),
+        ColorType::Alpha8 => dst[0] = unorm(rgba[3]),
+        ColorType::RgbaF16 => {
+            for (d, v) in dst.chunks_exact_mut(2).zip(rgba) {
+                d.copy_from_slice(&f16::from_f32(v).to_le_bytes());
+            }
+        }
+        ColorType::RgbaF32 => {
+            for (d, v) in dst.chunks_exact_mut(4).zip(rgba) {
+                d.copy_from_slice(&v.to_le_bytes());
+            }
+        }
+    }
+}
+
+#[inline]
+pub fn premultiply([r, g, b, a]: [f32; 4]) -> [f32; 4] {
+    [r * a, g * a, b * a, a]
+}
+
+/// Transparent colors stay black.
+#[inline]
+pub fn unpremultiply([r, g, b, a]: [f32; 4]) -> [f32; 4] {
+    if a > 0.0 {
+        let inv = 1.0 / a;
+        [r * inv, g * inv, b * inv, a]
+    } else {
+        [0.0; 4]
+    }
+}
+
+/// Rec. 601 luma, matching how images are commonly converted to grayscale.
+#[inline]
+fn luma([r, g, b, _]: [f32; 4]) -> f32 {
+    0.299 * r + 0.587 * g + 0.114 * b
+}
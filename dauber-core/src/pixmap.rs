@@ -0,0 +1,216 @@
+//! Borrowed views of pixel memory.
+
+use crate::error::{Error, Result};
+use crate::image_info::{AlphaType, ColorType, ImageInfo};
+use crate::pixel;
+
+/// Pixels described by an [`ImageInfo`], with `row_bytes` from the start of one row to the next.
+#[derive(Copy, Clone, Debug)]
+pub struct Pixmap<'a> {
+    info: ImageInfo,
+    pixels: &'a [u8],
+    row_bytes: usize,
+}
+
+/// Like [`Pixmap`], for pixels that can be written.
+#[derive(Debug)]
+pub struct PixmapMut<'a> {
+    info: ImageInfo,
+    pixels: &'a mut [u8],
+    row_bytes: usize,
+}
+
+impl<'a> Pixmap<'a> {
+    /// Fails if `info` is empty or `pixels` is too small to hold its rows.
+    pub fn new(info: ImageInfo, pixels: &'a [u8], row_bytes: usize) -> Result<Self> {
+        check(&info, pixels.len(), row_bytes)?;
+
+        Ok(Self {
+            info,
+            pixels,
+            row_bytes,
+        })
+    }
+
+    #[inline]
+    pub fn info(&self) -> &ImageInfo {
+        &self.info
+    }
+
+    #[inline]
+    pub fn row_bytes(&self) -> usize {
+        self.row_bytes
+    }
+
+    #[inline]
+    pub fn pixels(&self) -> &'a [u8] {
+        self.pixels
+    }
+
+    /// Copy the pixels starting at (`src_x`, `src_y`) into `dst`, converting them to its format.
+    ///
+    /// Only the part of `dst` overlapping these pixels is written. `false` if nothing overlaps.
+    pub fn read_pixels(&self, dst: &mut PixmapMut<'_>, src_x: i32, src_y: i32) -> bool {
+        let (src_x, src_y) = (src_x as i64, src_y as i64);
+        let left = src_x.max(0);
+        let top = src_y.max(0);
+        let right = (src_x + dst.info.width as i64).min(self.info.width as i64);
+        let bottom = (src_y + dst.info.height as i64).min(self.info.height as i64);
+        if left >= right || top >= bottom {
+            return false;
+        }
+
+        let src_bpp = self.info.bytes_per_pixel();
+        let dst_bpp = dst.info.bytes_per_pixel();
+        let len = (right - left) as usize;
+
+        for y in top..bottom {
+            let src = &self.pixels[y as usize * self.row_bytes + left as usize * src_bpp..];
+            let dst_start =
+                (y - src_y) as usize * dst.row_bytes + (left - src_x) as usize * dst_bpp;
+
+            convert_row(
+                &self.info,
+                &src[..len * src_bpp],
+                &dst.info,
+                &mut dst.pixels[dst_start..dst_start + len * dst_bpp],
+            );
+        }
+
+        true
+    }
+
+    /// Encode the pixels as an unpremultiplied RGBA PNG.
+    ///
+    /// Float color types are written with 16 bits per channel. Channel values are kept as
+    /// they are in the color space of the pixels.
+    pub fn encode_png(&self) -> Result<Vec<u8>> {
+        let ImageInfo {
+            width,
+            height,
+            color_type,
+            alpha_type,
+            ..
+        } = self.info;
+        let colors = self
+            .pixels
+            .chunks(self.row_bytes)
+            .take(height as usize)
+            .flat_map(|row| {
+                row[..self.info.min_row_bytes()].chunks_exact(color_type.bytes_per_pixel())
+            })
+            .map(|px| pixel::unpremultiply(pixel::load(color_type, alpha_type, px)));
+
+        let image: image::DynamicImage = match color_type {
+            ColorType::RgbaF16 | ColorType::RgbaF32 => {
+                let data = colors
+                    .flat_map(|c| c.map(|v| (v.clamp(0.0, 1.0) * 65535.0 + 0.5) as u16))
+                    .collect();
+                image::ImageBuffer::<image::Rgba<u16>, _>::from_raw(width, height, data)
+                    .expect("pixels match the image size")
+                    .into()
+            }
+            _ => {
+                let data = colors
+                    .flat_map(|c| c.map(|v| (v.clamp(0.0, 1.0) * 255.0 + 0.5) as u8))
+                    .collect();
+                image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(width, height, data)
+                    .expect("pixels match the image size")
+                    .into()
+            }
+        };
+
+        let mut v = Vec::new();
+        image
+            .write_to(
+                &mut std::io::Cursor::new(&mut v),
+                image::ImageOutputFormat::Png,
+            )
+            .map_err(|e| Error::Encode(e.into()))?;
+
+        Ok(v)
+    }
+}
+
+impl<'a> PixmapMut<'a> {
+    /// Fails if `info` is empty or `pixels` is too small to hold its rows.
+    pub fn new(info: ImageInfo, pixels: &'a mut [u8], row_bytes: usize) -> Result<Self> {
+        check(&info, pixels.len(), row_bytes)?;
+
+        Ok(Self {
+            info,
+            pixels,
+            row_bytes,
+        })
+    }
+
+    #[inline]
+    pub fn info(&self) -> &ImageInfo {
+        &self.info
+    }
+
+    #[inline]
+    pub fn row_bytes(&self) -> usize {
+        self.row_bytes
+    }
+
+    #[inline]
+    pub fn pixels(&self) -> &[u8] {
+        self.pixels
+    }
+
+    #[inline]
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        self.pixels
+    }
+
+    #[inline]
+    pub fn as_pixmap(&self) -> Pixmap<'_> {
+        Pixmap {
+            info: self.info,
+            pixels: self.pixels,
+            row_bytes: self.row_bytes,
+        }
+    }
+}
+
+fn check(info: &ImageInfo, len: usize, row_bytes: usize) -> Result<()> {
+    let needed = (info.height as usize)
+        .checked_sub(1)
+        .and_then(|rows| rows.checked_mul(row_bytes))
+        .and_then(|start| start.checked_add(info.min_row_bytes()));
+
+    match needed {
+        Some(needed) if info.width > 0 && row_bytes >= info.min_row_bytes() && needed <= len => {
+            Ok(())
+        }
+        _ => Err(Error::InvalidImageInfo(*info)),
+    }
+}
+
+/// Convert a row of pixels from `src_info` to `dst_info`.
+fn convert_row(src_info: &ImageInfo, src: &[u8], dst_info: &ImageInfo, dst: &mut [u8]) {
+    let premul = |alpha_type| alpha_type != AlphaType::Unpremul;
+    let same_alpha = src_info.alpha_type == dst_info.alpha_type
+        || premul(src_info.alpha_type) && premul(dst_info.alpha_type);
+
+    if src_info.color_type == dst_info.color_type
+        && same_alpha
+        && src_info.color_space == dst_info.color_space
+    {
+        dst.copy_from_slice(src);
+        return;
+    }
+
+    let src_pixels = src.chunks_exact(src_info.bytes_per_pixel());
+    let dst_pixels = dst.chunks_exact_mut(dst_info.bytes_per_pixel());
+    for (s, d) in src_pixels.zip(dst_pixels) {
+        let mut color = pixel::load(src_info.color_type, src_info.alpha_type, s);
+        if src_info.color_space != dst_info.color_space {
+            let unpremul = pixel::unpremultiply(color).into();
+            let converted = src_info.color_space.convert(unpremul, dst_info.color_space);
+            color = pixel::premultiply(converted.to_array());
+        }
+        pixel::store(dst_info.color_type, dst_info.alpha_type, color, d);
+    }
+}
@@ -0,0 +1,123 @@
+//! How images are sampled between their pixels.
+
+use crate::geom::matrix::Matrix;
+
+/// How the pixels within one mipmap level are combined.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FilterMode {
+    /// The pixel the sample falls into.
+    #[default]
+    Nearest,
+    /// Interpolate the four pixels around the sample.
+    Linear,
+}
+
+/// Which mipmap levels are sampled when an image is drawn smaller than its size.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MipmapMode {
+    /// Always sample the full image.
+    #[default]
+    None,
+    /// The level closest to the drawn size.
+    Nearest,
+    /// Interpolate the two levels around the drawn size.
+    Linear,
+}
+
+/// A cubic filter of the Mitchell-Netravali family, weighing the 4x4 pixels around a sample.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CubicResampler {
+    /// Blur, 0 keeps the image sharp.
+    pub b: f32,
+    /// Ringing, higher values sharpen edges more.
+    pub c: f32,
+}
+
+impl CubicResampler {
+    /// Balances blurring and ringing, good for scaling down.
+    pub const MITCHELL: Self = Self {
+        b: 1.0 / 3.0,
+        c: 1.0 / 3.0,
+    };
+
+    /// Interpolates the pixels exactly, good for scaling up.
+    pub const CATMULL_ROM: Self = Self { b: 0.0, c: 0.5 };
+
+    /// The weights of four consecutive pixels for a sample `t` past the center of the second.
+    pub fn weights(&self, t: f32) -> [f32; 4] {
+        let Self { b, c } = *self;
+        let near = |x: f32| {
+            ((12.0 - 9.0 * b - 6.0 * c) * x * x * x
+                + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                + (6.0 - 2.0 * b))
+                / 6.0
+        };
+        let far = |x: f32| {
+            ((-b - 6.0 * c) * x * x * x
+                + (6.0 * b + 30.0 * c) * x * x
+                + (-12.0 * b - 48.0 * c) * x
+                + (8.0 * b + 24.0 * c))
+                / 6.0
+        };
+
+        [far(1.0 + t), near(t), near(1.0 - t), far(2.0 - t)]
+    }
+}
+
+/// How an image is sampled when it is drawn transformed.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SamplingOptions {
+    pub filter: FilterMode,
+    pub mipmap: MipmapMode,
+    /// Replaces `filter` and `mipmap` when set.
+    pub cubic: Option<CubicResampler>,
+}
+
+impl SamplingOptions {
+    #[inline]
+    pub const fn new(filter: FilterMode, mipmap: MipmapMode) -> Self {
+        Self {
+            filter,
+            mipmap,
+            cubic: None,
+        }
+    }
+
+    #[inline]
+    pub const fn nearest() -> Self {
+        Self::new(FilterMode::Nearest, MipmapMode::None)
+    }
+
+    #[inline]
+    pub const fn linear() -> Self {
+        Self::new(FilterMode::Linear, MipmapMode::None)
+    }
+
+    #[inline]
+    pub const fn cubic(cubic: CubicResampler) -> Self {
+        Self {
+            filter: FilterMode::Nearest,
+            mipmap: MipmapMode::None,
+            cubic: Some(cubic),
+        }
+    }
+
+    /// The mipmap level to sample, given `inverse` mapping device pixels to image pixels
+    /// and the number of levels below the full image.
+    ///
+    /// Level 0 is the full image. The level is fractional only for linear mipmaps.
+    pub fn level(&self, inverse: &Matrix, levels: usize) -> f32 {
+        if self.cubic.is_some() || levels == 0 {
+            return 0.0;
+        }
+
+        // image pixels covered by one device pixel, as a power of two
+        let lod = inverse.max_scale().log2().clamp(0.0, levels as f32);
+
+        match self.mipmap {
+            MipmapMode::None => 0.0,
+            MipmapMode::Nearest => lod.round(),
+            MipmapMode::Linear => lod,
+        }
+    }
+}
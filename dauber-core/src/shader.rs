@@ -0,0 +1,324 @@
+use crate::color::Color;
+use crate::geom::matrix::Matrix;
+use crate::geom::point::Point;
+use crate::image::Image;
+use crate::sampling::SamplingOptions;
+
+/// Colors a paint per pixel instead of with a single color.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shader {
+    /// Colors along the line from `start` to `end`, constant perpendicular to it.
+    LinearGradient {
+        start: Point,
+        end: Point,
+        gradient: Gradient,
+    },
+    /// Colors by the distance to `center`, reaching the last stop at `radius`.
+    RadialGradient {
+        center: Point,
+        radius: f32,
+        gradient: Gradient,
+    },
+    /// Colors by the angle around `center`, in degrees clockwise from the positive x axis.
+    SweepGradient {
+        center: Point,
+        start_angle: f32,
+        end_angle: f32,
+        gradient: Gradient,
+    },
+    /// Colors by the circles interpolated from the start circle to the end circle,
+    /// the ones closer to the end circle drawing on top.
+    TwoPointConicalGradient {
+        start: Point,
+        start_radius: f32,
+        end: Point,
+        end_radius: f32,
+        gradient: Gradient,
+    },
+    /// Colors from `image`, with its pixels spanning (0, 0) to its size in local space.
+    Image {
+        image: Image,
+        tile_x: TileMode,
+        tile_y: TileMode,
+        sampling: SamplingOptions,
+        /// Maps the image before the canvas matrix.
+        local_matrix: Matrix,
+    },
+}
+
+impl Shader {
+    #[inline]
+    pub fn linear_gradient(start: Point, end: Point, gradient: Gradient) -> Self {
+        Self::LinearGradient {
+            start,
+            end,
+            gradient,
+        }
+    }
+
+    #[inline]
+    pub fn radial_gradient(center: Point, radius: f32, gradient: Gradient) -> Self {
+        Self::RadialGradient {
+            center,
+            radius,
+            gradient,
+        }
+    }
+
+    #[inline]
+    pub fn sweep_gradient(
+        center: Point,
+        start_angle: f32,
+        end_angle: f32,
+        gradient: Gradient,
+    ) -> Self {
+        Self::SweepGradient {
+            center,
+            start_angle,
+            end_angle,
+            gradient,
+        }
+    }
+
+    #[inline]
+    pub fn two_point_conical_gradient(
+        start: Point,
+        start_radius: f32,
+        end: Point,
+        end_radius: f32,
+        gradient: Gradient,
+    ) -> Self {
+        Self::TwoPointConicalGradient {
+            start,
+            start_radius,
+            end,
+            end_radius,
+            gradient,
+        }
+    }
+
+    /// Tile `image` with its own tile mode along each axis.
+    #[inline]
+    pub fn image(
+        image: Image,
+        tile_x: TileMode,
+        tile_y: TileMode,
+        sampling: SamplingOptions,
+        local_matrix: Matrix,
+    ) -> Self {
+        Self::Image {
+            image,
+            tile_x,
+            tile_y,
+            sampling,
+            local_matrix,
+        }
+    }
+
+    /// The stops of the gradient, `None` for images.
+    pub fn gradient(&self) -> Option<&Gradient> {
+        match self {
+            Self::LinearGradient { gradient, .. }
+            | Self::RadialGradient { gradient, .. }
+            | Self::SweepGradient { gradient, .. }
+            | Self::TwoPointConicalGradient { gradient, .. } => Some(gradient),
+            Self::Image { .. } => None,
+        }
+    }
+
+    /// Maps the geometry of the shader before the canvas matrix.
+    pub fn local_matrix(&self) -> &Matrix {
+        match self {
+            Self::LinearGradient { gradient, .. }
+            | Self::RadialGradient { gradient, .. }
+            | Self::SweepGradient { gradient, .. }
+            | Self::TwoPointConicalGradient { gradient, .. } => &gradient.local_matrix,
+            Self::Image { local_matrix, .. } => local_matrix,
+        }
+    }
+
+    /// Maps the local space of the shader to the space its gradient is evaluated in:
+    /// linear gradients run from (0, 0) to (1, 0), radial gradients are centered
+    /// at the origin with a radius of 1, sweep gradients are centered at the origin
+    /// and two-point conical gradients and images stay in local space.
+    pub fn unit_matrix(&self) -> Matrix {
+        match *self {
+            Self::LinearGradient { start, end, .. } => {
+                let d = end - start;
+                let scale = 1.0 / d.dot(d);
+
+                Matrix::scale(scale, scale)
+                    .concat(&Matrix::from_row(d.x, d.y, 0.0, -d.y, d.x, 0.0))
+                    .concat(&Matrix::translate(-start.x, -start.y))
+            }
+            Self::RadialGradient { center, radius, .. } => {
+                Matrix::scale(1.0 / radius, 1.0 / radius)
+                    .concat(&Matrix::translate(-center.x, -center.y))
+            }
+            Self::SweepGradient { center, .. } => Matrix::translate(-center.x, -center.y),
+            Self::TwoPointConicalGradient { .. } | Self::Image { .. } => Matrix::IDENTITY,
+        }
+    }
+
+    /// The color drawn everywhere when the geometry of the shader collapses,
+    /// like a linear gradient with equal points, or `None` if it does not.
+    pub fn degenerate_color(&self) -> Option<Color> {
+        let degenerate = match *self {
+            Self::Image { .. } => return None,
+            Self::LinearGradient { start, end, .. } => (end - start).length() <= f32::EPSILON,
+            Self::RadialGradient { radius, .. } => radius <= 0.0,
+            Self::SweepGradient {
+                start_angle,
+                end_angle,
+                ..
+            } => end_angle <= start_angle,
+            Self::TwoPointConicalGradient {
+                start,
+                start_radius,
+                end,
+                end_radius,
+                ..
+            } => (end - start).length() <= f32::EPSILON && end_radius == start_radius,
+        };
+
+        let gradient = self.gradient()?;
+        degenerate.then(|| gradient.degenerate_color())
+    }
+}
+
+/// Color stops shared by every kind of gradient.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    pub stops: Vec<GradientStop>,
+    pub tile_mode: TileMode,
+    /// Maps the geometry of the shader before the canvas matrix.
+    pub local_matrix: Matrix,
+}
+
+impl Gradient {
+    #[inline]
+    pub fn new<S: Into<Vec<GradientStop>>>(stops: S, tile_mode: TileMode) -> Self {
+        Self {
+            stops: stops.into(),
+            tile_mode,
+            local_matrix: Matrix::IDENTITY,
+        }
+    }
+
+    /// Colors spread evenly from 0 to 1.
+    pub fn evenly_spaced(colors: &[Color], tile_mode: TileMode) -> Self {
+        let step = 1.0 / (colors.len().max(2) - 1) as f32;
+        let stops: Vec<_> = colors
+            .iter()
+            .enumerate()
+            .map(|(i, &color)| GradientStop::new(i as f32 * step, color))
+            .collect();
+
+        Self::new(stops, tile_mode)
+    }
+
+    #[inline]
+    pub fn with_local_matrix(mut self, local_matrix: Matrix) -> Self {
+        self.local_matrix = local_matrix;
+        self
+    }
+
+    /// The stops with offsets clamped to 0..=1 and never decreasing,
+    /// extended to start at 0 and end at 1.
+    ///
+    /// Equal offsets make a hard transition, without stops everything is transparent.
+    pub fn normalized_stops(&self) -> Vec<GradientStop> {
+        let mut stops = Vec::with_capacity(self.stops.len() + 2);
+        let mut last = 0.0f32;
+
+        for stop in &self.stops {
+            // NaN offsets are treated as the previous one
+            let offset = stop.offset.clamp(last, 1.0).max(last);
+            stops.push(GradientStop::new(offset, stop.color));
+            last = offset;
+        }
+
+        match (stops.first().copied(), stops.last().copied()) {
+            (Some(first), Some(last)) => {
+                if first.offset > 0.0 {
+                    stops.insert(0, GradientStop::new(0.0, first.color));
+                }
+                if last.offset < 1.0 {
+                    stops.push(GradientStop::new(1.0, last.color));
+                }
+            }
+            _ => {
+                stops.push(GradientStop::new(0.0, Color::TRANSPARENT));
+                stops.push(GradientStop::new(1.0, Color::TRANSPARENT));
+            }
+        }
+
+        stops
+    }
+
+    /// What the whole gradient averages out to under its tile mode.
+    fn degenerate_color(&self) -> Color {
+        let stops = self.normalized_stops();
+
+        match self.tile_mode {
+            TileMode::Clamp => stops[stops.len() - 1].color,
+            TileMode::Decal => Color::TRANSPARENT,
+            TileMode::Repeat | TileMode::Mirror => {
+                let mut sum = [0.0; 4];
+                for pair in stops.windows(2) {
+                    let weight = (pair[1].offset - pair[0].offset) / 2.0;
+                    let (a, b) = (pair[0].color.to_array(), pair[1].color.to_array());
+                    for (sum, (a, b)) in sum.iter_mut().zip(a.into_iter().zip(b)) {
+                        *sum += (a + b) * weight;
+                    }
+                }
+
+                sum.into()
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GradientStop {
+    /// Position along the gradient, from 0 to 1.
+    pub offset: f32,
+    pub color: Color,
+}
+
+impl GradientStop {
+    #[inline]
+    pub const fn new(offset: f32, color: Color) -> Self {
+        Self { offset, color }
+    }
+}
+
+/// What a shader draws outside of its natural bounds.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TileMode {
+    /// Extend the edge colors.
+    #[default]
+    Clamp,
+    /// Start over at every edge.
+    Repeat,
+    /// Start over at every edge, flipping every other repetition.
+    Mirror,
+    /// Draw nothing outside.
+    Decal,
+}
+
+impl TileMode {
+    /// Map `t` into 0..=1, or `None` where nothing is drawn.
+    #[inline]
+    pub fn apply(self, t: f32) -> Option<f32> {
+        match self {
+            Self::Clamp => Some(t.clamp(0.0, 1.0)),
+            Self::Repeat => Some(t - t.floor()),
+            Self::Mirror => {
+                let t = t.rem_euclid(2.0);
+                Some(if t > 1.0 { 2.0 - t } else { t })
+            }
+            Self::Decal => (0.0..=1.0).contains(&t).then_some(t),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use dauber_core::geom::point;
+use dauber_core::geom::point::Point;
+use dauber_core::geom::rect::Rect;
+use dauber_core::path::{Path, PathSegment};
+use std::f32::consts::PI;
+
+/// The start and end points of every segment, per contour.
+fn contours(path: &Path) -> Vec<Vec<Point>> {
+    let mut contours = vec![];
+    for seg in path.iter() {
+        match seg {
+            PathSegment::Move { to } => contours.push(vec![to]),
+            PathSegment::Line { to, .. }
+            | PathSegment::Quadratic { to, .. }
+            | PathSegment::Cubic { to, .. } => contours.last_mut().unwrap().push(to),
+            PathSegment::Close => {}
+        }
+    }
+    contours
+}
+
+fn ends(path: &Path) -> Vec<(Point, Point)> {
+    contours(path)
+        .iter()
+        .map(|c| (c[0], c[c.len() - 1]))
+        .collect()
+}
+
+fn assert_near(a: Point, b: Point, tolerance: f32) {
+    assert!(
+        (a.x - b.x).abs() < tolerance && (a.y - b.y).abs() < tolerance,
+        "{a:?} != {b:?}"
+    );
+}
+
+#[test]
+fn line() {
+    let mut path = Path::new();
+    path.move_to(point(0., 0.));
+    path.line_to(point(10., 0.));
+
+    assert_eq!(
+        ends(&path.dash(&[2., 3.], 0.)),
+        [
+            (point(0., 0.), point(2., 0.)),
+            (point(5., 0.), point(7., 0.))
+        ]
+    );
+
+    assert_eq!(
+        ends(&path.dash(&[2., 3.], 1.)),
+        [
+            (point(0., 0.), point(1., 0.)),
+            (point(4., 0.), point(6., 0.)),
+            (point(9., 0.), point(10., 0.))
+        ]
+    );
+
+    // a negative phase wraps around
+    assert_eq!(
+        path.dash(&[2., 3.], -4.).iter().count(),
+        path.dash(&[2., 3.], 1.).iter().count()
+    );
+
+    // zero length dashes become dots
+    assert_eq!(
+        ends(&path.dash(&[0., 5.], 0.)),
+        [
+            (point(0., 0.), point(0., 0.)),
+            (point(5., 0.), point(5., 0.)),
+            (point(10., 0.), point(10., 0.))
+        ]
+    );
+
+    // invalid intervals leave the path alone
+    assert_eq!(contours(&path.dash(&[2., 3., 4.], 0.)), contours(&path));
+    assert_eq!(contours(&path.dash(&[0., 0.], 0.)), contours(&path));
+    assert_eq!(contours(&path.dash(&[-1., 3.], 0.)), contours(&path));
+}
+
+#[test]
+fn closed() {
+    let mut path = Path::new();
+    path.add_rect(&Rect::from_xywh(0., 0., 10., 10.));
+
+    // the first dash comes last
+    let dashed = path.dash(&[5., 5.], 0.);
+    assert_eq!(
+        ends(&dashed),
+        [
+            (point(10., 0.), point(10., 5.)),
+            (point(10., 10.), point(5., 10.)),
+            (point(0., 10.), point(0., 5.)),
+            (point(0., 0.), point(5., 0.))
+        ]
+    );
+
+    // the dash running through the start point stays in one piece
+    let dashed = path.dash(&[5., 5.], 2.5);
+    assert_eq!(contours(&dashed).len(), 4);
+    assert_eq!(
+        contours(&dashed)[3],
+        [point(0., 2.5), point(0., 0.), point(2.5, 0.)]
+    );
+}
+
+#[test]
+fn curve() {
+    // the control point bunches the parameter up at the start
+    let mut path = Path::new();
+    path.move_to(point(0., 0.));
+    path.quad_to(point(1., 0.), point(10., 0.));
+
+    for (start, end) in ends(&path.dash(&[3., 2.], 0.)) {
+        assert_near(start, point((start.x / 5.).round() * 5., 0.), 1e-3);
+        assert_near(end, point(start.x + 3., 0.), 1e-3);
+    }
+
+    let mut path = Path::new();
+    path.add_circle(point(0., 0.), 10.);
+
+    let quarter = PI * 10. / 2.;
+    let diagonal = 10. * (PI / 4.).cos();
+    let dashed = ends(&path.dash(&[quarter, quarter * 1.5], 0.));
+    assert_eq!(dashed.len(), 2);
+    assert_near(dashed[0].0, point(diagonal, diagonal), 0.05);
+    assert_near(dashed[0].1, point(-diagonal, diagonal), 0.05);
+    assert_near(dashed[1].0, point(-10., 0.), 1e-3);
+    assert_near(dashed[1].1, point(0., -10.), 0.05);
+}
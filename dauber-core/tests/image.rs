@@ -0,0 +1,123 @@
+use dauber_core::geom::matrix::Matrix;
+use dauber_core::image::Image;
+use dauber_core::image_info::{AlphaType, ColorType, ImageInfo};
+use dauber_core::pixmap::Pixmap;
+use dauber_core::sampling::{CubicResampler, FilterMode, MipmapMode, SamplingOptions};
+use dauber_core::Error;
+use std::io::Cursor;
+
+#[test]
+fn decode() {
+    let mut png = Vec::new();
+    image::RgbaImage::from_fn(3, 2, |x, y| {
+        image::Rgba([x as u8 * 100, y as u8 * 200, 0, 128])
+    })
+    .write_to(&mut Cursor::new(&mut png), image::ImageOutputFormat::Png)
+    .unwrap();
+
+    let image = Image::decode(&png).unwrap();
+    assert_eq!(image.width(), 3);
+    assert_eq!(image.height(), 2);
+    assert_eq!(image.info().color_type, ColorType::Rgba8888);
+    assert_eq!(image.info().alpha_type, AlphaType::Unpremul);
+    assert_eq!(&image.pixels()[(3 + 2) * 4..][..4], [200, 200, 0, 128]);
+
+    let mut jpeg = Vec::new();
+    image::RgbImage::from_pixel(8, 8, image::Rgb([255, 0, 0]))
+        .write_to(
+            &mut Cursor::new(&mut jpeg),
+            image::ImageOutputFormat::Jpeg(90),
+        )
+        .unwrap();
+
+    let image = Image::decode(&jpeg).unwrap();
+    assert_eq!(image.info().alpha_type, AlphaType::Opaque);
+
+    assert!(matches!(
+        Image::decode(b"not an image"),
+        Err(Error::Decode(_))
+    ));
+}
+
+#[test]
+fn encode_png() {
+    // two premultiplied pixels in rows padded to 12 bytes
+    let pixels = [
+        [128, 0, 0, 128, 0, 0, 255, 255, 9, 9, 9, 9],
+        [0, 0, 0, 0, 0, 64, 0, 64, 9, 9, 9, 9],
+    ]
+    .concat();
+    let pixmap = Pixmap::new(ImageInfo::new_wh(2, 2), &pixels, 12).unwrap();
+
+    let image = Image::decode(&pixmap.encode_png().unwrap()).unwrap();
+    assert_eq!((image.width(), image.height()), (2, 2));
+    assert_eq!(image.info().alpha_type, AlphaType::Unpremul);
+    assert_eq!(
+        image.pixels(),
+        [255, 0, 0, 128, 0, 0, 255, 255, 0, 0, 0, 0, 0, 255, 0, 64]
+    );
+
+    // float pixels keep 16 bits per channel
+    let info = ImageInfo::new_wh(1, 1).with_color_type(ColorType::RgbaF32);
+    let pixels: Vec<u8> = [0.25f32, 0.5, 0.75, 1.0]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    let pixmap = Pixmap::new(info, &pixels, 16).unwrap();
+    let image = Image::decode(&pixmap.encode_png().unwrap()).unwrap();
+    assert_eq!(image.info().color_type, ColorType::RgbaF16);
+}
+
+#[test]
+fn mipmap() {
+    let image = Image::from_pixels(ImageInfo::new_wh(5, 3), vec![255; 5 * 3 * 4]).unwrap();
+    let sizes: Vec<_> = image
+        .mipmap()
+        .iter()
+        .map(|level| (level.width(), level.height()))
+        .collect();
+    assert_eq!(sizes, [(2, 1), (1, 1)]);
+    assert!(image.mipmap()[1].pixels().iter().all(|&v| v == 255));
+
+    let image = Image::from_pixels(ImageInfo::new_wh(1, 1), vec![0; 4]).unwrap();
+    assert!(image.mipmap().is_empty());
+}
+
+#[test]
+fn sampling() {
+    for cubic in [CubicResampler::MITCHELL, CubicResampler::CATMULL_ROM] {
+        for t in [0.0, 0.25, 0.5, 0.9] {
+            let sum: f32 = cubic.weights(t).iter().sum();
+            assert!((sum - 1.0).abs() < 1e-5);
+        }
+    }
+    assert_eq!(
+        CubicResampler::CATMULL_ROM.weights(0.0),
+        [0.0, 1.0, 0.0, 0.0]
+    );
+
+    // sampling a quarter of the pixels
+    let inverse = Matrix::scale(4., 4.);
+    let level = |filter, mipmap| SamplingOptions::new(filter, mipmap).level(&inverse, 8);
+    assert_eq!(level(FilterMode::Linear, MipmapMode::None), 0.0);
+    assert_eq!(level(FilterMode::Linear, MipmapMode::Linear), 2.0);
+    assert_eq!(
+        SamplingOptions::new(FilterMode::Nearest, MipmapMode::Linear)
+            .level(&Matrix::scale(3., 3.), 8),
+        3f32.log2()
+    );
+    assert_eq!(
+        SamplingOptions::new(FilterMode::Nearest, MipmapMode::Nearest)
+            .level(&Matrix::scale(3., 3.), 8),
+        2.0
+    );
+    // never past the smallest level, and never for cubic filters
+    assert_eq!(
+        SamplingOptions::new(FilterMode::Linear, MipmapMode::Linear).level(&inverse, 1),
+        1.0
+    );
+    assert_eq!(
+        SamplingOptions::cubic(CubicResampler::MITCHELL).level(&inverse, 8),
+        0.0
+    );
+}
@@ -0,0 +1,68 @@
+use dauber_core::color::Color;
+use dauber_core::image_info::{AlphaType, ColorSpace, ColorType, ImageInfo};
+use dauber_core::pixel;
+
+#[test]
+fn row_bytes() {
+    let info = ImageInfo::new_wh(10, 4);
+    assert_eq!(info.min_row_bytes(), 40);
+    assert_eq!(info.with_color_type(ColorType::Rgb565).min_row_bytes(), 20);
+    assert_eq!(
+        info.with_color_type(ColorType::RgbaF32).min_row_bytes(),
+        160
+    );
+}
+
+#[test]
+fn color_space() {
+    let convert = |color: Color, from: ColorSpace, to: ColorSpace| {
+        let back = to.convert(from.convert(color, to), from);
+        back.to_array()
+            .into_iter()
+            .zip(color.to_array())
+            .all(|(a, b)| (a - b).abs() < 1e-4)
+    };
+
+    for to in [ColorSpace::LinearSrgb, ColorSpace::DisplayP3] {
+        assert!(convert(
+            Color::from([0.2, 0.7, 0.4, 0.5]),
+            ColorSpace::Srgb,
+            to
+        ));
+    }
+
+    // white stays white, alpha stays alpha
+    let white = ColorSpace::Srgb.convert(Color::WHITE.with_a(0.25), ColorSpace::DisplayP3);
+    assert!((white.r - 1.0).abs() < 1e-4 && (white.b - 1.0).abs() < 1e-4);
+    assert_eq!(white.a, 0.25);
+}
+
+#[test]
+fn store_load() {
+    let color = [0.25, 0.5, 0.125, 0.5];
+
+    for color_type in [
+        ColorType::Rgba8888,
+        ColorType::Bgra8888,
+        ColorType::RgbaF16,
+        ColorType::RgbaF32,
+    ] {
+        for alpha_type in [AlphaType::Premul, AlphaType::Unpremul] {
+            let mut px = [0; 16];
+            pixel::store(color_type, alpha_type, color, &mut px);
+            let loaded = pixel::load(color_type, alpha_type, &px);
+
+            let near = loaded.iter().zip(color).all(|(a, b)| (a - b).abs() < 0.01);
+            assert!(near, "{color_type:?} {alpha_type:?}: {loaded:?}");
+        }
+    }
+
+    let mut px = [0; 2];
+    pixel::store(
+        ColorType::Rgb565,
+        AlphaType::Opaque,
+        [1.0, 0.0, 1.0, 1.0],
+        &mut px,
+    );
+    assert_eq!(u16::from_le_bytes(px), 0xf81f);
+}
@@ -0,0 +1,76 @@
+use dauber_core::geom::matrix::Matrix;
+use dauber_core::geom::point;
+use dauber_core::geom::point::Point;
+use dauber_core::geom::rect::Rect;
+
+fn assert_near(a: Point, b: Point) {
+    assert!(
+        (a.x - b.x).abs() < 1e-4 && (a.y - b.y).abs() < 1e-4,
+        "{a:?} != {b:?}"
+    );
+}
+
+#[test]
+fn map_point() {
+    let pt = point(3., 4.);
+
+    assert_near(Matrix::IDENTITY.map_point(pt), pt);
+    assert_near(Matrix::translate(1., -2.).map_point(pt), point(4., 2.));
+    assert_near(Matrix::scale(2., 3.).map_point(pt), point(6., 12.));
+    assert_near(Matrix::rotate(90.).map_point(pt), point(-4., 3.));
+    assert_near(Matrix::skew(1., 0.).map_point(pt), point(7., 4.));
+    assert_near(
+        Matrix::rotate_around(180., point(1., 1.)).map_point(pt),
+        point(-1., -2.),
+    );
+
+    let persp = Matrix::from_row_persp(1., 0., 0., 0., 1., 0., 0., 0., 2.);
+    assert_near(persp.map_point(pt), point(1.5, 2.));
+}
+
+#[test]
+fn concat() {
+    // scale first, then translate
+    let m = Matrix::translate(10., 0.).concat(&Matrix::scale(2., 2.));
+    assert_near(m.map_point(point(1., 1.)), point(12., 2.));
+
+    let mut m2 = Matrix::scale(2., 2.);
+    m2.post_concat(&Matrix::translate(10., 0.));
+    assert_eq!(m, m2);
+    assert_eq!(m, Matrix::translate(10., 0.) * Matrix::scale(2., 2.));
+}
+
+#[test]
+fn invert() {
+    let m = Matrix::translate(5., 7.)
+        * Matrix::rotate(30.)
+        * Matrix::skew(0.3, 0.1)
+        * Matrix::scale(2., 0.5);
+    let inv = m.invert().unwrap();
+
+    assert!(!inv.has_perspective());
+    let pt = point(-3., 11.);
+    assert_near(inv.map_point(m.map_point(pt)), pt);
+
+    let persp = Matrix::from_row_persp(1., 0.2, 3., 0., 1., 4., 0.001, 0.002, 1.);
+    let inv = persp.invert().unwrap();
+    assert_near(inv.map_point(persp.map_point(pt)), pt);
+
+    assert!(Matrix::scale(0., 1.).invert().is_none());
+}
+
+#[test]
+fn map_rect() {
+    let rect = Rect::from_ltrb(0., 0., 2., 1.);
+
+    assert_eq!(
+        Matrix::scale(-1., 2.).map_rect(&rect),
+        Rect::from_ltrb(-2., 0., 0., 2.)
+    );
+    assert_eq!(
+        Matrix::translate(1., 1.).map_rect(&rect),
+        Rect::from_ltrb(1., 1., 3., 2.)
+    );
+    assert!((Matrix::rotate(45.).max_scale() - 1.).abs() < 1e-5);
+    assert!((Matrix::scale(3., -4.).max_scale() - 4.).abs() < 1e-5);
+}
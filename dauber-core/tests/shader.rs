@@ -0,0 +1,43 @@
+use dauber_core::color::Color;
+use dauber_core::shader::{Gradient, GradientStop, TileMode};
+
+#[test]
+fn tile_mode() {
+    assert_eq!(TileMode::Clamp.apply(-0.5), Some(0.0));
+    assert_eq!(TileMode::Clamp.apply(1.5), Some(1.0));
+    assert_eq!(TileMode::Repeat.apply(1.25), Some(0.25));
+    assert_eq!(TileMode::Repeat.apply(-0.25), Some(0.75));
+    assert_eq!(TileMode::Mirror.apply(1.25), Some(0.75));
+    assert_eq!(TileMode::Mirror.apply(-0.25), Some(0.25));
+    assert_eq!(TileMode::Decal.apply(0.5), Some(0.5));
+    assert_eq!(TileMode::Decal.apply(1.5), None);
+}
+
+#[test]
+fn normalized_stops() {
+    let gradient = Gradient::new(
+        [
+            GradientStop::new(0.25, Color::RED),
+            GradientStop::new(0.1, Color::GREEN),
+            GradientStop::new(0.75, Color::BLUE),
+        ],
+        TileMode::Clamp,
+    );
+
+    assert_eq!(
+        gradient.normalized_stops(),
+        [
+            GradientStop::new(0.0, Color::RED),
+            GradientStop::new(0.25, Color::RED),
+            GradientStop::new(0.25, Color::GREEN),
+            GradientStop::new(0.75, Color::BLUE),
+            GradientStop::new(1.0, Color::BLUE),
+        ]
+    );
+
+    let empty = Gradient::new([], TileMode::Clamp);
+    assert!(empty
+        .normalized_stops()
+        .iter()
+        .all(|stop| stop.color == Color::TRANSPARENT));
+}
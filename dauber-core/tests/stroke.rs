@@ -0,0 +1,113 @@
+use dauber_core::geom::point;
+use dauber_core::geom::point::Point;
+use dauber_core::geom::rect::Rect;
+use dauber_core::paint::{StrokeCap, StrokeJoin, StrokeStyle};
+use dauber_core::path::{Path, PathSegment};
+
+fn contours(path: &Path) -> Vec<Vec<Point>> {
+    let mut contours = vec![];
+    for seg in path.iter() {
+        match seg {
+            PathSegment::Move { to } => contours.push(vec![to]),
+            PathSegment::Line { to, .. } => contours.last_mut().unwrap().push(to),
+            PathSegment::Close => {}
+            seg => panic!("unexpected {seg:?}"),
+        }
+    }
+    contours
+}
+
+fn bounds(path: &Path) -> Rect {
+    Rect::from_points(&contours(path).concat())
+}
+
+fn assert_rect_near(a: Rect, b: Rect) {
+    assert!(
+        (a.l - b.l).abs() < 0.1
+            && (a.t - b.t).abs() < 0.1
+            && (a.r - b.r).abs() < 0.1
+            && (a.b - b.b).abs() < 0.1,
+        "{a:?} != {b:?}"
+    );
+}
+
+#[test]
+fn caps() {
+    let mut path = Path::new();
+    path.move_to(point(0., 0.));
+    path.line_to(point(10., 0.));
+
+    let mut style = StrokeStyle::new(4.);
+    let outline = path.stroke(&style, 0.01);
+    assert_eq!(contours(&outline).len(), 1);
+    assert_eq!(bounds(&outline), Rect::from_ltrb(0., -2., 10., 2.));
+
+    style.cap = StrokeCap::Square;
+    assert_eq!(
+        bounds(&path.stroke(&style, 0.01)),
+        Rect::from_ltrb(-2., -2., 12., 2.)
+    );
+
+    style.cap = StrokeCap::Round;
+    assert_rect_near(
+        bounds(&path.stroke(&style, 0.01)),
+        Rect::from_ltrb(-2., -2., 12., 2.),
+    );
+}
+
+#[test]
+fn joins() {
+    let mut path = Path::new();
+    path.add_rect(&Rect::from_xywh(0., 0., 10., 10.));
+
+    let mut style = StrokeStyle::new(2.);
+    let outline = path.stroke(&style, 0.01);
+    let rings = contours(&outline);
+
+    // outer and inner ring
+    assert_eq!(rings.len(), 2);
+    assert_eq!(bounds(&outline), Rect::from_ltrb(-1., -1., 11., 11.));
+    assert!(rings.concat().contains(&point(-1., -1.)));
+    assert!(rings.concat().contains(&point(1., 1.)));
+
+    style.join = StrokeJoin::Bevel;
+    assert!(!contours(&path.stroke(&style, 0.01))
+        .concat()
+        .contains(&point(-1., -1.)));
+
+    // a right angle miters up to a limit of sqrt(2)
+    style.join = StrokeJoin::Miter;
+    style.miter_limit = 1.4;
+    assert!(!contours(&path.stroke(&style, 0.01))
+        .concat()
+        .contains(&point(-1., -1.)));
+    style.miter_limit = 1.5;
+    assert!(contours(&path.stroke(&style, 0.01))
+        .concat()
+        .contains(&point(-1., -1.)));
+}
+
+#[test]
+fn degenerate() {
+    let mut path = Path::new();
+    path.move_to(point(5., 5.));
+    path.line_to(point(5., 5.));
+
+    let mut style = StrokeStyle::new(2.);
+    assert!(path.stroke(&style, 0.01).is_empty());
+
+    style.cap = StrokeCap::Round;
+    assert_rect_near(
+        bounds(&path.stroke(&style, 0.01)),
+        Rect::from_ltrb(4., 4., 6., 6.),
+    );
+
+    style.width = 0.;
+    assert!(path.stroke(&style, 0.01).is_empty());
+
+    // a lone move draws nothing
+    let mut path = Path::new();
+    path.move_to(point(5., 5.));
+    style.width = 2.;
+    assert!(path.stroke(&style, 0.01).is_empty());
+}
@@ -0,0 +1,166 @@
+use crate::types::{ShaderPaint, ShaderStop, Vertex};
+use dauber_core::blend_mode::BlendMode;
+use dauber_core::clip::ClipOp;
+use lyon::tessellation::VertexBuffers;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Tessellated triangles, drawn by index ranges.
+#[derive(Debug)]
+pub(crate) struct Geometry {
+    pub buffers: VertexBuffers<Vertex, u32>,
+    pub fill: Range<u32>,
+    pub stroke: Range<u32>,
+    /// A quad covering the whole surface.
+    pub cover: Range<u32>,
+}
+
+/// Where a [`Geometry`] ended up in a [`Batch`].
+#[derive(Clone, Debug)]
+pub(crate) struct Placed {
+    pub base_vertex: i32,
+    pub fill: Range<u32>,
+    pub stroke: Range<u32>,
+    pub cover: Range<u32>,
+}
+
+#[derive(Debug)]
+pub(crate) enum Command {
+    /// Set the clip bit of both stencil buffers everywhere.
+    ResetClip { geometry: Placed },
+    /// Combine the clip bit of both stencil buffers with a path.
+    Clip {
+        geometry: Placed,
+        op: ClipOp,
+        inverse: bool,
+    },
+    Draw {
+        geometry: Placed,
+        mode: BlendMode,
+        anti_alias: bool,
+        inverse: bool,
+        /// The texture sampled by image paints.
+        image: Option<Arc<wgpu::BindGroup>>,
+    },
+}
+
+impl Command {
+    #[inline]
+    pub fn geometry(&self) -> &Placed {
+        match self {
+            Self::ResetClip { geometry }
+            | Self::Clip { geometry, .. }
+            | Self::Draw { geometry, .. } => geometry,
+        }
+    }
+}
+
+/// Everything recorded since the last flush, uploaded and submitted at once.
+#[derive(Debug, Default)]
+pub(crate) struct Batch {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    pub paints: Vec<ShaderPaint>,
+    pub stops: Vec<ShaderStop>,
+    pub commands: Vec<Command>,
+}
+
+impl Batch {
+    /// Append the vertices and indices of `geometry`, drawn with the paint at `paint`.
+    pub fn push_geometry(&mut self, geometry: &Geometry, paint: u32) -> Placed {
+        let base_vertex = self.vertices.len() as i32;
+        let base_index = self.indices.len() as u32;
+        let offset = |range: &Range<u32>| range.start + base_index..range.end + base_index;
+
+        self.vertices.extend(
+            geometry
+                .buffers
+                .vertices
+                .iter()
+                .map(|vertex| Vertex { paint, ..*vertex }),
+        );
+        self.indices.extend_from_slice(&geometry.buffers.indices);
+
+        Placed {
+            base_vertex,
+            fill: offset(&geometry.fill),
+            stroke: offset(&geometry.stroke),
+            cover: offset(&geometry.cover),
+        }
+    }
+
+    /// Append `paint` with its gradient stops, returning its index.
+    pub fn push_paint(&mut self, mut paint: ShaderPaint, stops: &[ShaderStop]) -> u32 {
+        paint.stop_offset = self.stops.len() as u32;
+        self.stops.extend_from_slice(stops);
+        self.paints.push(paint);
+
+        self.paints.len() as u32 - 1
+    }
+
+    /// Empty the batch, keeping its allocations.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+        self.paints.clear();
+        self.stops.clear();
+        self.commands.clear();
+    }
+}
+
+/// A GPU buffer reused across flushes, replaced by a larger one when the data outgrows it.
+#[derive(Debug)]
+pub(crate) struct GrowableBuffer {
+    buffer: wgpu::Buffer,
+    usage: wgpu::BufferUsages,
+}
+
+impl GrowableBuffer {
+    pub fn new(device: &wgpu::Device, usage: wgpu::BufferUsages, size: u64) -> Self {
+        let usage = usage | wgpu::BufferUsages::COPY_DST;
+
+        Self {
+            buffer: create_buffer(device, usage, size),
+            usage,
+        }
+    }
+
+    #[inline]
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    /// Upload `data` to the start of the buffer, `true` if the buffer had to be replaced.
+    pub fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[u8]) -> bool {
+        if data.is_empty() {
+            return false;
+        }
+
+        // copies have to be a multiple of four bytes
+        let len = (data.len() as u64).next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT);
+
+        let grown = len > self.buffer.size();
+        if grown {
+            self.buffer = create_buffer(device, self.usage, len.next_power_of_two());
+        }
+
+        if len as usize == data.len() {
+            queue.write_buffer(&self.buffer, 0, data);
+        } else {
+            let mut padded = data.to_vec();
+            padded.resize(len as usize, 0);
+            queue.write_buffer(&self.buffer, 0, &padded);
+        }
+
+        grown
+    }
+}
+
+fn create_buffer(device: &wgpu::Device, usage: wgpu::BufferUsages, size: u64) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        size,
+        usage,
+        mapped_at_creation: false,
+    })
+}
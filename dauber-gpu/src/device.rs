@@ -1,15 +1,28 @@
-use crate::types::{Globals, ShaderPaint, Vertex};
+use crate::batch::{Batch, Command, Geometry, GrowableBuffer};
+use crate::texture::TextureCache;
+use crate::types::{Globals, ShaderPaint, ShaderStop, Vertex};
+use dauber_core::blend_mode::BlendMode;
+use dauber_core::clip::ClipOp;
 use dauber_core::color::Color;
-use dauber_core::device::Device;
+use dauber_core::device::{Device, DeviceFactory};
+use dauber_core::error::{Error, Result};
+use dauber_core::geom::matrix::Matrix;
 use dauber_core::geom::point::Point;
-use dauber_core::image_info::ImageInfo;
-use dauber_core::paint::{Paint, PaintStyle};
-use dauber_core::path;
+use dauber_core::geom::rect::Rect;
+use dauber_core::glyph::GlyphRun;
+use dauber_core::image::Image;
+use dauber_core::image_info::{ColorSpace, ColorType, ImageInfo};
+use dauber_core::paint::{Paint, PaintStyle, StrokeStyle};
 use dauber_core::path::{Path, PathSegment};
+use dauber_core::pixmap::{Pixmap, PixmapMut};
+use dauber_core::sampling::{FilterMode, SamplingOptions};
+use dauber_core::shader::{Shader, TileMode};
+use dauber_software::glyph_cache::{self, CachedGlyph, GlyphCache, GlyphMask};
 use lyon::tessellation;
 use lyon::tessellation::VertexBuffers;
+use std::collections::HashMap;
 use std::num::NonZeroU64;
-use wgpu::util::DeviceExt;
+use std::sync::Arc;
 use wgpu::{
     BindGroupLayoutDescriptor, BufferDescriptor, DeviceDescriptor, Features, IndexFormat,
     InstanceDescriptor, Limits, PowerPreference, RequestAdapterOptions, TextureViewDescriptor,
@@ -18,39 +31,124 @@ use wgpu::{
 #[derive(Debug)]
 pub struct Wgpu {
     info: ImageInfo,
+    /// `info` with the color type the color textures hold, converted to that of `info`
+    /// when the pixels are read.
+    surface: ImageInfo,
+    /// The format of the color textures, following the color type of `surface`.
+    format: wgpu::TextureFormat,
+    #[allow(dead_code)]
     instance: wgpu::Instance,
+    #[allow(dead_code)]
     adapter: wgpu::Adapter,
     device: wgpu::Device,
     queue: wgpu::Queue,
 
     surface_texture: wgpu::Texture,
     surface_texture_view: wgpu::TextureView,
+    #[allow(dead_code)]
     msaa_texture: wgpu::Texture,
     msaa_texture_view: wgpu::TextureView,
 
     globals_buffer: wgpu::Buffer,
-    paint_buffer: wgpu::Buffer,
+    vertex_buffer: GrowableBuffer,
+    index_buffer: GrowableBuffer,
+    /// The paint of every draw in the batch, indexed by its vertices.
+    paint_buffer: GrowableBuffer,
+    /// Gradient stops of every paint in the batch.
+    stops_buffer: GrowableBuffer,
+
+    stencil_texture_view: wgpu::TextureView,
+    msaa_stencil_texture_view: wgpu::TextureView,
 
     output_buffer: wgpu::Buffer,
 
-    render_pipeline: wgpu::RenderPipeline,
-    msaa_render_pipeline: wgpu::RenderPipeline,
+    pipeline_layout: wgpu::PipelineLayout,
+    shader: wgpu::ShaderModule,
+    pipelines: Pipelines,
+    msaa_pipelines: Pipelines,
+
+    /// What the surface looked like before the current draw, for the blend modes that read it
+    /// and to bring the multisampled texture up to date.
+    dst_texture: wgpu::Texture,
+    dst_texture_view: wgpu::TextureView,
+
+    /// Coverage of the glyphs in `glyphs`, uploaded before each flush.
+    glyph_texture: wgpu::Texture,
+    glyph_texture_view: wgpu::TextureView,
+    glyphs: GlyphCache,
 
+    bind_group_layout: wgpu::BindGroupLayout,
     bind_group: wgpu::BindGroup,
+    textures: TextureCache,
 
+    /// Draws and clip changes waiting for the next flush.
+    batch: Batch,
     clear: Option<Color>,
+    /// Whether draws without anti-aliasing changed the surface after the last multisampled pass.
+    msaa_stale: bool,
+    state: State,
+    saved: Vec<State>,
+}
+
+#[derive(Clone, Debug)]
+struct State {
+    matrix: Matrix,
+    /// Everything the clip is made of, replayed into the stencil buffers on restore.
+    clip: Vec<Arc<ClipElement>>,
+}
+
+#[derive(Debug)]
+struct ClipElement {
+    geometry: Geometry,
+    op: ClipOp,
+    inverse: bool,
 }
 
 impl Wgpu {
-    pub fn flush(&mut self) {
-        if let Some(_) = &self.clear {
-            self.draw_path(&Path::new(), &Paint::new());
+    /// Submit everything drawn since the last flush to the GPU.
+    pub fn flush(&mut self) -> Result<()> {
+        let encoder = self.encode_batch();
+        self.queue.submit([encoder.finish()]);
+
+        Ok(())
+    }
+
+    /// Fill the whole surface with `color`, regardless of the clip.
+    pub fn clear(&mut self, color: Color) {
+        // everything drawn before is covered anyway
+        self.batch
+            .commands
+            .retain(|command| !matches!(command, Command::Draw { .. }));
+        self.clear = Some(color);
+    }
+
+    /// Encode the surface as an unpremultiplied RGBA PNG, see [`Pixmap::encode_png`].
+    pub fn encode_to_png(&mut self) -> Result<Vec<u8>> {
+        let data = self.read_back()?;
+
+        Pixmap::new(self.info, &data, self.info.min_row_bytes())?.encode_png()
+    }
+
+    /// Copy the surface to the CPU in the color type of the device, rows without padding.
+    fn read_back(&mut self) -> Result<Vec<u8>> {
+        let data = self.read_surface()?;
+        if self.surface == self.info {
+            return Ok(data);
         }
 
-        let ImageInfo { width, height } = self.info;
+        let surface = Pixmap::new(self.surface, &data, self.surface.min_row_bytes())?;
+        let mut converted = vec![0; self.info.min_row_bytes() * self.info.height as usize];
+        let mut dst = PixmapMut::new(self.info, &mut converted, self.info.min_row_bytes())?;
+        surface.read_pixels(&mut dst, 0, 0);
+
+        Ok(converted)
+    }
 
-        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+    /// Copy the color texture to the CPU, rows without padding.
+    fn read_surface(&mut self) -> Result<Vec<u8>> {
+        let ImageInfo { width, height, .. } = self.info;
 
+        let mut encoder = self.encode_batch();
         encoder.copy_texture_to_buffer(
             wgpu::ImageCopyTexture {
                 aspect: wgpu::TextureAspect::All,
@@ -62,7 +160,7 @@ impl Wgpu {
                 buffer: &self.output_buffer,
                 layout: wgpu::ImageDataLayout {
                     offset: 0,
-                    bytes_per_row: Some(U32_SIZE * width),
+                    bytes_per_row: Some(padded_row_bytes(&self.surface)),
                     rows_per_image: Some(height),
                 },
             },
@@ -72,53 +170,46 @@ impl Wgpu {
                 depth_or_array_layers: 1,
             },
         );
-
         self.queue.submit([encoder.finish()]);
-    }
-
-    pub fn clear(&mut self, color: Color) {
-        self.clear = Some(color);
-    }
-
-    pub fn encode_to_png(&mut self) -> Vec<u8> {
-        self.flush();
-
-        let mut v = Vec::new();
-        {
-            let slice = self.output_buffer.slice(..);
-            let (tx, rx) = std::sync::mpsc::sync_channel(1);
-            slice.map_async(wgpu::MapMode::Read, move |res| {
-                tx.send(res).unwrap();
-            });
-            self.device.poll(wgpu::Maintain::Wait);
-            rx.recv().unwrap().unwrap();
-            let data = slice.get_mapped_range();
-
-            let buffer = image::ImageBuffer::<image::Rgba<u8>, _>::from_raw(
-                self.info.width,
-                self.info.height,
-                data,
-            )
-            .unwrap();
 
-            let mut buf = std::io::Cursor::new(&mut v);
+        let slice = self.output_buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::sync_channel(1);
+        slice.map_async(wgpu::MapMode::Read, move |res| {
+            let _ = tx.send(res);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
 
-            buffer
-                .write_to(&mut buf, image::ImageOutputFormat::Png)
-                .unwrap();
+        // the callback is dropped without being called when the device is gone
+        match rx.recv() {
+            Ok(Ok(())) => {}
+            Ok(Err(_)) | Err(_) => return Err(Error::DeviceLost),
         }
 
+        let row_bytes = self.surface.min_row_bytes();
+        let data = slice
+            .get_mapped_range()
+            .chunks(padded_row_bytes(&self.surface) as usize)
+            .flat_map(|row| &row[..row_bytes])
+            .copied()
+            .collect();
         self.output_buffer.unmap();
 
-        v
+        Ok(data)
     }
-}
 
-const U32_SIZE: u32 = std::mem::size_of::<u32>() as u32;
+    /// `color` converted into the color space of the surface, still unpremultiplied.
+    fn convert(&self, color: Color) -> [f32; 4] {
+        ColorSpace::Srgb
+            .convert(color, self.info.color_space)
+            .to_array()
+    }
+}
 
-impl Device for Wgpu {
-    fn new(info: ImageInfo) -> Self {
-        let ImageInfo { width, height } = info;
+impl DeviceFactory for Wgpu {
+    fn try_new(info: ImageInfo) -> Result<Self> {
+        let ImageInfo { width, height, .. } = info;
+        let surface = info.with_color_type(surface_color_type(info.color_type));
+        let format = texture_format(surface.color_type);
 
         let instance = wgpu::Instance::new(InstanceDescriptor::default());
 
@@ -127,7 +218,7 @@ impl Device for Wgpu {
             force_fallback_adapter: false,
             compatible_surface: None,
         }))
-        .unwrap();
+        .ok_or(Error::NoAdapter)?;
 
         let (device, queue) = pollster::block_on(adapter.request_device(
             &DeviceDescriptor {
@@ -137,7 +228,26 @@ impl Device for Wgpu {
             },
             None,
         ))
-        .unwrap();
+        .map_err(|e| Error::RequestDevice(e.into()))?;
+
+        let limits = device.limits();
+        let max = limits.max_texture_dimension_2d;
+        let size = padded_row_bytes(&surface) as u64 * height as u64;
+        let features = format.guaranteed_format_features(device.features());
+        let renderable = features.allowed_usages.contains(
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        ) && features.flags.contains(
+            wgpu::TextureFormatFeatureFlags::MULTISAMPLE_X4
+                | wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE
+                | wgpu::TextureFormatFeatureFlags::BLENDABLE,
+        );
+        if !renderable {
+            return Err(Error::UnsupportedColorType(info.color_type));
+        }
+        if width == 0 || height == 0 || width > max || height > max || size > limits.max_buffer_size
+        {
+            return Err(Error::InvalidImageInfo(info));
+        }
 
         let mut texture_desc = wgpu::TextureDescriptor {
             size: wgpu::Extent3d {
@@ -148,7 +258,7 @@ impl Device for Wgpu {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            format,
             usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
             label: None,
             view_formats: &[],
@@ -164,7 +274,34 @@ impl Device for Wgpu {
         let msaa_texture = device.create_texture(&texture_desc);
         let msaa_texture_view = msaa_texture.create_view(&texture_view_desc);
 
-        let output_buffer_size = (U32_SIZE * width * height) as wgpu::BufferAddress;
+        texture_desc.format = STENCIL_FORMAT;
+        let msaa_stencil_texture_view = device
+            .create_texture(&texture_desc)
+            .create_view(&texture_view_desc);
+        texture_desc.sample_count = 1;
+        let stencil_texture_view = device
+            .create_texture(&texture_desc)
+            .create_view(&texture_view_desc);
+
+        texture_desc.format = format;
+        texture_desc.usage = wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST;
+        let dst_texture = device.create_texture(&texture_desc);
+        let dst_texture_view = dst_texture.create_view(&texture_view_desc);
+
+        let glyphs = GlyphCache::default();
+        let glyph_texture = device.create_texture(&wgpu::TextureDescriptor {
+            size: wgpu::Extent3d {
+                width: glyphs.width(),
+                height: glyphs.height(),
+                depth_or_array_layers: 1,
+            },
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            ..texture_desc
+        });
+        let glyph_texture_view = glyph_texture.create_view(&texture_view_desc);
+
+        let output_buffer_size = size as wgpu::BufferAddress;
         let output_buffer_desc = BufferDescriptor {
             size: output_buffer_size,
             usage: wgpu::BufferUsages::COPY_DST
@@ -194,14 +331,46 @@ impl Device for Wgpu {
                     binding: 1,
                     visibility: wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
                         has_dynamic_offset: false,
                         min_binding_size: Some(
-                            NonZeroU64::new(std::mem::size_of::<Paint>() as u64).unwrap(),
+                            NonZeroU64::new(std::mem::size_of::<ShaderPaint>() as u64).unwrap(),
                         ),
                     },
                     count: None,
                 },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(
+                            NonZeroU64::new(std::mem::size_of::<ShaderStop>() as u64).unwrap(),
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -212,12 +381,26 @@ impl Device for Wgpu {
             mapped_at_creation: false,
         });
 
-        let paint_buffer = device.create_buffer(&BufferDescriptor {
-            label: None,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            size: std::mem::size_of::<Paint>() as u64,
-            mapped_at_creation: false,
-        });
+        let vertex_buffer = GrowableBuffer::new(
+            &device,
+            wgpu::BufferUsages::VERTEX,
+            INITIAL_BATCH_CAPACITY * std::mem::size_of::<Vertex>() as u64,
+        );
+        let index_buffer = GrowableBuffer::new(
+            &device,
+            wgpu::BufferUsages::INDEX,
+            INITIAL_BATCH_CAPACITY * std::mem::size_of::<u32>() as u64,
+        );
+        let paint_buffer = GrowableBuffer::new(
+            &device,
+            wgpu::BufferUsages::STORAGE,
+            INITIAL_BATCH_CAPACITY * std::mem::size_of::<ShaderPaint>() as u64,
+        );
+        let stops_buffer = GrowableBuffer::new(
+            &device,
+            wgpu::BufferUsages::STORAGE,
+            INITIAL_BATCH_CAPACITY * std::mem::size_of::<ShaderStop>() as u64,
+        );
 
         queue.write_buffer(
             &globals_buffer,
@@ -227,32 +410,21 @@ impl Device for Wgpu {
             }),
         );
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                        buffer: &globals_buffer,
-                        offset: 0,
-                        size: None,
-                    }),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                        buffer: &paint_buffer,
-                        offset: 0,
-                        size: None,
-                    }),
-                },
-            ],
-        });
+        let bind_group = create_bind_group(
+            &device,
+            &bind_group_layout,
+            &globals_buffer,
+            paint_buffer.buffer(),
+            stops_buffer.buffer(),
+            &dst_texture_view,
+            &glyph_texture_view,
+        );
+
+        let textures = TextureCache::new(&device);
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: None,
-            bind_group_layouts: &[&bind_group_layout],
+            bind_group_layouts: &[&bind_group_layout, textures.layout()],
             push_constant_ranges: &[],
         });
 
@@ -261,50 +433,19 @@ impl Device for Wgpu {
             source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
         });
 
-        let color_targets = [Some(wgpu::ColorTargetState {
-            format: texture_desc.format,
-            blend: Some(wgpu::BlendState::REPLACE),
-            write_mask: wgpu::ColorWrites::ALL,
-        })];
-
-        let mut render_pipeline_desc = wgpu::RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[Vertex::layout()],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &color_targets,
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
+        let factory = PipelineFactory {
+            device: &device,
+            layout: &pipeline_layout,
+            shader: &shader,
+            format,
         };
+        let pipelines = Pipelines::new(&factory, 1);
+        let msaa_pipelines = Pipelines::new(&factory, 4);
 
-        let render_pipeline = device.create_render_pipeline(&render_pipeline_desc);
-
-        render_pipeline_desc.multisample.count = 4;
-        let msaa_render_pipeline = device.create_render_pipeline(&render_pipeline_desc);
-
-        Self {
+        let mut wgpu = Self {
             info,
+            surface,
+            format,
             instance,
             adapter,
             device,
@@ -314,263 +455,1282 @@ impl Device for Wgpu {
             msaa_texture,
             msaa_texture_view,
             globals_buffer,
+            vertex_buffer,
+            index_buffer,
             paint_buffer,
+            stops_buffer,
+            stencil_texture_view,
+            msaa_stencil_texture_view,
             output_buffer,
-            render_pipeline,
-            msaa_render_pipeline,
+            pipeline_layout,
+            shader,
+            pipelines,
+            msaa_pipelines,
+            dst_texture,
+            dst_texture_view,
+            glyph_texture,
+            glyph_texture_view,
+            glyphs,
+            bind_group_layout,
             bind_group,
+            textures,
+            batch: Batch::default(),
             clear: None,
-        }
+            msaa_stale: false,
+            state: State {
+                matrix: Matrix::IDENTITY,
+                clip: vec![],
+            },
+            saved: vec![],
+        };
+
+        wgpu.replay_clip();
+
+        Ok(wgpu)
     }
+}
 
+impl Device for Wgpu {
     fn image_info(&self) -> &ImageInfo {
         &self.info
     }
 
-    fn draw_path(&mut self, path: &Path, paint: &Paint) {
-        let mut first_pt = None::<Point>;
-        let mut last_pt = None::<Point>;
+    fn matrix(&self) -> &Matrix {
+        &self.state.matrix
+    }
 
-        let mut buffers = VertexBuffers::<Vertex, u16>::new();
+    fn set_matrix(&mut self, matrix: Matrix) {
+        self.state.matrix = matrix;
+    }
 
-        match paint.style() {
-            PaintStyle::Fill => {
-                let mut b = tessellation::BuffersBuilder::new(
-                    &mut buffers,
-                    |vertex: tessellation::FillVertex| Vertex {
-                        pos: vertex.position().to_array(),
-                    },
-                );
-
-                let iter = LyonIter {
-                    iter: path.iter(),
-                    last: None,
-                    first_pt: &mut first_pt,
-                    last_pt: &mut last_pt,
-                };
-
-                tessellation::FillTessellator::new()
-                    .tessellate(iter, &tessellation::FillOptions::even_odd(), &mut b)
-                    .unwrap();
+    fn save(&mut self) {
+        self.saved.push(self.state.clone());
+    }
+
+    fn restore(&mut self) {
+        if let Some(state) = self.saved.pop() {
+            // the clip only ever grows between save and restore
+            let clip_changed = state.clip.len() != self.state.clip.len();
+            self.state = state;
+
+            if clip_changed {
+                self.replay_clip();
             }
-            PaintStyle::Stroke(width) => {
-                let mut b = tessellation::BuffersBuilder::new(
-                    &mut buffers,
-                    |vertex: tessellation::StrokeVertex| Vertex {
-                        pos: vertex.position().to_array(),
-                    },
-                );
-
-                let iter = LyonIter {
-                    iter: path.iter(),
-                    last: None,
-                    first_pt: &mut first_pt,
-                    last_pt: &mut last_pt,
-                };
-
-                tessellation::StrokeTessellator::new()
-                    .tessellate(
-                        iter,
-                        &tessellation::StrokeOptions::default().with_line_width(width),
-                        &mut b,
-                    )
-                    .unwrap();
+        }
+    }
+
+    fn save_count(&self) -> usize {
+        self.saved.len() + 1
+    }
+
+    /// The clip lives in the stencil buffers, so anti-aliased draws always
+    /// get sample accurate clip edges regardless of `anti_alias`.
+    fn clip_path(&mut self, path: &Path, op: ClipOp, _anti_alias: bool) -> Result<()> {
+        let element = ClipElement {
+            geometry: self.tessellate(path, true, None)?,
+            op,
+            inverse: path.fill_type().is_inverse(),
+        };
+
+        self.push_clip(&element);
+        self.state.clip.push(Arc::new(element));
+
+        Ok(())
+    }
+
+    fn draw_path(&mut self, path: &Path, paint: &Paint) -> Result<()> {
+        let effected;
+        let path = match paint.path_effect() {
+            Some(effect) => {
+                effected = effect.apply(path);
+                &effected
             }
-            PaintStyle::FillAndStroke(width) => {
-                let mut b = tessellation::BuffersBuilder::new(
-                    &mut buffers,
-                    |vertex: tessellation::FillVertex| Vertex {
-                        pos: vertex.position().to_array(),
-                    },
-                );
-
-                let iter = LyonIter {
-                    iter: path.iter(),
-                    last: None,
-                    first_pt: &mut first_pt,
-                    last_pt: &mut last_pt,
-                };
-
-                tessellation::FillTessellator::new()
-                    .tessellate(iter, &tessellation::FillOptions::even_odd(), &mut b)
-                    .unwrap();
-
-                let mut b = tessellation::BuffersBuilder::new(
-                    &mut buffers,
-                    |vertex: tessellation::StrokeVertex| Vertex {
-                        pos: vertex.position().to_array(),
-                    },
-                );
-
-                let iter = LyonIter {
-                    iter: path.iter(),
-                    last: None,
-                    first_pt: &mut first_pt,
-                    last_pt: &mut last_pt,
-                };
-
-                tessellation::StrokeTessellator::new()
-                    .tessellate(
-                        iter,
-                        &tessellation::StrokeOptions::default().with_line_width(width),
-                        &mut b,
-                    )
-                    .unwrap();
+            None => path,
+        };
+
+        let fill = !matches!(paint.style(), PaintStyle::Stroke(_));
+        let geometry = self.tessellate(path, fill, paint.stroke_style())?;
+        let Some((paint_index, image)) = self.push_paint(paint)? else {
+            return Ok(());
+        };
+
+        self.push_draw(
+            &geometry,
+            paint_index,
+            paint,
+            fill && path.fill_type().is_inverse(),
+            image,
+        )
+    }
+
+    /// Filled glyphs are drawn as quads textured by coverage masks rasterized once and
+    /// cached in an atlas, other paints and transforms tessellate the glyph outlines.
+    ///
+    /// Masks scale the source by their coverage, so blend modes that do not fade
+    /// linearly with the source draw outlines as well.
+    fn draw_glyph_run(&mut self, run: &GlyphRun<'_>, origin: Point, paint: &Paint) -> Result<()> {
+        let font = run.font();
+        let matrix = self.state.matrix;
+        let scale = glyph_cache::mask_scale(font, &matrix, paint)
+            .filter(|_| coverage_scales_source(paint.blend_mode()));
+        let Some(scale) = scale else {
+            return self.draw_path(&run.to_path(origin), paint);
+        };
+
+        let mut masks = vec![];
+        let mut path = Path::new();
+        for (&glyph, p) in run.glyphs().iter().zip(run.positions()) {
+            let p = Point::new(origin.x + p.x, origin.y + p.y);
+            let position = matrix.map_point(p);
+
+            let mut cached = self
+                .glyphs
+                .get(font, glyph, scale, position, paint.anti_alias);
+            if cached == CachedGlyph::Full {
+                // the atlas is full of glyphs waiting to be drawn, draw them to make room
+                self.draw_masks(&masks, paint)?;
+                masks.clear();
+                self.flush()?;
+                cached = self
+                    .glyphs
+                    .get(font, glyph, scale, position, paint.anti_alias);
+            }
+
+            match cached {
+                CachedGlyph::Mask(mask) => masks.push(mask),
+                CachedGlyph::Empty => {}
+                CachedGlyph::Full => {
+                    font.append_outline(glyph, p, &mut path);
+                }
             }
         }
 
-        self.queue.write_buffer(
-            &self.paint_buffer,
-            0,
-            bytemuck::bytes_of(&ShaderPaint {
-                color: paint.color().to_array(),
-            }),
+        self.draw_masks(&masks, paint)?;
+        if !path.is_empty() {
+            self.draw_path(&path, paint)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_image_rect(
+        &mut self,
+        image: &Image,
+        src: &Rect,
+        dst: &Rect,
+        sampling: &SamplingOptions,
+        paint: &Paint,
+    ) -> Result<()> {
+        let bounds = Rect::from_ltrb(0., 0., image.width() as f32, image.height() as f32);
+        let (Some(to_dst), Some(clipped)) =
+            (Matrix::rect_to_rect(src, dst), src.intersect(&bounds))
+        else {
+            return Ok(());
+        };
+        let Some(inverse) = self.state.matrix.concat(&to_dst).invert() else {
+            return Ok(());
+        };
+
+        let (shader_paint, texture) = self.image_paint(
+            image,
+            &clipped,
+            [TileMode::Clamp; 2],
+            &inverse,
+            sampling,
+            paint,
+        )?;
+        let paint_index = self.batch.push_paint(shader_paint, &[]);
+
+        let mut path = Path::new();
+        path.add_rect(&to_dst.map_rect(&clipped));
+        let geometry = self.tessellate(&path, true, None)?;
+
+        self.push_draw(&geometry, paint_index, paint, false, Some(texture))
+    }
+
+    fn read_pixels(&mut self, dst: &mut PixmapMut<'_>, src_x: i32, src_y: i32) -> Result<bool> {
+        let data = self.read_back()?;
+        let src = Pixmap::new(self.info, &data, self.info.min_row_bytes())?;
+
+        Ok(src.read_pixels(dst, src_x, src_y))
+    }
+}
+
+impl Wgpu {
+    fn prepare_pipelines(&mut self, mode: BlendMode, anti_alias: bool) {
+        let factory = PipelineFactory {
+            device: &self.device,
+            layout: &self.pipeline_layout,
+            shader: &self.shader,
+            format: self.format,
+        };
+
+        let pipelines = if anti_alias {
+            &mut self.msaa_pipelines
+        } else {
+            &mut self.pipelines
+        };
+        pipelines.prepare(&factory, mode);
+    }
+
+    /// Record drawing `geometry` with the paint at `paint_index`.
+    fn push_draw(
+        &mut self,
+        geometry: &Geometry,
+        paint_index: u32,
+        paint: &Paint,
+        inverse: bool,
+        image: Option<Arc<wgpu::BindGroup>>,
+    ) -> Result<()> {
+        let mode = paint.blend_mode();
+        self.prepare_pipelines(mode, paint.anti_alias);
+
+        let geometry = self.batch.push_geometry(geometry, paint_index);
+        self.batch.commands.push(Command::Draw {
+            geometry,
+            mode,
+            anti_alias: paint.anti_alias,
+            inverse,
+            image,
+        });
+
+        if self.batch.vertices.len() > MAX_BATCH_VERTICES {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Add `paint` to the batch, converted for the fragment shader, along with
+    /// the texture of its image shader.
+    ///
+    /// `None` when the shader cannot be mapped to the surface.
+    fn push_paint(&mut self, paint: &Paint) -> Result<Option<(u32, Option<Arc<wgpu::BindGroup>>)>> {
+        if let Some(Shader::Image {
+            image,
+            tile_x,
+            tile_y,
+            sampling,
+            local_matrix,
+        }) = paint.shader()
+        {
+            let Some(inverse) = self.state.matrix.concat(local_matrix).invert() else {
+                return Ok(None);
+            };
+
+            let bounds = Rect::from_ltrb(0., 0., image.width() as f32, image.height() as f32);
+            let (shader_paint, texture) = self.image_paint(
+                image,
+                &bounds,
+                [*tile_x, *tile_y],
+                &inverse,
+                sampling,
+                paint,
+            )?;
+
+            return Ok(Some((
+                self.batch.push_paint(shader_paint, &[]),
+                Some(texture),
+            )));
+        }
+
+        Ok(self
+            .shader_paint(paint)
+            .map(|(shader_paint, stops)| (self.batch.push_paint(shader_paint, &stops), None)))
+    }
+
+    /// Convert an image for the fragment shader, uploading it if needed.
+    ///
+    /// `inverse` maps the surface to pixels of the image, samples stay within `bounds`
+    /// and repeat past them by `tile_modes`.
+    fn image_paint(
+        &mut self,
+        image: &Image,
+        bounds: &Rect,
+        tile_modes: [TileMode; 2],
+        inverse: &Matrix,
+        sampling: &SamplingOptions,
+        paint: &Paint,
+    ) -> Result<(ShaderPaint, Arc<wgpu::BindGroup>)> {
+        // the mipmap is only uploaded once a smaller level would be sampled
+        let mipmap = sampling.level(inverse, 1) > 0.0;
+        let (texture, levels) = self.textures.get(
+            &self.device,
+            &self.queue,
+            image,
+            mipmap,
+            self.info.color_space,
+        )?;
+
+        let Matrix {
+            sx,
+            kx,
+            tx,
+            ky,
+            sy,
+            ty,
+            p0,
+            p1,
+            p2,
+        } = *inverse;
+        let cubic = sampling.cubic.map_or([0.0; 2], |cubic| [cubic.b, cubic.c]);
+        let shader_paint = ShaderPaint {
+            color: self.convert(paint.color()),
+            inverse: [[sx, ky, p0, 0.0], [kx, sy, p1, 0.0], [tx, ty, p2, 0.0]],
+            params: [bounds.l, bounds.t, bounds.r, bounds.b],
+            radii: [cubic[0], cubic[1], sampling.level(inverse, levels), 0.0],
+            kind: ShaderPaint::IMAGE,
+            tile_mode: tile_mode(tile_modes[0]),
+            stop_count: 0,
+            blend_mode: paint.blend_mode() as u32,
+            stop_offset: 0,
+            sampling: match (sampling.cubic, sampling.filter) {
+                (Some(_), _) => ShaderPaint::FILTER_CUBIC,
+                (None, FilterMode::Nearest) => ShaderPaint::FILTER_NEAREST,
+                (None, FilterMode::Linear) => ShaderPaint::FILTER_LINEAR,
+            },
+            tile_mode_y: tile_mode(tile_modes[1]),
+            _padding: 0,
+        };
+
+        Ok((shader_paint, texture))
+    }
+
+    fn shader_paint(&self, paint: &Paint) -> Option<(ShaderPaint, Vec<ShaderStop>)> {
+        let color = paint.color();
+        let solid = |color: [f32; 4]| {
+            let paint = ShaderPaint {
+                color,
+                inverse: [[0.0; 4]; 3],
+                params: [0.0; 4],
+                radii: [0.0; 4],
+                kind: ShaderPaint::SOLID,
+                tile_mode: 0,
+                stop_count: 0,
+                blend_mode: paint.blend_mode() as u32,
+                stop_offset: 0,
+                sampling: 0,
+                tile_mode_y: 0,
+                _padding: 0,
+            };
+            (paint, vec![])
+        };
+
+        let Some(shader) = paint.shader() else {
+            return Some(solid(self.convert(color)));
+        };
+        if let Some(degenerate) = shader.degenerate_color() {
+            return Some(solid(
+                self.convert(degenerate.with_a(degenerate.a * color.a)),
+            ));
+        }
+
+        // images are converted by `push_paint`
+        let gradient = shader.gradient()?;
+        let inverse = shader
+            .unit_matrix()
+            .concat(&self.state.matrix.concat(&gradient.local_matrix).invert()?);
+
+        let (kind, params, radii) = match *shader {
+            Shader::LinearGradient { .. } => (ShaderPaint::LINEAR, [0.0; 4], [0.0; 4]),
+            Shader::RadialGradient { .. } => (ShaderPaint::RADIAL, [0.0; 4], [0.0; 4]),
+            Shader::SweepGradient {
+                start_angle,
+                end_angle,
+                ..
+            } => {
+                let range = end_angle - start_angle;
+                let params = [360.0 / range, -start_angle / range, 0.0, 0.0];
+                (ShaderPaint::SWEEP, params, [0.0; 4])
+            }
+            Shader::TwoPointConicalGradient {
+                start,
+                start_radius,
+                end,
+                end_radius,
+                ..
+            } => (
+                ShaderPaint::TWO_POINT_CONICAL,
+                [start.x, start.y, end.x, end.y],
+                [start_radius, end_radius, 0.0, 0.0],
+            ),
+            Shader::Image { .. } => return None,
+        };
+
+        let stops: Vec<_> = gradient
+            .normalized_stops()
+            .into_iter()
+            .map(|stop| ShaderStop {
+                color: self.convert(stop.color),
+                offset: stop.offset,
+                _padding: [0.0; 3],
+            })
+            .collect();
+
+        let Matrix {
+            sx,
+            kx,
+            tx,
+            ky,
+            sy,
+            ty,
+            p0,
+            p1,
+            p2,
+        } = inverse;
+
+        let shader_paint = ShaderPaint {
+            color: self.convert(color),
+            inverse: [[sx, ky, p0, 0.0], [kx, sy, p1, 0.0], [tx, ty, p2, 0.0]],
+            params,
+            radii,
+            kind,
+            tile_mode: tile_mode(gradient.tile_mode),
+            stop_count: stops.len() as u32,
+            blend_mode: paint.blend_mode() as u32,
+            stop_offset: 0,
+            sampling: 0,
+            tile_mode_y: 0,
+            _padding: 0,
+        };
+
+        Some((shader_paint, stops))
+    }
+
+    /// Tessellate `path` transformed by the current matrix.
+    fn tessellate(&self, path: &Path, fill: bool, stroke: Option<StrokeStyle>) -> Result<Geometry> {
+        let matrix = self.state.matrix;
+
+        // u32 indices, long polylines and detailed outlines easily exceed 65535 vertices
+        let mut buffers = VertexBuffers::<Vertex, u32>::new();
+
+        if fill {
+            let options = if path.fill_type().is_even_odd() {
+                tessellation::FillOptions::even_odd()
+            } else {
+                tessellation::FillOptions::non_zero()
+            };
+            tessellate_fill(&path.transformed(&matrix), &options, &mut buffers)?;
+        }
+
+        let fill_end = buffers.indices.len() as u32;
+
+        // strokes are outlined in local coordinates so the matrix transforms the width as well,
+        // by the same stroker as the raster device
+        if let Some(style) = stroke {
+            let tolerance =
+                tessellation::FillOptions::DEFAULT_TOLERANCE / matrix.max_scale().max(f32::EPSILON);
+            let outline = path.stroke(&style, tolerance).transformed(&matrix);
+            tessellate_fill(
+                &outline,
+                &tessellation::FillOptions::non_zero(),
+                &mut buffers,
+            )?;
+        }
+
+        Ok(self.with_cover(buffers, fill_end))
+    }
+
+    /// Complete triangles that fill up to the index `fill_end` and stroke after it
+    /// with a quad covering the whole surface, masked by the stencil buffer.
+    fn with_cover(&self, mut buffers: VertexBuffers<Vertex, u32>, fill_end: u32) -> Geometry {
+        let stroke_end = buffers.indices.len() as u32;
+        let (w, h) = (self.info.width as f32, self.info.height as f32);
+        let base = buffers.vertices.len() as u32;
+
+        buffers
+            .vertices
+            .extend([[0., 0.], [w, 0.], [0., h], [w, h]].map(Vertex::new));
+        buffers.indices.extend([0, 1, 2, 2, 1, 3].map(|i| base + i));
+
+        Geometry {
+            fill: 0..fill_end,
+            stroke: fill_end..stroke_end,
+            cover: stroke_end..stroke_end + 6,
+            buffers,
+        }
+    }
+
+    /// Record drawing glyph `masks` from the atlas, each as a pixel aligned quad.
+    fn draw_masks(&mut self, masks: &[GlyphMask], paint: &Paint) -> Result<()> {
+        if masks.is_empty() {
+            return Ok(());
+        }
+        let Some((paint_index, image)) = self.push_paint(paint)? else {
+            return Ok(());
+        };
+
+        let mut buffers = VertexBuffers::<Vertex, u32>::new();
+        for &GlyphMask { left, top, atlas } in masks {
+            let (l, t) = (left as f32, top as f32);
+            let (x, y) = (atlas.x as f32, atlas.y as f32);
+            let (w, h) = (atlas.width as f32, atlas.height as f32);
+            let base = buffers.vertices.len() as u32;
+
+            buffers
+                .vertices
+                .extend([[0., 0.], [w, 0.], [0., h], [w, h]].map(|[dx, dy]| Vertex {
+                    mask: [x + dx, y + dy],
+                    ..Vertex::new([l + dx, t + dy])
+                }));
+            buffers.indices.extend([0, 1, 2, 2, 1, 3].map(|i| base + i));
+        }
+
+        let fill_end = buffers.indices.len() as u32;
+        let geometry = self.with_cover(buffers, fill_end);
+
+        self.push_draw(&geometry, paint_index, paint, false, image)
+    }
+
+    /// Copy the glyphs rasterized since the last upload into the atlas texture.
+    fn upload_glyphs(&mut self) {
+        let Some(rect) = self.glyphs.take_dirty() else {
+            return;
+        };
+
+        self.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.glyph_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: rect.x,
+                    y: rect.y,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            self.glyphs.data(),
+            wgpu::ImageDataLayout {
+                offset: rect.y as u64 * self.glyphs.width() as u64 + rect.x as u64,
+                bytes_per_row: Some(self.glyphs.width()),
+                rows_per_image: None,
+            },
+            wgpu::Extent3d {
+                width: rect.width,
+                height: rect.height,
+                depth_or_array_layers: 1,
+            },
         );
+    }
 
-        let indices_len = buffers.indices.len() as u32;
+    /// Record `element` into the clip of both stencil buffers.
+    fn push_clip(&mut self, element: &ClipElement) {
+        let geometry = self.batch.push_geometry(&element.geometry, 0);
+        self.batch.commands.push(Command::Clip {
+            geometry,
+            op: element.op,
+            inverse: element.inverse,
+        });
+    }
 
-        let vertex_buffer = self
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: None,
-                contents: bytemuck::cast_slice(&buffers.vertices),
-                usage: wgpu::BufferUsages::VERTEX,
-            });
+    /// Rebuild both stencil buffers from the current clip.
+    fn replay_clip(&mut self) {
+        let cover = self
+            .tessellate(&Path::new(), false, None)
+            .expect("the cover quad always fits");
+        let geometry = self.batch.push_geometry(&cover, 0);
+        self.batch.commands.push(Command::ResetClip { geometry });
+
+        for element in self.state.clip.clone() {
+            self.push_clip(&element);
+        }
+    }
+
+    /// Upload the batch and record its passes, leaving the batch empty.
+    fn encode_batch(&mut self) -> wgpu::CommandEncoder {
+        let mut batch = std::mem::take(&mut self.batch);
 
-        let index_buffer = self
+        self.vertex_buffer.write(
+            &self.device,
+            &self.queue,
+            bytemuck::cast_slice(&batch.vertices),
+        );
+        self.index_buffer.write(
+            &self.device,
+            &self.queue,
+            bytemuck::cast_slice(&batch.indices),
+        );
+        let paints_grown = self.paint_buffer.write(
+            &self.device,
+            &self.queue,
+            bytemuck::cast_slice(&batch.paints),
+        );
+        let stops_grown = self.stops_buffer.write(
+            &self.device,
+            &self.queue,
+            bytemuck::cast_slice(&batch.stops),
+        );
+        self.upload_glyphs();
+        if paints_grown || stops_grown {
+            self.bind_group = create_bind_group(
+                &self.device,
+                &self.bind_group_layout,
+                &self.globals_buffer,
+                self.paint_buffer.buffer(),
+                self.stops_buffer.buffer(),
+                &self.dst_texture_view,
+                &self.glyph_texture_view,
+            );
+        }
+
+        let mut encoder = self
             .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: None,
-                contents: bytemuck::cast_slice(&buffers.indices),
-                usage: wgpu::BufferUsages::INDEX,
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+        for pass in self.plan_passes(&batch.commands) {
+            self.encode_pass(&mut encoder, &pass);
+        }
+
+        // keep the allocations for the next batch
+        batch.clear();
+        self.batch = batch;
+        self.textures.purge();
+        // every glyph of the batch is in the atlas texture by now
+        self.glyphs.unpin();
+
+        encoder
+    }
+
+    /// Group `commands` into as few render passes as possible.
+    ///
+    /// Draws with and without anti-aliasing render into different color textures, so each
+    /// switch starts a new pass, bringing the multisampled texture up to date if needed.
+    /// Clip changes reach each stencil buffer right before its next draw.
+    fn plan_passes<'a>(&mut self, commands: &'a [Command]) -> Vec<Pass<'a>> {
+        let mut passes: Vec<Pass> = vec![];
+        let mut pending_clip: [Vec<&Command>; 2] = [vec![], vec![]];
+
+        for command in commands {
+            let Command::Draw {
+                mode, anti_alias, ..
+            } = *command
+            else {
+                for pending in &mut pending_clip {
+                    pending.push(command);
+                }
+                continue;
+            };
+
+            let copy_dst = blend_state(mode).is_none();
+            if copy_dst
+                || passes
+                    .last()
+                    .is_none_or(|pass| pass.anti_alias != anti_alias)
+            {
+                let load_msaa = anti_alias && self.msaa_stale && self.clear.is_none();
+                passes.push(Pass {
+                    anti_alias,
+                    copy_dst: copy_dst || load_msaa,
+                    load_msaa,
+                    clear: self.clear.take(),
+                    commands: vec![],
+                });
+                // multisampled passes resolve into the surface
+                self.msaa_stale = !anti_alias;
+            }
+
+            let pass = passes.last_mut().expect("a pass was just added");
+            pass.commands.append(&mut pending_clip[anti_alias as usize]);
+            pass.commands.push(command);
+        }
+
+        // neither the clear nor the clip can wait for the next draw
+        let [pending, msaa_pending] = pending_clip;
+        if self.clear.is_some() || !pending.is_empty() {
+            self.msaa_stale |= self.clear.is_some();
+            passes.push(Pass {
+                anti_alias: false,
+                copy_dst: false,
+                load_msaa: false,
+                clear: self.clear.take(),
+                commands: pending,
+            });
+        }
+        if !msaa_pending.is_empty() {
+            passes.push(Pass {
+                anti_alias: true,
+                copy_dst: false,
+                load_msaa: false,
+                clear: None,
+                commands: msaa_pending,
             });
+        }
+
+        passes
+    }
+
+    fn encode_pass(&self, encoder: &mut wgpu::CommandEncoder, pass: &Pass) {
+        let ImageInfo { width, height, .. } = self.info;
+
+        if pass.copy_dst {
+            encoder.copy_texture_to_texture(
+                self.surface_texture.as_image_copy(),
+                self.dst_texture.as_image_copy(),
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        // passes only changing the clip leave the surface alone
+        let draws = pass.clear.is_some()
+            || pass
+                .commands
+                .iter()
+                .any(|command| matches!(command, Command::Draw { .. }));
 
-        let (render_pipeline, view, resolve_target) = if paint.anti_alias {
+        let (pipelines, view, resolve_target, stencil_view) = if pass.anti_alias {
             (
-                &self.msaa_render_pipeline,
+                &self.msaa_pipelines,
                 &self.msaa_texture_view,
-                Some(&self.surface_texture_view),
+                Some(&self.surface_texture_view).filter(|_| draws),
+                &self.msaa_stencil_texture_view,
             )
         } else {
-            (&self.render_pipeline, &self.surface_texture_view, None)
+            (
+                &self.pipelines,
+                &self.surface_texture_view,
+                None,
+                &self.stencil_texture_view,
+            )
         };
 
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        let load = pass
+            .clear
+            .map(|color| {
+                let [r, g, b, a] = self.convert(color).map(|v| v as f64);
+                let a = a.clamp(0.0, 1.0);
+                let c = |v: f64| v.clamp(0.0, 1.0) * a;
+                wgpu::LoadOp::Clear(wgpu::Color {
+                    r: c(r),
+                    g: c(g),
+                    b: c(b),
+                    a,
+                })
+            })
+            .unwrap_or(wgpu::LoadOp::Load);
+
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target,
+                ops: wgpu::Operations { load, store: true },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: stencil_view,
+                depth_ops: None,
+                stencil_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                }),
+            }),
+        });
 
-        {
-            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: None,
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view,
-                    resolve_target,
-                    ops: wgpu::Operations {
-                        load: self
-                            .clear
-                            .take()
-                            .map(|Color { r, g, b, a }| {
-                                wgpu::LoadOp::Clear(wgpu::Color {
-                                    r: r as f64,
-                                    g: g as f64,
-                                    b: b as f64,
-                                    a: a as f64,
-                                })
-                            })
-                            .unwrap_or(wgpu::LoadOp::Load),
-                        store: true,
-                    },
-                })],
-                depth_stencil_attachment: None,
-            });
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        let mut image = self.textures.empty();
+        rpass.set_bind_group(1, image, &[]);
+        rpass.set_vertex_buffer(0, self.vertex_buffer.buffer().slice(..));
+        rpass.set_index_buffer(self.index_buffer.buffer().slice(..), IndexFormat::Uint32);
 
-            rpass.set_pipeline(render_pipeline);
-            rpass.set_bind_group(0, &self.bind_group, &[]);
-            rpass.set_vertex_buffer(0, vertex_buffer.slice(..));
-            rpass.set_index_buffer(index_buffer.slice(..), IndexFormat::Uint16);
-            rpass.draw_indexed(0..indices_len, 0, 0..1);
+        if pass.load_msaa {
+            let geometry = pass.commands[0].geometry();
+            rpass.set_pipeline(&pipelines.load);
+            rpass.draw_indexed(geometry.cover.clone(), geometry.base_vertex, 0..1);
         }
 
-        self.queue.submit([encoder.finish()]);
+        for command in &pass.commands {
+            match command {
+                Command::ResetClip { geometry } => {
+                    rpass.set_pipeline(&pipelines.clip_reset);
+                    rpass.set_stencil_reference(CLIP_BIT);
+                    rpass.draw_indexed(geometry.cover.clone(), geometry.base_vertex, 0..1);
+                }
+                Command::Clip {
+                    geometry,
+                    op,
+                    inverse,
+                } => {
+                    let base = geometry.base_vertex;
+
+                    rpass.set_pipeline(&pipelines.stencil);
+                    rpass.set_stencil_reference(1);
+                    rpass.draw_indexed(geometry.fill.clone(), base, 0..1);
+
+                    if *inverse {
+                        rpass.set_pipeline(&pipelines.clip_invert);
+                        rpass.draw_indexed(geometry.cover.clone(), base, 0..1);
+                    }
+
+                    rpass.set_pipeline(match op {
+                        ClipOp::Intersect => &pipelines.clip_intersect,
+                        ClipOp::Difference => &pipelines.clip_difference,
+                    });
+                    rpass.set_stencil_reference(0);
+                    rpass.draw_indexed(geometry.cover.clone(), base, 0..1);
+                }
+                Command::Draw {
+                    geometry,
+                    mode,
+                    inverse,
+                    image: draw_image,
+                    ..
+                } => {
+                    let draw_image = draw_image.as_ref().unwrap_or(self.textures.empty());
+                    if !Arc::ptr_eq(draw_image, image) {
+                        image = draw_image;
+                        rpass.set_bind_group(1, image, &[]);
+                    }
+
+                    let draw = &pipelines.draw[mode];
+                    let base = geometry.base_vertex;
+
+                    if *inverse {
+                        rpass.set_pipeline(&pipelines.stencil);
+                        rpass.set_stencil_reference(1);
+                        rpass.draw_indexed(geometry.fill.clone(), base, 0..1);
+
+                        rpass.set_pipeline(&draw.cover);
+                        rpass.set_stencil_reference(CLIP_BIT);
+                        rpass.draw_indexed(geometry.cover.clone(), base, 0..1);
+                    } else {
+                        rpass.set_pipeline(&draw.fill);
+                        rpass.set_stencil_reference(CLIP_BIT);
+                        rpass.draw_indexed(geometry.fill.clone(), base, 0..1);
+                    }
+
+                    rpass.set_pipeline(&draw.fill);
+                    rpass.set_stencil_reference(CLIP_BIT);
+                    rpass.draw_indexed(geometry.stroke.clone(), base, 0..1);
+                }
+            }
+        }
     }
 }
 
-struct LyonIter<'a> {
-    iter: path::Iter<'a>,
-    last: Option<PathSegment>,
-    first_pt: &'a mut Option<Point>,
-    last_pt: &'a mut Option<Point>,
+/// Commands recorded into one render pass.
+struct Pass<'a> {
+    anti_alias: bool,
+    /// Copy the surface into the destination texture before the pass.
+    copy_dst: bool,
+    /// Start by filling the multisampled texture with the destination texture.
+    load_msaa: bool,
+    clear: Option<Color>,
+    commands: Vec<&'a Command>,
 }
 
-impl<'a> Iterator for LyonIter<'a> {
-    type Item = lyon::path::PathEvent;
+/// Elements the batch buffers hold before they first grow.
+const INITIAL_BATCH_CAPACITY: u64 = 256;
+/// Vertices after which a draw flushes the batch, bounding its memory.
+const MAX_BATCH_VERTICES: usize = 1 << 20;
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    globals_buffer: &wgpu::Buffer,
+    paint_buffer: &wgpu::Buffer,
+    stops_buffer: &wgpu::Buffer,
+    dst_texture_view: &wgpu::TextureView,
+    glyph_texture_view: &wgpu::TextureView,
+) -> wgpu::BindGroup {
+    let entry = |binding, buffer| wgpu::BindGroupEntry {
+        binding,
+        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer,
+            offset: 0,
+            size: None,
+        }),
+    };
+    let texture = |binding, view| wgpu::BindGroupEntry {
+        binding,
+        resource: wgpu::BindingResource::TextureView(view),
+    };
+
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: None,
+        layout,
+        entries: &[
+            entry(0, globals_buffer),
+            entry(1, paint_buffer),
+            entry(2, stops_buffer),
+            texture(3, dst_texture_view),
+            texture(4, glyph_texture_view),
+        ],
+    })
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.iter.next() {
-            Some(seg) => {
-                self.last = Some(seg.clone());
-                Some(path_seg_to_lyon_event(seg, self.first_pt, self.last_pt))
-            }
-            None => match self.last.take() {
-                Some(PathSegment::Close) | None => None,
-                Some(_) => Some(lyon::path::PathEvent::End {
-                    last: lyon_point((*self.last_pt)?),
-                    first: lyon_point((*self.first_pt)?),
-                    close: false,
-                }),
-            },
+/// Bytes per row of the output buffer, padded to what texture copies require.
+fn padded_row_bytes(info: &ImageInfo) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64;
+    let padded = (info.min_row_bytes() as u64).div_ceil(align) * align;
+
+    padded.try_into().unwrap_or(u32::MAX)
+}
+
+/// The color type the color textures of a device with `color_type` hold.
+///
+/// Packed, gray and alpha only pixels cannot be blended into directly, they are rendered
+/// as RGBA and converted when read.
+fn surface_color_type(color_type: ColorType) -> ColorType {
+    match color_type {
+        ColorType::Rgb565 | ColorType::Gray8 | ColorType::Alpha8 => ColorType::Rgba8888,
+        color_type => color_type,
+    }
+}
+
+/// The color texture format for `color_type`.
+///
+/// The textures hold premultiplied colors, blended without any conversion like the raster
+/// device does.
+fn texture_format(color_type: ColorType) -> wgpu::TextureFormat {
+    match color_type {
+        ColorType::Bgra8888 => wgpu::TextureFormat::Bgra8Unorm,
+        ColorType::RgbaF16 => wgpu::TextureFormat::Rgba16Float,
+        ColorType::RgbaF32 => wgpu::TextureFormat::Rgba32Float,
+        ColorType::Rgba8888 | ColorType::Rgb565 | ColorType::Gray8 | ColorType::Alpha8 => {
+            wgpu::TextureFormat::Rgba8Unorm
         }
     }
 }
 
-fn lyon_point(Point { x, y }: Point) -> lyon::math::Point {
-    lyon::math::point(x, y)
+/// The tile mode as the fragment shader expects it.
+fn tile_mode(mode: TileMode) -> u32 {
+    match mode {
+        TileMode::Clamp => 0,
+        TileMode::Repeat => 1,
+        TileMode::Mirror => 2,
+        TileMode::Decal => 3,
+    }
+}
+
+const STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Stencil8;
+
+/// Set where the clip allows drawing.
+const CLIP_BIT: u32 = 0x80;
+/// The remaining stencil bits, zero between passes.
+const FILL_MASK: u32 = 0x7f;
+
+#[derive(Debug)]
+struct Pipelines {
+    sample_count: u32,
+    /// Marks the covered area in the fill bits without touching the color.
+    stencil: wgpu::RenderPipeline,
+    /// Flips the lowest fill bit.
+    clip_invert: wgpu::RenderPipeline,
+    /// Clears the clip bit where the fill bits are zero, resetting the fill bits.
+    clip_intersect: wgpu::RenderPipeline,
+    /// Clears the clip bit where the fill bits are set, resetting the fill bits.
+    clip_difference: wgpu::RenderPipeline,
+    /// Sets the clip bit and resets the fill bits.
+    clip_reset: wgpu::RenderPipeline,
+    /// Replaces the color with the destination texture, ignoring the stencil.
+    load: wgpu::RenderPipeline,
+    /// Pipelines drawing the paint, created for every blend mode on first use.
+    draw: HashMap<BlendMode, DrawPipelines>,
+}
+
+#[derive(Debug)]
+struct DrawPipelines {
+    /// Draws where the clip bit is set.
+    fill: wgpu::RenderPipeline,
+    /// Draws where the clip bit is set and the fill bits are zero,
+    /// resetting the fill bits everywhere else.
+    cover: wgpu::RenderPipeline,
+}
+
+/// Everything pipelines are created from, apart from their sample count.
+struct PipelineFactory<'a> {
+    device: &'a wgpu::Device,
+    layout: &'a wgpu::PipelineLayout,
+    shader: &'a wgpu::ShaderModule,
+    format: wgpu::TextureFormat,
 }
 
-fn path_seg_to_lyon_event(
-    seg: PathSegment,
-    first_pt: &mut Option<Point>,
-    last_pt: &mut Option<Point>,
-) -> lyon::path::PathEvent {
-    match seg {
-        PathSegment::Move { to } => {
-            *first_pt = Some(to);
-            *last_pt = Some(to);
-            lyon::path::PathEvent::Begin { at: lyon_point(to) }
+impl PipelineFactory<'_> {
+    fn create(
+        &self,
+        sample_count: u32,
+        entry_point: &str,
+        blend: Option<wgpu::BlendState>,
+        write_mask: wgpu::ColorWrites,
+        stencil: wgpu::StencilState,
+    ) -> wgpu::RenderPipeline {
+        let color_targets = [Some(wgpu::ColorTargetState {
+            format: self.format,
+            blend,
+            write_mask,
+        })];
+
+        self.device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: None,
+                layout: Some(self.layout),
+                vertex: wgpu::VertexState {
+                    module: self.shader,
+                    entry_point: "vs_main",
+                    buffers: &[Vertex::layout()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: self.shader,
+                    entry_point,
+                    targets: &color_targets,
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: None,
+                    unclipped_depth: false,
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    conservative: false,
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: STENCIL_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Always,
+                    stencil,
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+            })
+    }
+}
+
+impl Pipelines {
+    fn new(factory: &PipelineFactory, sample_count: u32) -> Self {
+        use wgpu::CompareFunction as Cmp;
+        use wgpu::StencilOperation as Op;
+
+        let create = |stencil| {
+            factory.create(
+                sample_count,
+                "fs_stencil",
+                None,
+                wgpu::ColorWrites::empty(),
+                stencil,
+            )
+        };
+
+        Self {
+            sample_count,
+            stencil: create(stencil_state(
+                Cmp::Always,
+                Op::Keep,
+                Op::Replace,
+                0,
+                FILL_MASK,
+            )),
+            clip_invert: create(stencil_state(Cmp::Always, Op::Keep, Op::Invert, 0, 1)),
+            clip_intersect: create(stencil_state(
+                Cmp::Equal,
+                Op::DecrementClamp,
+                Op::Zero,
+                FILL_MASK,
+                !0,
+            )),
+            clip_difference: create(stencil_state(
+                Cmp::NotEqual,
+                Op::Keep,
+                Op::Zero,
+                FILL_MASK,
+                !0,
+            )),
+            clip_reset: create(stencil_state(Cmp::Always, Op::Keep, Op::Replace, 0, !0)),
+            load: factory.create(
+                sample_count,
+                "fs_load",
+                Some(wgpu::BlendState::REPLACE),
+                wgpu::ColorWrites::ALL,
+                stencil_state(Cmp::Always, Op::Keep, Op::Keep, 0, 0),
+            ),
+            draw: HashMap::new(),
         }
-        PathSegment::Line { from, to } => {
-            *last_pt = Some(to);
-            lyon::path::PathEvent::Line {
-                from: lyon_point(from),
-                to: lyon_point(to),
+    }
+
+    /// Create the pipelines drawing with `mode` unless they exist already.
+    fn prepare(&mut self, factory: &PipelineFactory, mode: BlendMode) {
+        use wgpu::CompareFunction as Cmp;
+        use wgpu::StencilOperation as Op;
+
+        let sample_count = self.sample_count;
+
+        self.draw.entry(mode).or_insert_with(|| {
+            // modes the blend state cannot express blend in the shader and replace the destination
+            let (entry_point, blend) = match blend_state(mode) {
+                Some(blend) => ("fs_main", blend),
+                None => ("fs_blend", wgpu::BlendState::REPLACE),
+            };
+            let create = |stencil| {
+                factory.create(
+                    sample_count,
+                    entry_point,
+                    Some(blend),
+                    wgpu::ColorWrites::ALL,
+                    stencil,
+                )
+            };
+
+            DrawPipelines {
+                fill: create(stencil_state(Cmp::Equal, Op::Keep, Op::Keep, CLIP_BIT, 0)),
+                cover: create(stencil_state(
+                    Cmp::Equal,
+                    Op::Replace,
+                    Op::Keep,
+                    !0,
+                    FILL_MASK,
+                )),
             }
-        }
-        PathSegment::Quadratic { from, ctrl, to } => {
-            *last_pt = Some(to);
-            lyon::path::PathEvent::Quadratic {
-                from: lyon_point(from),
-                ctrl: lyon_point(ctrl),
-                to: lyon_point(to),
+        });
+    }
+}
+
+fn stencil_state(
+    compare: wgpu::CompareFunction,
+    fail_op: wgpu::StencilOperation,
+    pass_op: wgpu::StencilOperation,
+    read_mask: u32,
+    write_mask: u32,
+) -> wgpu::StencilState {
+    let face = wgpu::StencilFaceState {
+        compare,
+        fail_op,
+        depth_fail_op: wgpu::StencilOperation::Keep,
+        pass_op,
+    };
+
+    wgpu::StencilState {
+        front: face,
+        back: face,
+        read_mask,
+        write_mask,
+    }
+}
+
+/// The blend state of `mode` for premultiplied colors, `None` if it needs the shader.
+fn blend_state(mode: BlendMode) -> Option<wgpu::BlendState> {
+    use wgpu::BlendFactor as F;
+
+    let (src_factor, dst_factor) = match mode {
+        BlendMode::Clear => (F::Zero, F::Zero),
+        BlendMode::Src => (F::One, F::Zero),
+        BlendMode::Dst => (F::Zero, F::One),
+        BlendMode::SrcOver => (F::One, F::OneMinusSrcAlpha),
+        BlendMode::DstOver => (F::OneMinusDstAlpha, F::One),
+        BlendMode::SrcIn => (F::DstAlpha, F::Zero),
+        BlendMode::DstIn => (F::Zero, F::SrcAlpha),
+        BlendMode::SrcOut => (F::OneMinusDstAlpha, F::Zero),
+        BlendMode::DstOut => (F::Zero, F::OneMinusSrcAlpha),
+        BlendMode::SrcATop => (F::DstAlpha, F::OneMinusSrcAlpha),
+        BlendMode::DstATop => (F::OneMinusDstAlpha, F::SrcAlpha),
+        BlendMode::Xor => (F::OneMinusDstAlpha, F::OneMinusSrcAlpha),
+        BlendMode::Plus => (F::One, F::One),
+        BlendMode::Modulate => (F::Zero, F::Src),
+        BlendMode::Screen => (F::One, F::OneMinusSrc),
+        _ => return None,
+    };
+
+    let component = wgpu::BlendComponent {
+        src_factor,
+        dst_factor,
+        operation: wgpu::BlendOperation::Add,
+    };
+
+    Some(wgpu::BlendState {
+        color: component,
+        alpha: component,
+    })
+}
+
+/// Whether scaling the source by a coverage fades the result of `mode` between the
+/// destination and full coverage, like the raster device does.
+fn coverage_scales_source(mode: BlendMode) -> bool {
+    matches!(
+        mode,
+        BlendMode::Dst
+            | BlendMode::SrcOver
+            | BlendMode::DstOver
+            | BlendMode::DstOut
+            | BlendMode::SrcATop
+            | BlendMode::Xor
+            | BlendMode::Screen
+    )
+}
+
+fn lyon_path(path: &Path) -> lyon::path::Path {
+    let point = |Point { x, y }: Point| lyon::math::point(x, y);
+
+    let mut builder = lyon::path::Path::builder();
+    let mut open = false;
+
+    for seg in path.iter() {
+        match seg {
+            PathSegment::Move { to } => {
+                if open {
+                    builder.end(false);
+                }
+                builder.begin(point(to));
+                open = true;
             }
-        }
-        PathSegment::Cubic {
-            from,
-            ctrl1,
-            ctrl2,
-            to,
-        } => {
-            *last_pt = Some(to);
-            lyon::path::PathEvent::Cubic {
-                from: lyon_point(from),
-                ctrl1: lyon_point(ctrl1),
-                ctrl2: lyon_point(ctrl2),
-                to: lyon_point(to),
+            PathSegment::Line { from, to } => {
+                if !open {
+                    builder.begin(point(from));
+                    open = true;
+                }
+                builder.line_to(point(to));
+            }
+            PathSegment::Quadratic { from, ctrl, to } => {
+                if !open {
+                    builder.begin(point(from));
+                    open = true;
+                }
+                builder.quadratic_bezier_to(point(ctrl), point(to));
+            }
+            PathSegment::Cubic {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            } => {
+                if !open {
+                    builder.begin(point(from));
+                    open = true;
+                }
+                builder.cubic_bezier_to(point(ctrl1), point(ctrl2), point(to));
+            }
+            PathSegment::Close => {
+                if open {
+                    builder.end(true);
+                    open = false;
+                }
             }
         }
-        PathSegment::Close => lyon::path::PathEvent::End {
-            last: lyon_point(last_pt.unwrap()),
-            first: lyon_point(first_pt.unwrap()),
-            close: true,
-        },
     }
+
+    if open {
+        builder.end(false);
+    }
+
+    builder.build()
+}
+
+/// Append the triangles filling `path` to `buffers`.
+fn tessellate_fill(
+    path: &Path,
+    options: &tessellation::FillOptions,
+    buffers: &mut VertexBuffers<Vertex, u32>,
+) -> Result<()> {
+    let mut b = tessellation::BuffersBuilder::new(buffers, |vertex: tessellation::FillVertex| {
+        Vertex::new(vertex.position().to_array())
+    });
+
+    tessellation::FillTessellator::new()
+        .tessellate_path(&lyon_path(path), options, &mut b)
+        .map_err(|e| Error::Tessellation(e.into()))
 }
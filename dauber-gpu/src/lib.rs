@@ -1,2 +1,4 @@
+mod batch;
 pub mod device;
+mod texture;
 pub mod types;
@@ -1,12 +1,15 @@
 struct VertexOutput {
-    //@location(0) v_color: vec4<f32>,
     @builtin(position) position: vec4<f32>,
+    // index of the paint, the same for every vertex of a draw
+    @location(0) @interpolate(flat) paint: u32,
+    // texel of the glyph atlas, negative outside of glyph masks
+    @location(1) mask: vec2<f32>,
 };
 
 struct VertexInput {
     @location(0) pos: vec2<f32>,
-    //@builtin(vertex_index) vindex: u32,
-    //@builtin(instance_index) instance_idx: u32
+    @location(1) paint: u32,
+    @location(2) mask: vec2<f32>,
 };
 
 struct Globals {
@@ -26,20 +29,527 @@ fn vs_main(in: VertexInput) -> VertexOutput {
     let invert_y = vec2<f32>(1.0, -1.0);
 
     return VertexOutput(
-        vec4<f32>(((in.pos / globals.view) * 2.0 - 1.0) * invert_y, 0.0, 1.0)
+        vec4<f32>(((in.pos / globals.view) * 2.0 - 1.0) * invert_y, 0.0, 1.0),
+        in.paint,
+        in.mask,
     );
 }
 
 struct Paint {
     color: vec4<f32>,
+    inverse: mat3x3<f32>,
+    params: vec4<f32>,
+    radii: vec4<f32>,
+    kind: u32,
+    tile_mode: u32,
+    stop_count: u32,
+    blend_mode: u32,
+    stop_offset: u32,
+    sampling: u32,
+    tile_mode_y: u32,
+};
+
+struct Stop {
+    color: vec4<f32>,
+    offset: f32,
 };
 
 @group(0)
 @binding(1)
-var<uniform> paint: Paint;
+var<storage, read> paints: array<Paint>;
+
+@group(0)
+@binding(2)
+var<storage, read> stops: array<Stop>;
+
+// a copy of the surface, read by the blend modes the blend state cannot express
+@group(0)
+@binding(3)
+var dst_texture: texture_2d<f32>;
+
+// coverage of cached glyphs
+@group(0)
+@binding(4)
+var glyph_atlas: texture_2d<f32>;
+
+// the image drawn by image paints, premultiplied with its mipmap
+@group(1)
+@binding(0)
+var image_texture: texture_2d<f32>;
+
+const TAU: f32 = 6.2831855;
+
+// the largest t whose interpolated circle passes through p with a radius that is not negative,
+// along with whether there is one
+fn two_point_conical(paint: Paint, p: vec2<f32>) -> vec2<f32> {
+    let start = paint.params.xy;
+    let r0 = paint.radii.x;
+    let cd = paint.params.zw - start;
+    let pd = p - start;
+    let dr = paint.radii.y - r0;
+
+    let a = dot(cd, cd) - dr * dr;
+    let b = dot(pd, cd) + r0 * dr;
+    let c = dot(pd, pd) - r0 * r0;
+
+    if abs(a) <= 1e-6 * (dot(cd, cd) + dr * dr) {
+        let t = c / (2.0 * b);
+        return vec2<f32>(t, f32(b != 0.0 && r0 + t * dr >= 0.0));
+    }
+
+    let disc = b * b - a * c;
+    if disc < 0.0 {
+        return vec2<f32>(0.0);
+    }
+
+    let s = sqrt(disc);
+    let t0 = (b + s) / a;
+    let t1 = (b - s) / a;
+    let hi = max(t0, t1);
+    let lo = min(t0, t1);
+
+    if r0 + hi * dr >= 0.0 {
+        return vec2<f32>(hi, 1.0);
+    }
+    return vec2<f32>(lo, f32(r0 + lo * dr >= 0.0));
+}
+
+// t mapped into 0..=1, along with whether anything is drawn there
+fn tile(tile_mode: u32, t: f32) -> vec2<f32> {
+    switch tile_mode {
+        // clamp
+        case 0u: {
+            return vec2<f32>(clamp(t, 0.0, 1.0), 1.0);
+        }
+        // repeat
+        case 1u: {
+            return vec2<f32>(t - floor(t), 1.0);
+        }
+        // mirror
+        case 2u: {
+            let m = t - 2.0 * floor(t * 0.5);
+            return vec2<f32>(select(m, 2.0 - m, m > 1.0), 1.0);
+        }
+        // decal
+        default: {
+            return vec2<f32>(t, f32(t >= 0.0 && t <= 1.0));
+        }
+    }
+}
+
+fn gradient(paint: Paint, t: f32) -> vec4<f32> {
+    let first = paint.stop_offset;
+    if t <= stops[first].offset {
+        return stops[first].color;
+    }
+
+    for (var i = first + 1u; i < first + paint.stop_count; i++) {
+        let b = stops[i];
+        if t <= b.offset {
+            let a = stops[i - 1u];
+            return mix(a.color, b.color, (t - a.offset) / (b.offset - a.offset));
+        }
+    }
+
+    return stops[first + paint.stop_count - 1u].color;
+}
+
+fn premultiply(color: vec4<f32>) -> vec4<f32> {
+    let c = clamp(color, vec4<f32>(0.0), vec4<f32>(1.0));
+    return vec4<f32>(c.rgb * c.a, c.a);
+}
+
+// a sample coordinate along one axis of a mipmap level, with the pixels it may read
+struct Axis {
+    v: f32,
+    // the bounds, in which pixel centers are sampled
+    lo: f32,
+    hi: f32,
+    // the first and last pixel touching the bounds
+    first: i32,
+    last: i32,
+    // how the pixels repeat past the bounds
+    mode: u32,
+};
+
+fn axis(v: f32, lo: f32, hi: f32, size: i32, mode: u32) -> Axis {
+    let first = clamp(i32(floor(lo)), 0, size - 1);
+    let last = clamp(i32(ceil(hi)) - 1, first, size - 1);
+    return Axis(v, lo, hi, first, last, mode);
+}
+
+fn rem_euclid(a: i32, b: i32) -> i32 {
+    return ((a % b) + b) % b;
+}
+
+// pixel i moved into the bounds by the tile mode, -1 outside of a decal
+fn tap(a: Axis, i: i32) -> i32 {
+    let n = a.last - a.first + 1;
+    switch a.mode {
+        // repeat
+        case 1u: {
+            return a.first + rem_euclid(i - a.first, n);
+        }
+        // mirror
+        case 2u: {
+            let m = rem_euclid(i - a.first, 2 * n);
+            return a.first + select(2 * n - 1 - m, m, m < n);
+        }
+        // decal
+        case 3u: {
+            return select(i, -1, i < a.first || i > a.last);
+        }
+        default: {
+            return clamp(i, a.first, a.last);
+        }
+    }
+}
+
+// the pixel at (x, y) of a mipmap level, transparent when a decal leaves it out
+fn texel(x: i32, y: i32, level: i32) -> vec4<f32> {
+    if x < 0 || y < 0 {
+        return vec4<f32>(0.0);
+    }
+    return textureLoad(image_texture, vec2<i32>(x, y), level);
+}
+
+// the pixel before v and how far past its center v is,
+// when clamping v is kept at least half a pixel inside the bounds
+fn split(a: Axis) -> vec2<f32> {
+    var v = a.v;
+    if a.mode == 0u {
+        v = clamp(a.v, a.lo + 0.5, a.hi - 0.5);
+        if a.hi - a.lo < 1.0 {
+            v = (a.lo + a.hi) * 0.5;
+        }
+    }
+    let f = floor(v - 0.5);
+    return vec2<f32>(f, v - 0.5 - f);
+}
+
+// the weights of four consecutive pixels for a sample t past the center of the second
+fn cubic_weights(t: f32, b: f32, c: f32) -> vec4<f32> {
+    let x = vec4<f32>(1.0 + t, t, 1.0 - t, 2.0 - t);
+    let near = ((12.0 - 9.0 * b - 6.0 * c) * x * x * x + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+        + (6.0 - 2.0 * b)) / 6.0;
+    let far = ((-b - 6.0 * c) * x * x * x + (6.0 * b + 30.0 * c) * x * x
+        + (-12.0 * b - 48.0 * c) * x + (8.0 * b + 24.0 * c)) / 6.0;
+    return vec4<f32>(far.x, near.y, near.z, far.w);
+}
+
+// the color at p in pixels of the full image, read from one mipmap level
+fn sample_level(paint: Paint, p: vec2<f32>, level: i32) -> vec4<f32> {
+    let size = vec2<i32>(textureDimensions(image_texture, level));
+    let scale = vec2<f32>(size) / vec2<f32>(textureDimensions(image_texture, 0));
+    let bounds = paint.params * scale.xyxy;
+    let x = axis(p.x * scale.x, bounds.x, bounds.z, size.x, paint.tile_mode);
+    let y = axis(p.y * scale.y, bounds.y, bounds.w, size.y, paint.tile_mode_y);
+
+    switch paint.sampling {
+        // nearest
+        case 0u: {
+            return texel(tap(x, i32(floor(x.v))), tap(y, i32(floor(y.v))), level);
+        }
+        // linear
+        case 1u: {
+            let sx = split(x);
+            let sy = split(y);
+            let x0 = i32(sx.x);
+            let y0 = i32(sy.x);
+            let top = mix(
+                texel(tap(x, x0), tap(y, y0), level),
+                texel(tap(x, x0 + 1), tap(y, y0), level),
+                sx.y,
+            );
+            let bottom = mix(
+                texel(tap(x, x0), tap(y, y0 + 1), level),
+                texel(tap(x, x0 + 1), tap(y, y0 + 1), level),
+                sx.y,
+            );
+            return mix(top, bottom, sy.y);
+        }
+        // cubic
+        default: {
+            let sx = split(x);
+            let sy = split(y);
+            let wx = cubic_weights(sx.y, paint.radii.x, paint.radii.y);
+            let wy = cubic_weights(sy.y, paint.radii.x, paint.radii.y);
+
+            var sum = vec4<f32>(0.0);
+            for (var j = 0; j < 4; j++) {
+                for (var i = 0; i < 4; i++) {
+                    let at = texel(tap(x, i32(sx.x) + i - 1), tap(y, i32(sy.x) + j - 1), level);
+                    sum += at * wx[i] * wy[j];
+                }
+            }
+
+            // the negative lobes can overshoot
+            let a = clamp(sum.a, 0.0, 1.0);
+            return vec4<f32>(clamp(sum.rgb, vec3<f32>(0.0), vec3<f32>(a)), a);
+        }
+    }
+}
+
+fn shade_image(paint: Paint, p: vec2<f32>) -> vec4<f32> {
+    let lod = paint.radii.z;
+    let level = floor(lod);
+
+    var color = sample_level(paint, p, i32(level));
+    if lod > level {
+        color = mix(color, sample_level(paint, p, i32(level) + 1), lod - level);
+    }
+    return color * clamp(paint.color.a, 0.0, 1.0);
+}
+
+// the premultiplied color of the paint at pos
+fn shade(paint: Paint, pos: vec2<f32>) -> vec4<f32> {
+    if paint.kind == 0u {
+        return premultiply(paint.color);
+    }
+
+    let h = paint.inverse * vec3<f32>(pos, 1.0);
+    let p = h.xy / h.z;
+
+    if paint.kind == 5u {
+        return shade_image(paint, p);
+    }
+
+    var t = vec2<f32>(0.0, 1.0);
+    switch paint.kind {
+        // linear
+        case 1u: {
+            t.x = p.x;
+        }
+        // radial
+        case 2u: {
+            t.x = length(p);
+        }
+        // sweep
+        case 3u: {
+            let turn = atan2(p.y, p.x) / TAU;
+            t.x = (turn - floor(turn)) * paint.params.x + paint.params.y;
+        }
+        // two-point conical
+        default: {
+            t = two_point_conical(paint, p);
+        }
+    }
+
+    if t.y == 0.0 {
+        return vec4<f32>(0.0);
+    }
+
+    let tiled = tile(paint.tile_mode, t.x);
+    if tiled.y == 0.0 {
+        return vec4<f32>(0.0);
+    }
+
+    let color = gradient(paint, tiled.x);
+    return premultiply(vec4<f32>(color.rgb, color.a * paint.color.a));
+}
+
+fn hard_light(s: vec3<f32>, d: vec3<f32>, sa: f32, da: f32) -> vec3<f32> {
+    let blended = select(
+        sa * da - 2.0 * (da - d) * (sa - s),
+        2.0 * s * d,
+        2.0 * s <= vec3<f32>(sa),
+    );
+    return s * (1.0 - da) + d * (1.0 - sa) + blended;
+}
+
+fn color_dodge(s: f32, d: f32, sa: f32, da: f32) -> f32 {
+    if d <= 0.0 {
+        return s * (1.0 - da);
+    }
+    if s >= sa {
+        return s + d * (1.0 - sa);
+    }
+    return sa * min(da, d * sa / (sa - s)) + s * (1.0 - da) + d * (1.0 - sa);
+}
+
+fn color_burn(s: f32, d: f32, sa: f32, da: f32) -> f32 {
+    if d >= da {
+        return d + s * (1.0 - da);
+    }
+    if s <= 0.0 {
+        return d * (1.0 - sa);
+    }
+    return sa * (da - min(da, (da - d) * sa / s)) + s * (1.0 - da) + d * (1.0 - sa);
+}
+
+fn soft_light(s: f32, d: f32, sa: f32, da: f32) -> f32 {
+    let m = select(0.0, d / da, da > 0.0);
+    let s2 = 2.0 * s;
+
+    var blended: f32;
+    if s2 <= sa {
+        blended = d * (sa + (s2 - sa) * (1.0 - m));
+    } else {
+        let m4 = 4.0 * m;
+        let f = select(sqrt(m) - m, (m4 * m4 + m4) * (m - 1.0) + 7.0 * m, 4.0 * d <= da);
+        blended = d * sa + da * (s2 - sa) * f;
+    }
+
+    return s * (1.0 - da) + d * (1.0 - sa) + blended;
+}
+
+fn lum(c: vec3<f32>) -> f32 {
+    return dot(c, vec3<f32>(0.3, 0.59, 0.11));
+}
+
+fn sat(c: vec3<f32>) -> f32 {
+    return max(c.r, max(c.g, c.b)) - min(c.r, min(c.g, c.b));
+}
 
+fn set_sat(c: vec3<f32>, s: f32) -> vec3<f32> {
+    let range = sat(c);
+    if range > 0.0 {
+        return (c - min(c.r, min(c.g, c.b))) * s / range;
+    }
+    return vec3<f32>(0.0);
+}
+
+fn set_lum(c: vec3<f32>, l: f32) -> vec3<f32> {
+    return c + (l - lum(c));
+}
+
+// bring the channels back into 0..=a while keeping the luminosity
+fn clip_color(c: vec3<f32>, a: f32) -> vec3<f32> {
+    let l = lum(c);
+    let lo = min(c.r, min(c.g, c.b));
+    let hi = max(c.r, max(c.g, c.b));
+
+    var r = c;
+    if lo < 0.0 && l - lo > 0.0 {
+        r = l + (r - l) * l / (l - lo);
+    }
+    if hi > a && hi - l > 0.0 {
+        r = l + (r - l) * (a - l) / (hi - l);
+    }
+    return max(r, vec3<f32>(0.0));
+}
+
+// the blend modes after screen, matching the order of BlendMode
+fn blend(mode: u32, src: vec4<f32>, dst: vec4<f32>) -> vec4<f32> {
+    let s = src.rgb;
+    let d = dst.rgb;
+    let sa = src.a;
+    let da = dst.a;
+
+    var c: vec3<f32>;
+    switch mode {
+        // overlay
+        case 15u: {
+            c = hard_light(d, s, da, sa);
+        }
+        // darken
+        case 16u: {
+            c = s + d - max(s * da, d * sa);
+        }
+        // lighten
+        case 17u: {
+            c = s + d - min(s * da, d * sa);
+        }
+        // color dodge
+        case 18u: {
+            c = vec3<f32>(
+                color_dodge(s.r, d.r, sa, da),
+                color_dodge(s.g, d.g, sa, da),
+                color_dodge(s.b, d.b, sa, da),
+            );
+        }
+        // color burn
+        case 19u: {
+            c = vec3<f32>(
+                color_burn(s.r, d.r, sa, da),
+                color_burn(s.g, d.g, sa, da),
+                color_burn(s.b, d.b, sa, da),
+            );
+        }
+        // hard light
+        case 20u: {
+            c = hard_light(s, d, sa, da);
+        }
+        // soft light
+        case 21u: {
+            c = vec3<f32>(
+                soft_light(s.r, d.r, sa, da),
+                soft_light(s.g, d.g, sa, da),
+                soft_light(s.b, d.b, sa, da),
+            );
+        }
+        // difference
+        case 22u: {
+            c = s + d - 2.0 * min(s * da, d * sa);
+        }
+        // exclusion
+        case 23u: {
+            c = s + d - 2.0 * s * d;
+        }
+        // multiply
+        case 24u: {
+            c = s * (1.0 - da) + d * (1.0 - sa) + s * d;
+        }
+        // the non-separable modes, with both sides scaled to the same alpha
+        default: {
+            var blended: vec3<f32>;
+            switch mode {
+                // hue
+                case 25u: {
+                    blended = set_lum(set_sat(s * da, sat(d) * sa), lum(d) * sa);
+                }
+                // saturation
+                case 26u: {
+                    blended = set_lum(set_sat(d * sa, sat(s) * da), lum(d) * sa);
+                }
+                // color
+                case 27u: {
+                    blended = set_lum(s * da, lum(d) * sa);
+                }
+                // luminosity
+                default: {
+                    blended = set_lum(d * sa, lum(s) * da);
+                }
+            }
+
+            c = s * (1.0 - da) + d * (1.0 - sa) + clip_color(blended, sa * da);
+        }
+    }
+
+    return vec4<f32>(c, sa + da - sa * da);
+}
+
+// the coverage of a glyph mask, full outside of masks
+fn coverage(mask: vec2<f32>) -> f32 {
+    if mask.x < 0.0 {
+        return 1.0;
+    }
+    return textureLoad(glyph_atlas, vec2<i32>(mask), 0).r;
+}
+
+// partial coverage scales the source, which fades the modes of the blend state
+// that are linear in it
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return shade(paints[in.paint], in.position.xy) * coverage(in.mask);
+}
+
+// blends with the destination copy instead of the blend state
+@fragment
+fn fs_blend(in: VertexOutput) -> @location(0) vec4<f32> {
+    let paint = paints[in.paint];
+    let dst = textureLoad(dst_texture, vec2<i32>(in.position.xy), 0);
+    return mix(dst, blend(paint.blend_mode, shade(paint, in.position.xy), dst), coverage(in.mask));
+}
+
+// copies the destination texture, bringing the multisampled texture up to date
+@fragment
+fn fs_load(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureLoad(dst_texture, vec2<i32>(in.position.xy), 0);
+}
+
+// used by passes that only touch the stencil buffer
 @fragment
-fn fs_main() -> @location(0) vec4<f32> {
-// rgba
-    return paint.color;
-}
\ No newline at end of file
+fn fs_stencil(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(0.0);
+}
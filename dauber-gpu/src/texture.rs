@@ -0,0 +1,200 @@
+use dauber_core::error::{Error, Result};
+use dauber_core::image::Image;
+use dauber_core::image_info::{AlphaType, ColorSpace, ColorType, ImageInfo};
+use dauber_core::pixmap::PixmapMut;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Images uploaded as textures, kept for as long as they are drawn.
+#[derive(Debug)]
+pub(crate) struct TextureCache {
+    layout: wgpu::BindGroupLayout,
+    /// Bound while drawing without an image.
+    empty: Arc<wgpu::BindGroup>,
+    /// Keyed by the unique id of the image.
+    textures: HashMap<u32, CachedTexture>,
+}
+
+#[derive(Debug)]
+struct CachedTexture {
+    bind_group: Arc<wgpu::BindGroup>,
+    /// Mipmap levels below the full image.
+    levels: usize,
+    /// Drawn since the last purge.
+    used: bool,
+}
+
+impl TextureCache {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+
+        let texture = create_texture(device, 1, 1, 1);
+        let empty = Arc::new(create_bind_group(device, &layout, &texture));
+
+        Self {
+            layout,
+            empty,
+            textures: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+
+    #[inline]
+    pub fn empty(&self) -> &Arc<wgpu::BindGroup> {
+        &self.empty
+    }
+
+    /// The bind group of `image` and its number of mipmap levels, uploading it first
+    /// if it is not cached or lacks the mipmap.
+    ///
+    /// The texture holds premultiplied colors in `color_space`.
+    pub fn get(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &Image,
+        mipmap: bool,
+        color_space: ColorSpace,
+    ) -> Result<(Arc<wgpu::BindGroup>, usize)> {
+        let cached = self
+            .textures
+            .get(&image.unique_id())
+            .filter(|cached| !mipmap || cached.levels > 0 || image.mipmap().is_empty());
+
+        if cached.is_none() {
+            let max = device.limits().max_texture_dimension_2d;
+            if image.width() > max || image.height() > max {
+                return Err(Error::InvalidImageInfo(*image.info()));
+            }
+
+            let levels: &[Image] = if mipmap { image.mipmap() } else { &[] };
+            let texture = create_texture(
+                device,
+                image.width(),
+                image.height(),
+                levels.len() as u32 + 1,
+            );
+            for (level, pixels) in std::iter::once(image).chain(levels).enumerate() {
+                write_level(queue, &texture, level as u32, pixels, color_space);
+            }
+
+            self.textures.insert(
+                image.unique_id(),
+                CachedTexture {
+                    bind_group: Arc::new(create_bind_group(device, &self.layout, &texture)),
+                    levels: levels.len(),
+                    used: false,
+                },
+            );
+        }
+
+        let cached = self
+            .textures
+            .get_mut(&image.unique_id())
+            .expect("the texture was just cached");
+        cached.used = true;
+
+        Ok((cached.bind_group.clone(), cached.levels))
+    }
+
+    /// Drop the textures that were not drawn since the last purge.
+    pub fn purge(&mut self) {
+        self.textures
+            .retain(|_, cached| std::mem::take(&mut cached.used));
+    }
+}
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+fn create_texture(device: &wgpu::Device, width: u32, height: u32, levels: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: levels,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    })
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    texture: &wgpu::Texture,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: None,
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::TextureView(
+                &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+            ),
+        }],
+    })
+}
+
+/// Convert `image` to the texture format and upload it as mipmap level `level`.
+fn write_level(
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    level: u32,
+    image: &Image,
+    color_space: ColorSpace,
+) {
+    let ImageInfo { width, height, .. } = *image.info();
+    let info = ImageInfo::new(
+        width,
+        height,
+        ColorType::RgbaF16,
+        AlphaType::Premul,
+        color_space,
+    );
+
+    let mut pixels = vec![0; info.min_row_bytes() * height as usize];
+    let mut dst =
+        PixmapMut::new(info, &mut pixels, info.min_row_bytes()).expect("the buffer fits the level");
+    image.read_pixels(&mut dst, 0, 0);
+
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: level,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        &pixels,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(info.min_row_bytes() as u32),
+            rows_per_image: Some(height),
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+}
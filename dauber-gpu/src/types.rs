@@ -1,5 +1,5 @@
 use std::mem::size_of;
-use wgpu::{vertex_attr_array, VertexBufferLayout, VertexStepMode};
+use wgpu::{vertex_attr_array, VertexAttribute, VertexBufferLayout, VertexStepMode};
 
 #[derive(bytemuck::Pod, bytemuck::Zeroable, Copy, Clone, Debug)]
 #[repr(C)]
@@ -11,20 +11,78 @@ pub struct Globals {
 #[repr(C)]
 pub struct Vertex {
     pub pos: [f32; 2],
+    /// Index of the paint, filled in when the vertex is added to a batch.
+    pub paint: u32,
+    /// Texel of the glyph atlas scaling the coverage, negative without a mask.
+    pub mask: [f32; 2],
 }
 
 #[derive(bytemuck::Pod, bytemuck::Zeroable, Copy, Clone, Debug)]
 #[repr(C)]
 pub struct ShaderPaint {
     pub color: [f32; 4],
+    /// Maps framebuffer coordinates to the unit space of the shader, as padded columns.
+    /// Images use pixels of the full image instead.
+    pub inverse: [[f32; 4]; 3],
+    /// Sweep: scale and bias of `t`, two-point conical: both centers,
+    /// image: the bounds samples stay within.
+    pub params: [f32; 4],
+    /// Two-point conical: both radii, image: B and C of the cubic filter
+    /// and the mipmap level.
+    pub radii: [f32; 4],
+    pub kind: u32,
+    /// Images tile with this mode along x and `tile_mode_y` along y.
+    pub tile_mode: u32,
+    pub stop_count: u32,
+    pub blend_mode: u32,
+    /// Where the stops of this paint start.
+    pub stop_offset: u32,
+    /// How images are sampled, one of the `FILTER_` constants.
+    pub sampling: u32,
+    pub tile_mode_y: u32,
+    pub _padding: u32,
+}
+
+impl ShaderPaint {
+    pub const SOLID: u32 = 0;
+    pub const LINEAR: u32 = 1;
+    pub const RADIAL: u32 = 2;
+    pub const SWEEP: u32 = 3;
+    pub const TWO_POINT_CONICAL: u32 = 4;
+    pub const IMAGE: u32 = 5;
+
+    pub const FILTER_NEAREST: u32 = 0;
+    pub const FILTER_LINEAR: u32 = 1;
+    pub const FILTER_CUBIC: u32 = 2;
+}
+
+#[derive(bytemuck::Pod, bytemuck::Zeroable, Copy, Clone, Debug)]
+#[repr(C)]
+pub struct ShaderStop {
+    pub color: [f32; 4],
+    pub offset: f32,
+    pub _padding: [f32; 3],
 }
 
 impl Vertex {
+    const ATTRIBUTES: [VertexAttribute; 3] =
+        vertex_attr_array![0 => Float32x2, 1 => Uint32, 2 => Float32x2];
+
+    /// A vertex at `pos` without a mask.
+    #[inline]
+    pub const fn new(pos: [f32; 2]) -> Self {
+        Self {
+            pos,
+            paint: 0,
+            mask: [-1.0; 2],
+        }
+    }
+
     pub const fn layout() -> VertexBufferLayout<'static> {
         VertexBufferLayout {
             array_stride: size_of::<Vertex>() as u64,
             step_mode: VertexStepMode::Vertex,
-            attributes: &vertex_attr_array![0 => Float32x2],
+            attributes: &Self::ATTRIBUTES,
         }
     }
 }
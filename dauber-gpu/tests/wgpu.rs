@@ -1,11 +1,23 @@
+use dauber_core::blend_mode::BlendMode;
+use dauber_core::clip::ClipOp;
 use dauber_core::color::Color;
-use dauber_core::device::Device;
+use dauber_core::device::{Device, DeviceFactory};
+use dauber_core::geom::matrix::Matrix;
 use dauber_core::geom::point;
 use dauber_core::geom::point::Point;
-use dauber_core::image_info::ImageInfo;
-use dauber_core::paint::{Paint, PaintStyle};
-use dauber_core::path::Path;
+use dauber_core::geom::rect::Rect;
+use dauber_core::glyph::{GlyphId, GlyphRun, GlyphSource};
+use dauber_core::image::Image;
+use dauber_core::image_info::{AlphaType, ColorSpace, ColorType, ImageInfo};
+use dauber_core::paint::{Paint, PaintStyle, StrokeCap, StrokeJoin};
+use dauber_core::path::{Path, PathFillType};
+use dauber_core::path_effect::PathEffect;
+use dauber_core::pixmap::PixmapMut;
+use dauber_core::sampling::{CubicResampler, FilterMode, MipmapMode, SamplingOptions};
+use dauber_core::shader::{Gradient, Shader, TileMode};
+use dauber_core::Error;
 use dauber_gpu::device::Wgpu;
+use dauber_software::device::Raster;
 use std::fs::write;
 
 #[test]
@@ -24,14 +36,14 @@ fn wgpu() {
     path.close();
 
     wgpu.clear(Color::BLACK);
-    wgpu.draw_path(&path, &paint);
+    wgpu.draw_path(&path, &paint).unwrap();
 
     let mut path = Path::new();
     paint.anti_alias = false;
     paint.set_color(Color::CYAN);
     path.add_circle(Point::new(500., 200.), 100.);
 
-    wgpu.draw_path(&path, &paint);
+    wgpu.draw_path(&path, &paint).unwrap();
 
     let mut path = Path::new();
     paint.set_style(PaintStyle::Stroke(4.));
@@ -41,9 +53,765 @@ fn wgpu() {
     path.move_to(point(650., 100.));
     path.cubic_to(point(850., 400.), point(900., 0.), point(1000., 100.));
 
-    wgpu.draw_path(&path, &paint);
+    wgpu.draw_path(&path, &paint).unwrap();
 
-    let v = wgpu.encode_to_png();
+    let v = wgpu.encode_to_png().unwrap();
 
     write("out.png", v).unwrap();
 }
+
+#[test]
+fn fill_type() {
+    let mut wgpu = Wgpu::new(ImageInfo::new_wh(64, 64));
+    let mut paint = Paint::new();
+    paint.set_color(Color::WHITE);
+
+    // two overlapping squares with the same direction
+    let mut path = Path::new();
+    for (l, t) in [(4., 4.), (12., 12.)] {
+        path.move_to(point(l, t));
+        path.line_to(point(l + 16., t));
+        path.line_to(point(l + 16., t + 16.));
+        path.line_to(point(l, t + 16.));
+        path.close();
+    }
+
+    wgpu.clear(Color::BLACK);
+    wgpu.draw_path(&path, &paint).unwrap();
+
+    let mut path = path.clone();
+    path.set_fill_type(PathFillType::InverseEvenOdd);
+    paint.set_color(Color::RED);
+
+    wgpu.draw_path(&path, &paint).unwrap();
+
+    let image = image::load_from_memory(&wgpu.encode_to_png().unwrap())
+        .unwrap()
+        .to_rgba8();
+
+    assert_eq!(image.get_pixel(16, 16).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(8, 8).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(24, 24).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(48, 48).0, [255, 0, 0, 255]);
+}
+
+#[test]
+fn matrix() {
+    let mut wgpu = Wgpu::new(ImageInfo::new_wh(64, 64));
+    let mut paint = Paint::new();
+    paint.set_color(Color::WHITE);
+
+    let mut path = Path::new();
+    path.move_to(point(0., 0.));
+    path.line_to(point(4., 0.));
+    path.line_to(point(4., 4.));
+    path.line_to(point(0., 4.));
+    path.close();
+
+    wgpu.clear(Color::BLACK);
+    wgpu.set_matrix(Matrix::translate(32., 8.) * Matrix::scale(4., 2.));
+    wgpu.draw_path(&path, &paint).unwrap();
+
+    // the stroke width is scaled as well
+    wgpu.set_matrix(Matrix::scale(4., 4.));
+    paint.set_style(PaintStyle::Stroke(1.));
+    let mut path = Path::new();
+    path.move_to(point(1., 10.));
+    path.line_to(point(7., 10.));
+    wgpu.draw_path(&path, &paint).unwrap();
+
+    let image = image::load_from_memory(&wgpu.encode_to_png().unwrap())
+        .unwrap()
+        .to_rgba8();
+
+    assert_eq!(image.get_pixel(32, 8).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(47, 15).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(48, 15).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(47, 16).0, [0, 0, 0, 255]);
+
+    assert_eq!(image.get_pixel(16, 38).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(16, 41).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(16, 42).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn clip() {
+    let mut wgpu = Wgpu::new(ImageInfo::new_wh(64, 64));
+    let mut paint = Paint::new();
+    paint.set_color(Color::WHITE);
+
+    let mut full = Path::new();
+    full.add_rect(&Rect::from_xywh(0., 0., 64., 64.));
+
+    let mut clip = Path::new();
+    clip.add_rect(&Rect::from_xywh(8., 8., 40., 40.));
+
+    let mut hole = Path::new();
+    hole.add_rect(&Rect::from_xywh(16., 16., 8., 8.));
+
+    wgpu.clear(Color::BLACK);
+    wgpu.save();
+    wgpu.clip_path(&clip, ClipOp::Intersect, false).unwrap();
+    wgpu.clip_path(&hole, ClipOp::Difference, false).unwrap();
+    wgpu.draw_path(&full, &paint).unwrap();
+    wgpu.restore();
+
+    // an inverse clip outside the circle, restricted to the right half
+    let mut circle = Path::new();
+    circle.add_circle(point(56., 56.), 4.);
+    circle.set_fill_type(PathFillType::InverseWinding);
+
+    paint.set_color(Color::RED);
+    wgpu.clip_path(&circle, ClipOp::Intersect, false).unwrap();
+    wgpu.set_matrix(Matrix::translate(48., 48.));
+    wgpu.clip_path(&full, ClipOp::Intersect, false).unwrap();
+    wgpu.draw_path(&full, &paint).unwrap();
+
+    let image = image::load_from_memory(&wgpu.encode_to_png().unwrap())
+        .unwrap()
+        .to_rgba8();
+
+    assert_eq!(image.get_pixel(8, 8).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(47, 47).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(7, 8).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(48, 20).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(20, 20).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(24, 20).0, [255, 255, 255, 255]);
+
+    assert_eq!(image.get_pixel(49, 49).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(56, 56).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(47, 49).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn stroke_cap_join() {
+    let mut wgpu = Wgpu::new(ImageInfo::new_wh(64, 64));
+    let mut paint = Paint::new();
+    paint.set_color(Color::WHITE);
+    paint.set_style(PaintStyle::Stroke(16.));
+    paint.set_stroke_cap(StrokeCap::Round);
+    paint.set_stroke_join(StrokeJoin::Bevel);
+
+    let mut path = Path::new();
+    path.move_to(point(16., 16.));
+    path.line_to(point(48., 16.));
+    path.line_to(point(48., 48.));
+
+    wgpu.clear(Color::BLACK);
+    wgpu.draw_path(&path, &paint).unwrap();
+
+    let image = image::load_from_memory(&wgpu.encode_to_png().unwrap())
+        .unwrap()
+        .to_rgba8();
+
+    // round start cap
+    assert_eq!(image.get_pixel(10, 16).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(9, 9).0, [0, 0, 0, 255]);
+    // beveled corner
+    assert_eq!(image.get_pixel(52, 14).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(55, 9).0, [0, 0, 0, 255]);
+    // round end cap
+    assert_eq!(image.get_pixel(48, 54).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(55, 55).0, [0, 0, 0, 255]);
+
+    // a right angle miter is sqrt(2) times the width, beveled below that limit like on a raster
+    paint.set_stroke_join(StrokeJoin::Miter);
+    for (limit, corner) in [(1.3, [0, 0, 0, 255]), (1.5, [255, 255, 255, 255])] {
+        paint.set_miter_limit(limit);
+        wgpu.clear(Color::BLACK);
+        wgpu.draw_path(&path, &paint).unwrap();
+
+        let image = image::load_from_memory(&wgpu.encode_to_png().unwrap())
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(image.get_pixel(54, 10).0, corner, "miter limit {limit}");
+    }
+}
+
+#[test]
+fn dash() {
+    let mut wgpu = Wgpu::new(ImageInfo::new_wh(64, 64));
+    let mut paint = Paint::new();
+    paint.set_color(Color::WHITE);
+    paint.set_style(PaintStyle::Stroke(8.));
+    paint.set_path_effect(Some(PathEffect::dash([8., 8.], 0.)));
+
+    let mut path = Path::new();
+    path.move_to(point(0., 32.));
+    path.line_to(point(64., 32.));
+
+    wgpu.clear(Color::BLACK);
+    wgpu.draw_path(&path, &paint).unwrap();
+
+    let image = image::load_from_memory(&wgpu.encode_to_png().unwrap())
+        .unwrap()
+        .to_rgba8();
+
+    assert_eq!(image.get_pixel(4, 32).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(12, 32).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(20, 32).0, [255, 255, 255, 255]);
+}
+
+#[test]
+fn gradient() {
+    let mut wgpu = Wgpu::new(ImageInfo::new_wh(64, 64));
+    let colors = |tile_mode| Gradient::evenly_spaced(&[Color::RED, Color::BLUE], tile_mode);
+
+    let mut paint = Paint::new();
+    paint.set_shader(Some(Shader::linear_gradient(
+        point(16., 0.),
+        point(48., 0.),
+        colors(TileMode::Clamp),
+    )));
+
+    let mut path = Path::new();
+    path.add_rect(&Rect::from_ltrb(0., 0., 64., 32.));
+
+    wgpu.clear(Color::BLACK);
+    wgpu.draw_path(&path, &paint).unwrap();
+
+    paint.set_shader(Some(Shader::radial_gradient(
+        point(32., 48.),
+        8.,
+        colors(TileMode::Decal),
+    )));
+
+    let mut path = Path::new();
+    path.add_rect(&Rect::from_ltrb(0., 32., 64., 64.));
+    wgpu.draw_path(&path, &paint).unwrap();
+
+    let image = image::load_from_memory(&wgpu.encode_to_png().unwrap())
+        .unwrap()
+        .to_rgba8();
+
+    assert_eq!(image.get_pixel(4, 16).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(60, 16).0, [0, 0, 255, 255]);
+    let [r, _, b, _] = image.get_pixel(32, 16).0;
+    assert!(r > 0 && b > 0);
+
+    // nothing outside of the decal radius
+    assert_eq!(image.get_pixel(4, 48).0, [0, 0, 0, 255]);
+    let [r, _, b, _] = image.get_pixel(32, 48).0;
+    assert!(r > 200 && b < 100);
+}
+
+#[test]
+fn blend_mode() {
+    let modes = [
+        BlendMode::Clear,
+        BlendMode::Src,
+        BlendMode::Dst,
+        BlendMode::SrcOver,
+        BlendMode::DstOver,
+        BlendMode::SrcIn,
+        BlendMode::DstIn,
+        BlendMode::SrcOut,
+        BlendMode::DstOut,
+        BlendMode::SrcATop,
+        BlendMode::DstATop,
+        BlendMode::Xor,
+        BlendMode::Plus,
+        BlendMode::Modulate,
+        BlendMode::Screen,
+        BlendMode::Overlay,
+        BlendMode::Darken,
+        BlendMode::Lighten,
+        BlendMode::ColorDodge,
+        BlendMode::ColorBurn,
+        BlendMode::HardLight,
+        BlendMode::SoftLight,
+        BlendMode::Difference,
+        BlendMode::Exclusion,
+        BlendMode::Multiply,
+        BlendMode::Hue,
+        BlendMode::Saturation,
+        BlendMode::Color,
+        BlendMode::Luminosity,
+    ];
+
+    let info = ImageInfo::new_wh(64, 16);
+    let mut wgpu = Wgpu::new(info);
+    let mut raster = Raster::new(info);
+
+    // a translucent destination on the left half
+    let mut dst = Path::new();
+    dst.add_rect(&Rect::from_ltrb(0., 0., 8., 16.));
+    let mut dst_paint = Paint::new();
+    dst_paint.set_color(Color::from([0.8, 0.3, 0.6, 0.7]));
+
+    // a translucent source on the top half
+    let mut src = Path::new();
+    src.add_rect(&Rect::from_ltrb(0., 0., 16., 8.));
+    let mut src_paint = Paint::new();
+    src_paint.set_color(Color::from([0.2, 0.5, 0.9, 0.6]));
+
+    for mode in modes {
+        src_paint.set_blend_mode(mode);
+
+        wgpu.clear(Color::TRANSPARENT);
+        wgpu.draw_path(&dst, &dst_paint).unwrap();
+        wgpu.draw_path(&src, &src_paint).unwrap();
+
+        raster.clear(Color::TRANSPARENT);
+        raster.draw_path(&dst, &dst_paint).unwrap();
+        raster.draw_path(&src, &src_paint).unwrap();
+
+        assert_near(
+            &mut wgpu,
+            &raster,
+            &[(4, 4), (12, 4), (4, 12), (12, 12)],
+            mode,
+        );
+    }
+
+    // a translucent stroke crossing itself is blended once where it overlaps
+    let mut cross = Path::new();
+    cross.move_to(point(20., 2.));
+    cross.line_to(point(36., 14.));
+    cross.line_to(point(36., 2.));
+    cross.line_to(point(20., 14.));
+    let mut stroke = Paint::new();
+    stroke.set_style(PaintStyle::Stroke(4.));
+    stroke.set_color(Color::RED.with_a(0.5));
+
+    wgpu.clear(Color::TRANSPARENT);
+    wgpu.draw_path(&cross, &stroke).unwrap();
+    raster.clear(Color::TRANSPARENT);
+    raster.draw_path(&cross, &stroke).unwrap();
+    assert_near(
+        &mut wgpu,
+        &raster,
+        &[(28, 8), (24, 5), (36, 8)],
+        BlendMode::SrcOver,
+    );
+}
+
+/// Compare the pixels of both devices at `points`.
+fn assert_near(wgpu: &mut Wgpu, raster: &Raster, points: &[(u32, u32)], mode: BlendMode) {
+    let image = image::load_from_memory(&wgpu.encode_to_png().unwrap())
+        .unwrap()
+        .to_rgba8();
+    let reference = image::load_from_memory(&raster.encode_to_png().unwrap())
+        .unwrap()
+        .to_rgba8();
+
+    for &(x, y) in points {
+        let expected = reference.get_pixel(x, y).0;
+        let actual = image.get_pixel(x, y).0;
+
+        // rounding is amplified where the png divided by a small alpha
+        let premultiplied = |[r, g, b, a]: [u8; 4]| {
+            [r, g, b]
+                .map(|c| c as u32 * a as u32 / 255)
+                .map(|c| c as u8)
+        };
+        let near = premultiplied(actual)
+            .into_iter()
+            .zip(premultiplied(expected))
+            .chain([(actual[3], expected[3])])
+            .all(|(a, e)| a.abs_diff(e) <= 2);
+        assert!(
+            near,
+            "{mode:?} at ({x}, {y}): {actual:?} is not near {expected:?}"
+        );
+    }
+}
+
+#[test]
+fn color_type() {
+    for info in [
+        ImageInfo::new_wh(64, 16).with_color_type(ColorType::Bgra8888),
+        ImageInfo::new_wh(64, 16).with_color_type(ColorType::RgbaF16),
+        ImageInfo::new_wh(64, 16).with_color_type(ColorType::Rgb565),
+        ImageInfo::new_wh(64, 16).with_color_type(ColorType::Gray8),
+        ImageInfo::new_wh(64, 16).with_color_type(ColorType::Alpha8),
+        ImageInfo::new_wh(64, 16).with_color_space(ColorSpace::DisplayP3),
+    ] {
+        let mut wgpu = Wgpu::new(info);
+        let mut raster = Raster::new(info);
+
+        let mut rect = Path::new();
+        rect.add_rect(&Rect::from_ltrb(0., 0., 8., 16.));
+        let mut paint = Paint::new();
+        paint.set_color(Color::from([0.8, 0.3, 0.6, 0.7]));
+
+        wgpu.clear(Color::BLUE);
+        wgpu.draw_path(&rect, &paint).unwrap();
+        raster.clear(Color::BLUE);
+        raster.draw_path(&rect, &paint).unwrap();
+
+        let image = image::load_from_memory(&wgpu.encode_to_png().unwrap())
+            .unwrap()
+            .to_rgba8();
+        let reference = image::load_from_memory(&raster.encode_to_png().unwrap())
+            .unwrap()
+            .to_rgba8();
+
+        for (x, y) in [(4, 4), (12, 4)] {
+            let expected = reference.get_pixel(x, y).0;
+            let actual = image.get_pixel(x, y).0;
+            let near = actual
+                .iter()
+                .zip(expected)
+                .all(|(&a, e)| a.abs_diff(e) <= 2);
+            assert!(near, "{info:?}: {actual:?} is not near {expected:?}");
+        }
+    }
+}
+
+#[test]
+fn read_pixels() {
+    let info = ImageInfo::new_wh(64, 16).with_color_type(ColorType::RgbaF16);
+    let mut wgpu = Wgpu::new(info);
+
+    let mut rect = Path::new();
+    rect.add_rect(&Rect::from_ltrb(0., 0., 8., 16.));
+    let mut paint = Paint::new();
+    paint.set_color(Color::RED.with_a(0.5));
+
+    wgpu.clear(Color::BLUE);
+    wgpu.draw_path(&rect, &paint).unwrap();
+
+    let dst_info = ImageInfo::new_wh(4, 1).with_alpha_type(AlphaType::Unpremul);
+    let mut pixels = [0; 16];
+    let mut dst = PixmapMut::new(dst_info, &mut pixels, 16).unwrap();
+    assert!(wgpu.read_pixels(&mut dst, 6, 4).unwrap());
+
+    assert_eq!(&pixels[..4], [128, 0, 128, 255]);
+    assert_eq!(&pixels[12..], [0, 0, 255, 255]);
+}
+
+#[test]
+fn unaligned_width() {
+    for (width, height) in [(150, 150), (1, 3), (65, 2)] {
+        let info = ImageInfo::new_wh(width, height);
+        let mut wgpu = Wgpu::new(info);
+        let mut raster = Raster::new(info);
+
+        let mut rect = Path::new();
+        rect.add_rect(&Rect::from_ltrb(0., 0., width as f32 / 2., height as f32));
+        let mut paint = Paint::new();
+        paint.set_color(Color::RED);
+
+        wgpu.clear(Color::BLUE);
+        wgpu.draw_path(&rect, &paint).unwrap();
+        raster.clear(Color::BLUE);
+        raster.draw_path(&rect, &paint).unwrap();
+
+        let mut pixels = vec![0; info.min_row_bytes() * height as usize];
+        let mut dst = PixmapMut::new(info, &mut pixels, info.min_row_bytes()).unwrap();
+        assert!(wgpu.read_pixels(&mut dst, 0, 0).unwrap());
+        assert_eq!(pixels, raster.pixels(), "{width}x{height}");
+
+        let png = image::load_from_memory(&wgpu.encode_to_png().unwrap()).unwrap();
+        assert_eq!((png.width(), png.height()), (width, height));
+    }
+}
+
+#[test]
+fn batch() {
+    let info = ImageInfo::new_wh(64, 64);
+    let mut wgpu = Wgpu::new(info);
+    let mut raster = Raster::new(info);
+    wgpu.clear(Color::BLACK);
+    raster.clear(Color::BLACK);
+
+    // many draws alternating anti-aliasing, clip changes and blend modes within one flush
+    for i in 0..256 {
+        let (x, y) = ((i % 16) as f32 * 4., (i / 16) as f32 * 4.);
+        let mut rect = Path::new();
+        rect.add_rect(&Rect::from_ltrb(x, y, x + 4., y + 4.));
+
+        let mut paint = Paint::new();
+        paint.set_color(Color::from_rgba_u8(
+            i as u8,
+            255 - i as u8,
+            (i * 7) as u8,
+            255,
+        ));
+        paint.anti_alias = i % 3 == 0;
+        if i % 5 == 0 {
+            paint.set_blend_mode(BlendMode::Difference);
+        }
+
+        for device in [&mut wgpu as &mut dyn Device, &mut raster] {
+            if i % 7 == 0 {
+                device.save();
+                device.clip_path(&rect, ClipOp::Intersect, false).unwrap();
+            }
+            device.draw_path(&rect, &paint).unwrap();
+            if i % 7 == 0 {
+                device.restore();
+            }
+        }
+    }
+
+    let mut pixels = vec![0; info.min_row_bytes() * 64];
+    let mut dst = PixmapMut::new(info, &mut pixels, info.min_row_bytes()).unwrap();
+    assert!(wgpu.read_pixels(&mut dst, 0, 0).unwrap());
+
+    for i in 0..256 {
+        let at = ((i / 16) * 4 + 2) * info.min_row_bytes() + ((i % 16) * 4 + 2) * 4;
+        assert_eq!(pixels[at..at + 4], raster.pixels()[at..at + 4], "draw {i}");
+    }
+}
+
+#[test]
+fn large_path() {
+    let info = ImageInfo::new_wh(64, 64);
+    let mut wgpu = Wgpu::new(info);
+    let mut raster = Raster::new(info);
+
+    // circles far beyond 65535 vertices once tessellated
+    let circle = |cx: f32, cy: f32| {
+        let mut path = Path::new();
+        let n = 100_000;
+        for i in 0..n {
+            let angle = i as f32 / n as f32 * std::f32::consts::TAU;
+            let p = point(cx + 12. * angle.cos(), cy + 12. * angle.sin());
+            if i == 0 {
+                path.move_to(p);
+            } else {
+                path.line_to(p);
+            }
+        }
+        path.close();
+        path
+    };
+    let (disc, ring) = (circle(16., 32.), circle(48., 32.));
+
+    let mut fill = Paint::new();
+    fill.set_color(Color::RED);
+    let mut stroke = Paint::new();
+    stroke.set_color(Color::GREEN);
+    stroke.set_style(PaintStyle::Stroke(4.));
+
+    wgpu.clear(Color::BLACK);
+    raster.clear(Color::BLACK);
+    for device in [&mut wgpu as &mut dyn Device, &mut raster] {
+        device.draw_path(&disc, &fill).unwrap();
+        device.draw_path(&ring, &stroke).unwrap();
+    }
+
+    let mut pixels = vec![0; info.min_row_bytes() * 64];
+    let mut dst = PixmapMut::new(info, &mut pixels, info.min_row_bytes()).unwrap();
+    assert!(wgpu.read_pixels(&mut dst, 0, 0).unwrap());
+
+    for (x, y) in [(16, 32), (2, 32), (48, 32), (60, 32), (48, 20), (62, 62)] {
+        let at = y * info.min_row_bytes() + x * 4;
+        assert_eq!(
+            pixels[at..at + 4],
+            raster.pixels()[at..at + 4],
+            "({x}, {y})"
+        );
+    }
+}
+
+#[test]
+fn draw_image() {
+    let info = ImageInfo::new_wh(64, 64);
+    let mut wgpu = Wgpu::new(info);
+    let mut raster = Raster::new(info);
+
+    let size = 16;
+    let pixels = (0..size * size)
+        .flat_map(|i| {
+            let (x, y) = (i % size, i / size);
+            [(x * 16) as u8, (y * 16) as u8, ((x ^ y) * 16) as u8, 255]
+        })
+        .collect();
+    let image = Image::from_pixels(ImageInfo::new_wh(size, size), pixels).unwrap();
+    let src = Rect::from_ltrb(2., 2., 14., 14.);
+    let paint = Paint::new();
+
+    let samplings = [
+        SamplingOptions::nearest(),
+        SamplingOptions::linear(),
+        SamplingOptions::cubic(CubicResampler::MITCHELL),
+        SamplingOptions::cubic(CubicResampler::CATMULL_ROM),
+    ];
+    wgpu.clear(Color::BLACK);
+    raster.clear(Color::BLACK);
+    for device in [&mut wgpu as &mut dyn Device, &mut raster] {
+        for (i, sampling) in samplings.iter().enumerate() {
+            let (x, y) = ((i % 2 * 32) as f32, (i / 2 * 32) as f32);
+            device.set_matrix(Matrix::translate(x + 4., y + 2.).concat(&Matrix::rotate(10.)));
+            device
+                .draw_image_rect(
+                    &image,
+                    &src,
+                    &Rect::from_ltrb(0., 0., 25., 25.),
+                    sampling,
+                    &paint,
+                )
+                .unwrap();
+        }
+    }
+    compare(&mut wgpu, &raster, info);
+
+    // a linear blend between the first two mipmap levels
+    wgpu.clear(Color::BLACK);
+    raster.clear(Color::BLACK);
+    for device in [&mut wgpu as &mut dyn Device, &mut raster] {
+        device
+            .draw_image_rect(
+                &image,
+                &Rect::from_ltrb(0., 0., 16., 16.),
+                &Rect::from_ltrb(0., 0., 11., 11.),
+                &SamplingOptions::new(FilterMode::Linear, MipmapMode::Linear),
+                &paint,
+            )
+            .unwrap();
+    }
+    compare(&mut wgpu, &raster, info);
+}
+
+#[test]
+fn image_shader() {
+    let info = ImageInfo::new_wh(64, 64);
+    let mut wgpu = Wgpu::new(info);
+    let mut raster = Raster::new(info);
+
+    let size = 8;
+    let pixels = (0..size * size)
+        .flat_map(|i| {
+            let (x, y) = (i % size, i / size);
+            [(x * 32) as u8, (y * 32) as u8, 255 - (x * y * 4) as u8, 255]
+        })
+        .collect();
+    let image = Image::from_pixels(ImageInfo::new_wh(size, size), pixels).unwrap();
+
+    let tiles = [
+        (
+            TileMode::Repeat,
+            TileMode::Mirror,
+            SamplingOptions::nearest(),
+        ),
+        (
+            TileMode::Mirror,
+            TileMode::Repeat,
+            SamplingOptions::linear(),
+        ),
+        (
+            TileMode::Decal,
+            TileMode::Clamp,
+            SamplingOptions::cubic(CubicResampler::CATMULL_ROM),
+        ),
+        (
+            TileMode::Repeat,
+            TileMode::Decal,
+            SamplingOptions::new(FilterMode::Linear, MipmapMode::Linear),
+        ),
+    ];
+    wgpu.clear(Color::BLACK);
+    raster.clear(Color::BLACK);
+    for device in [&mut wgpu as &mut dyn Device, &mut raster] {
+        for (i, &(tile_x, tile_y, sampling)) in tiles.iter().enumerate() {
+            let (x, y) = ((i % 2 * 32) as f32, (i / 2 * 32) as f32);
+            let scale = if i == 3 { 0.7 } else { 1.5 };
+            let mut paint = Paint::new();
+            paint.set_shader(Some(Shader::image(
+                image.clone(),
+                tile_x,
+                tile_y,
+                sampling,
+                Matrix::translate(x + 12., y + 10.)
+                    .concat(&Matrix::rotate(20.))
+                    .concat(&Matrix::scale(scale, scale)),
+            )));
+
+            let mut path = Path::new();
+            path.add_rect(&Rect::from_ltrb(x + 1., y + 1., x + 31., y + 31.));
+            device.draw_path(&path, &paint).unwrap();
+        }
+    }
+    compare(&mut wgpu, &raster, info);
+}
+
+/// Every glyph but the first is a circle three tenths of an em wide, sitting on the baseline.
+#[derive(Debug)]
+struct Dots(f32);
+
+impl GlyphSource for Dots {
+    fn typeface_id(&self) -> u32 {
+        1
+    }
+
+    fn size(&self) -> f32 {
+        self.0
+    }
+
+    fn append_outline(&self, glyph: GlyphId, origin: Point, path: &mut Path) -> bool {
+        if glyph == GlyphId::NOTDEF {
+            return false;
+        }
+
+        let r = 0.3 * self.0;
+        path.add_circle(point(origin.x + r, origin.y - r), r);
+        true
+    }
+}
+
+#[test]
+fn glyph_run() {
+    let info = ImageInfo::new_wh(96, 48);
+    let mut wgpu = Wgpu::new(info);
+    let mut raster = Raster::new(info);
+
+    let font = Dots(20.);
+    let glyphs = [1, 0, 2, 1].map(GlyphId);
+    let positions = [
+        point(0., 0.),
+        point(10., 0.),
+        point(14.25, 0.),
+        point(27.5, 0.),
+    ];
+    let run = GlyphRun::new(&font, &glyphs, &positions);
+
+    let mut anti_aliased = Paint::new();
+    anti_aliased.anti_alias = true;
+    let mut gradient = anti_aliased.clone();
+    gradient.set_shader(Some(Shader::linear_gradient(
+        point(0., 0.),
+        point(96., 0.),
+        Gradient::evenly_spaced(&[Color::RED, Color::BLUE], TileMode::Clamp),
+    )));
+    gradient.set_blend_mode(BlendMode::Screen);
+
+    // both devices blend the same masks from their glyph caches
+    for (matrix, paint) in [
+        (Matrix::IDENTITY, Paint::new()),
+        (Matrix::IDENTITY, anti_aliased),
+        (Matrix::scale(2., 2.), gradient),
+    ] {
+        wgpu.clear(Color::GRAY);
+        raster.clear(Color::GRAY);
+        for device in [&mut wgpu as &mut dyn Device, &mut raster] {
+            device.set_matrix(matrix);
+            device.draw_glyph_run(&run, point(4., 20.), &paint).unwrap();
+        }
+        compare(&mut wgpu, &raster, info);
+    }
+}
+
+fn compare(wgpu: &mut Wgpu, raster: &Raster, info: ImageInfo) {
+    let mut pixels = vec![0; info.min_row_bytes() * info.height as usize];
+    let mut dst = PixmapMut::new(info, &mut pixels, info.min_row_bytes()).unwrap();
+    assert!(wgpu.read_pixels(&mut dst, 0, 0).unwrap());
+
+    for (i, (a, b)) in pixels.iter().zip(raster.pixels()).enumerate() {
+        let pixel = i / 4;
+        assert!(
+            a.abs_diff(*b) <= 2,
+            "({}, {}): {a} != {b}",
+            pixel % info.width as usize,
+            pixel / info.width as usize
+        );
+    }
+}
+
+#[test]
+fn invalid_image_info() {
+    let result = Wgpu::try_new(ImageInfo::new_wh(u32::MAX, 1));
+    assert!(matches!(result, Err(Error::InvalidImageInfo(_))));
+}
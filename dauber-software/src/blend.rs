@@ -0,0 +1,155 @@
+use dauber_core::blend_mode::BlendMode;
+
+/// Blend the premultiplied `src` with the premultiplied `dst`.
+pub(crate) fn blend(mode: BlendMode, src: [f32; 4], dst: [f32; 4]) -> [f32; 4] {
+    let (sa, da) = (src[3], dst[3]);
+
+    // the Porter-Duff modes only differ in how much of each side they keep
+    let porter_duff = |fs: f32, fd: f32| [0, 1, 2, 3].map(|i| src[i] * fs + dst[i] * fd);
+    let separable = |f: fn(f32, f32, f32, f32) -> f32| {
+        let [r, g, b] = [0, 1, 2].map(|i| f(src[i], dst[i], sa, da));
+        [r, g, b, sa + da - sa * da]
+    };
+
+    match mode {
+        BlendMode::Clear => [0.0; 4],
+        BlendMode::Src => src,
+        BlendMode::Dst => dst,
+        BlendMode::SrcOver => porter_duff(1.0, 1.0 - sa),
+        BlendMode::DstOver => porter_duff(1.0 - da, 1.0),
+        BlendMode::SrcIn => porter_duff(da, 0.0),
+        BlendMode::DstIn => porter_duff(0.0, sa),
+        BlendMode::SrcOut => porter_duff(1.0 - da, 0.0),
+        BlendMode::DstOut => porter_duff(0.0, 1.0 - sa),
+        BlendMode::SrcATop => porter_duff(da, 1.0 - sa),
+        BlendMode::DstATop => porter_duff(1.0 - da, sa),
+        BlendMode::Xor => porter_duff(1.0 - da, 1.0 - sa),
+        BlendMode::Plus => [0, 1, 2, 3].map(|i| (src[i] + dst[i]).min(1.0)),
+        BlendMode::Modulate => [0, 1, 2, 3].map(|i| src[i] * dst[i]),
+        BlendMode::Screen => [0, 1, 2, 3].map(|i| src[i] + dst[i] - src[i] * dst[i]),
+        BlendMode::Overlay => separable(|s, d, sa, da| hard_light(d, s, da, sa)),
+        BlendMode::Darken => separable(|s, d, sa, da| s + d - (s * da).max(d * sa)),
+        BlendMode::Lighten => separable(|s, d, sa, da| s + d - (s * da).min(d * sa)),
+        BlendMode::ColorDodge => separable(color_dodge),
+        BlendMode::ColorBurn => separable(color_burn),
+        BlendMode::HardLight => separable(hard_light),
+        BlendMode::SoftLight => separable(soft_light),
+        BlendMode::Difference => separable(|s, d, sa, da| s + d - 2.0 * (s * da).min(d * sa)),
+        BlendMode::Exclusion => separable(|s, d, _, _| s + d - 2.0 * s * d),
+        BlendMode::Multiply => separable(|s, d, sa, da| s * (1.0 - da) + d * (1.0 - sa) + s * d),
+        BlendMode::Hue | BlendMode::Saturation | BlendMode::Color | BlendMode::Luminosity => {
+            non_separable(mode, src, dst)
+        }
+    }
+}
+
+fn hard_light(s: f32, d: f32, sa: f32, da: f32) -> f32 {
+    let blended = if 2.0 * s <= sa {
+        2.0 * s * d
+    } else {
+        sa * da - 2.0 * (da - d) * (sa - s)
+    };
+
+    s * (1.0 - da) + d * (1.0 - sa) + blended
+}
+
+fn color_dodge(s: f32, d: f32, sa: f32, da: f32) -> f32 {
+    if d <= 0.0 {
+        s * (1.0 - da)
+    } else if s >= sa {
+        s + d * (1.0 - sa)
+    } else {
+        sa * da.min(d * sa / (sa - s)) + s * (1.0 - da) + d * (1.0 - sa)
+    }
+}
+
+fn color_burn(s: f32, d: f32, sa: f32, da: f32) -> f32 {
+    if d >= da {
+        d + s * (1.0 - da)
+    } else if s <= 0.0 {
+        d * (1.0 - sa)
+    } else {
+        sa * (da - da.min((da - d) * sa / s)) + s * (1.0 - da) + d * (1.0 - sa)
+    }
+}
+
+fn soft_light(s: f32, d: f32, sa: f32, da: f32) -> f32 {
+    let m = if da > 0.0 { d / da } else { 0.0 };
+    let s2 = 2.0 * s;
+
+    let blended = if s2 <= sa {
+        d * (sa + (s2 - sa) * (1.0 - m))
+    } else {
+        let m4 = 4.0 * m;
+        let f = if 4.0 * d <= da {
+            (m4 * m4 + m4) * (m - 1.0) + 7.0 * m
+        } else {
+            m.sqrt() - m
+        };
+        d * sa + da * (s2 - sa) * f
+    };
+
+    s * (1.0 - da) + d * (1.0 - sa) + blended
+}
+
+fn non_separable(mode: BlendMode, src: [f32; 4], dst: [f32; 4]) -> [f32; 4] {
+    let (sa, da) = (src[3], dst[3]);
+    let s = [src[0], src[1], src[2]];
+    let d = [dst[0], dst[1], dst[2]];
+    let scale = |c: [f32; 3], a: f32| c.map(|v| v * a);
+
+    // both sides scaled to the same alpha, sa * da
+    let blended = match mode {
+        BlendMode::Hue => set_lum(set_sat(scale(s, da), sat(d) * sa), lum(d) * sa),
+        BlendMode::Saturation => set_lum(set_sat(scale(d, sa), sat(s) * da), lum(d) * sa),
+        BlendMode::Color => set_lum(scale(s, da), lum(d) * sa),
+        _ => set_lum(scale(d, sa), lum(s) * da),
+    };
+    let blended = clip_color(blended, sa * da);
+
+    let [r, g, b] = [0, 1, 2].map(|i| s[i] * (1.0 - da) + d[i] * (1.0 - sa) + blended[i]);
+    [r, g, b, sa + da - sa * da]
+}
+
+#[inline]
+fn lum([r, g, b]: [f32; 3]) -> f32 {
+    0.3 * r + 0.59 * g + 0.11 * b
+}
+
+#[inline]
+fn sat([r, g, b]: [f32; 3]) -> f32 {
+    r.max(g).max(b) - r.min(g).min(b)
+}
+
+fn set_sat(c: [f32; 3], s: f32) -> [f32; 3] {
+    let min = c[0].min(c[1]).min(c[2]);
+    let range = sat(c);
+
+    if range > 0.0 {
+        c.map(|v| (v - min) * s / range)
+    } else {
+        [0.0; 3]
+    }
+}
+
+fn set_lum(c: [f32; 3], l: f32) -> [f32; 3] {
+    let diff = l - lum(c);
+    c.map(|v| v + diff)
+}
+
+/// Bring the channels back into 0..=`a` while keeping the luminosity.
+fn clip_color(c: [f32; 3], a: f32) -> [f32; 3] {
+    let l = lum(c);
+    let min = c[0].min(c[1]).min(c[2]);
+    let max = c[0].max(c[1]).max(c[2]);
+
+    c.map(|mut v| {
+        if min < 0.0 && l - min > 0.0 {
+            v = l + (v - l) * l / (l - min);
+        }
+        if max > a && max - l > 0.0 {
+            v = l + (v - l) * (a - l) / (max - l);
+        }
+        v.max(0.0)
+    })
+}
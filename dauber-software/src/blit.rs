@@ -0,0 +1,152 @@
+use crate::blend::blend;
+use crate::mask::{mul_div_255, Mask};
+use crate::sampler::ImageContext;
+use crate::shader::ShaderContext;
+use dauber_core::blend_mode::BlendMode;
+use dauber_core::color::Color;
+use dauber_core::image_info::{AlphaType, ColorType, ImageInfo};
+use dauber_core::pixel;
+
+/// Receives the pixels covered by a scan converted shape.
+pub(crate) trait Blitter {
+    /// Fully cover `width` pixels starting at (`x`, `y`).
+    fn blit_h(&mut self, x: u32, y: u32, width: u32);
+
+    /// Partially cover the pixels starting at (`x`, `y`), one coverage value per pixel.
+    fn blit_anti_h(&mut self, x: u32, y: u32, coverage: &[u8]);
+}
+
+/// Where the colors of a [`PaintBlitter`] come from.
+pub(crate) enum Source<'a> {
+    /// premultiplied
+    Solid([f32; 4]),
+    Shader(&'a ShaderContext),
+    Image(&'a ImageContext<'a>),
+}
+
+/// Blends a paint into pixels of any color type.
+pub(crate) struct PaintBlitter<'a> {
+    pixels: &'a mut [u8],
+    info: ImageInfo,
+    source: Source<'a>,
+    blend_mode: BlendMode,
+    clip: Option<&'a Mask>,
+    /// Shaded colors of the current row.
+    row: Vec<[f32; 4]>,
+}
+
+impl<'a> PaintBlitter<'a> {
+    pub fn new(
+        pixels: &'a mut [u8],
+        info: ImageInfo,
+        source: Source<'a>,
+        blend_mode: BlendMode,
+        clip: Option<&'a Mask>,
+    ) -> Self {
+        Self {
+            pixels,
+            info,
+            source,
+            blend_mode,
+            clip,
+            row: vec![],
+        }
+    }
+}
+
+impl PaintBlitter<'_> {
+    fn blend_row(&mut self, x: u32, y: u32, len: usize, coverage: impl Fn(usize) -> u8) {
+        let ImageInfo {
+            width,
+            color_type,
+            alpha_type,
+            ..
+        } = self.info;
+        let bpp = color_type.bytes_per_pixel();
+        let start = (y * width + x) as usize * bpp;
+        let end = start + len * bpp;
+        // premultiplied RGBA8 is blended in place, everything else goes through floats
+        let direct = color_type == ColorType::Rgba8888 && alpha_type != AlphaType::Unpremul;
+        let clip = self.clip.map(|clip| clip.row(x, y, len));
+
+        match self.source {
+            Source::Solid(_) => {}
+            Source::Shader(shader) => {
+                self.row.resize(len, [0.0; 4]);
+                shader.shade_row(x, y, &mut self.row[..len]);
+            }
+            Source::Image(image) => {
+                self.row.resize(len, [0.0; 4]);
+                image.shade_row(x, y, &mut self.row[..len]);
+            }
+        }
+
+        for (i, px) in self.pixels[start..end].chunks_exact_mut(bpp).enumerate() {
+            let c = match clip {
+                Some(clip) => mul_div_255(coverage(i), clip[i]),
+                None => coverage(i),
+            };
+
+            let color = match self.source {
+                Source::Solid(color) => color,
+                Source::Shader(_) | Source::Image(_) => self.row[i],
+            };
+
+            match (c, self.blend_mode) {
+                (0, _) => {}
+                (u8::MAX, BlendMode::SrcOver) if direct => src_over(px, color),
+                (c, BlendMode::SrcOver) if direct => {
+                    src_over(px, color.map(|v| v * c as f32 * (1.0 / 255.0)))
+                }
+                (c, mode) => {
+                    let dst = pixel::load(color_type, alpha_type, px);
+                    let blended = blend_lerp(mode, color, dst, c);
+                    pixel::store(color_type, alpha_type, blended, px);
+                }
+            }
+        }
+    }
+}
+
+impl Blitter for PaintBlitter<'_> {
+    fn blit_h(&mut self, x: u32, y: u32, width: u32) {
+        self.blend_row(x, y, width as usize, |_| u8::MAX);
+    }
+
+    fn blit_anti_h(&mut self, x: u32, y: u32, coverage: &[u8]) {
+        self.blend_row(x, y, coverage.len(), |i| coverage[i]);
+    }
+}
+
+#[inline]
+pub(crate) fn premultiply(Color { r, g, b, a }: Color) -> [f32; 4] {
+    let a = a.clamp(0.0, 1.0);
+    [
+        r.clamp(0.0, 1.0) * a,
+        g.clamp(0.0, 1.0) * a,
+        b.clamp(0.0, 1.0) * a,
+        a,
+    ]
+}
+
+#[inline]
+fn src_over(dst: &mut [u8], src: [f32; 4]) {
+    let inv_a = 1.0 - src[3];
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d = to_u8(s + *d as f32 * (1.0 / 255.0) * inv_a);
+    }
+}
+
+/// Blend `src` into `dst`, partial coverage fading between the result and `dst`.
+#[inline]
+fn blend_lerp(mode: BlendMode, src: [f32; 4], dst: [f32; 4], coverage: u8) -> [f32; 4] {
+    let blended = blend(mode, src, dst);
+    let c = coverage as f32 * (1.0 / 255.0);
+
+    [0, 1, 2, 3].map(|i| dst[i] + (blended[i] - dst[i]) * c)
+}
+
+#[inline]
+fn to_u8(v: f32) -> u8 {
+    (v.clamp(0.0, 1.0) * 255.0 + 0.5) as u8
+}
@@ -0,0 +1,378 @@
+use crate::blit::{premultiply, Blitter, PaintBlitter, Source};
+use crate::edge::{EdgeList, TOLERANCE};
+use crate::glyph_cache::{self, CachedGlyph, GlyphCache, GlyphMask};
+use crate::mask::{Mask, MaskBlitter};
+use crate::sampler::ImageContext;
+use crate::scan::fill;
+use crate::shader::ShaderContext;
+use dauber_core::blend_mode::BlendMode;
+use dauber_core::clip::ClipOp;
+use dauber_core::color::Color;
+use dauber_core::device::{Device, DeviceFactory};
+use dauber_core::error::{Error, Result};
+use dauber_core::geom::matrix::Matrix;
+use dauber_core::geom::point::Point;
+use dauber_core::geom::rect::Rect;
+use dauber_core::glyph::GlyphRun;
+use dauber_core::image::Image;
+use dauber_core::image_info::{ColorSpace, ImageInfo};
+use dauber_core::paint::{Paint, PaintStyle, StrokeStyle};
+use dauber_core::path::{Path, PathFillType};
+use dauber_core::pixel;
+use dauber_core::pixmap::{Pixmap, PixmapMut};
+use dauber_core::sampling::SamplingOptions;
+use dauber_core::shader::{Shader, TileMode};
+use std::sync::Arc;
+
+/// A CPU device rendering into pixels of the color type of its [`ImageInfo`].
+#[derive(Debug)]
+pub struct Raster {
+    info: ImageInfo,
+    pixels: Vec<u8>,
+    state: State,
+    saved: Vec<State>,
+    glyphs: GlyphCache,
+}
+
+#[derive(Clone, Debug)]
+struct State {
+    matrix: Matrix,
+    /// `None` while nothing is clipped, shared with the saved states until modified.
+    clip: Option<Arc<Mask>>,
+}
+
+impl Raster {
+    pub fn clear(&mut self, color: Color) {
+        let ImageInfo {
+            color_type,
+            alpha_type,
+            ..
+        } = self.info;
+        let bpp = color_type.bytes_per_pixel();
+
+        let mut px = [0; 16];
+        pixel::store(color_type, alpha_type, self.premultiply(color), &mut px);
+
+        for dst in self.pixels.chunks_exact_mut(bpp) {
+            dst.copy_from_slice(&px[..bpp]);
+        }
+    }
+
+    /// Pixels laid out as described by [`Device::image_info`], row by row without padding.
+    #[inline]
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Encode the pixels as an unpremultiplied RGBA PNG, see [`Pixmap::encode_png`].
+    pub fn encode_to_png(&self) -> Result<Vec<u8>> {
+        Pixmap::new(self.info, &self.pixels, self.info.min_row_bytes())?.encode_png()
+    }
+
+    /// Premultiply `color` after converting it into the color space of the device.
+    fn premultiply(&self, color: Color) -> [f32; 4] {
+        premultiply(ColorSpace::Srgb.convert(color, self.info.color_space))
+    }
+
+    fn fill(&mut self, edges: &EdgeList, fill_type: PathFillType, paint: &Paint) {
+        self.with_source(paint, |raster, source| {
+            raster.blit(
+                edges,
+                fill_type,
+                paint.anti_alias,
+                source,
+                paint.blend_mode(),
+            );
+        });
+    }
+
+    /// Call `draw` with where the colors of `paint` come from, unless it draws nothing.
+    fn with_source(&mut self, paint: &Paint, draw: impl FnOnce(&mut Self, Source<'_>)) {
+        let color_space = self.info.color_space;
+        let color = paint.color();
+
+        let (context, image);
+        let source = match paint.shader() {
+            None => Source::Solid(self.premultiply(color)),
+            Some(Shader::Image {
+                image: pixels,
+                tile_x,
+                tile_y,
+                sampling,
+                local_matrix,
+            }) => {
+                let bounds = Rect::from_ltrb(0., 0., pixels.width() as f32, pixels.height() as f32);
+                match ImageContext::new(
+                    pixels,
+                    bounds,
+                    [*tile_x, *tile_y],
+                    &self.state.matrix.concat(local_matrix),
+                    sampling,
+                    color.a,
+                    color_space,
+                ) {
+                    Some(context) => {
+                        image = context;
+                        Source::Image(&image)
+                    }
+                    None => return,
+                }
+            }
+            Some(shader) => match shader.degenerate_color() {
+                Some(c) => Source::Solid(self.premultiply(c.with_a(c.a * color.a))),
+                None => {
+                    match ShaderContext::new(shader, &self.state.matrix, color.a, color_space) {
+                        Some(shader) => {
+                            context = shader;
+                            Source::Shader(&context)
+                        }
+                        None => return,
+                    }
+                }
+            },
+        };
+
+        draw(self, source);
+    }
+
+    fn blit(
+        &mut self,
+        edges: &EdgeList,
+        fill_type: PathFillType,
+        anti_alias: bool,
+        source: Source<'_>,
+        blend_mode: BlendMode,
+    ) {
+        let ImageInfo { width, height, .. } = self.info;
+
+        let mut blitter = PaintBlitter::new(
+            &mut self.pixels,
+            self.info,
+            source,
+            blend_mode,
+            self.state.clip.as_deref(),
+        );
+        fill(edges, fill_type, anti_alias, width, height, &mut blitter);
+    }
+
+    /// Blend `source` through glyph masks of the cache.
+    fn blit_masks(&mut self, masks: &[GlyphMask], source: Source<'_>, blend_mode: BlendMode) {
+        let (width, height) = (self.info.width as i32, self.info.height as i32);
+
+        let mut blitter = PaintBlitter::new(
+            &mut self.pixels,
+            self.info,
+            source,
+            blend_mode,
+            self.state.clip.as_deref(),
+        );
+        for &GlyphMask { left, top, atlas } in masks {
+            let (l, r) = (left.max(0), (left + atlas.width as i32).min(width));
+            if l >= r {
+                continue;
+            }
+
+            for y in 0..atlas.height {
+                let dy = top + y as i32;
+                if (0..height).contains(&dy) {
+                    let row = &self.glyphs.row(&atlas, y)[(l - left) as usize..(r - left) as usize];
+                    blitter.blit_anti_h(l as u32, dy as u32, row);
+                }
+            }
+        }
+    }
+}
+
+impl DeviceFactory for Raster {
+    fn try_new(info: ImageInfo) -> Result<Self> {
+        let len = (info.width as usize)
+            .checked_mul(info.height as usize)
+            .and_then(|len| len.checked_mul(info.bytes_per_pixel()))
+            .filter(|&len| len > 0 && len <= isize::MAX as usize)
+            .ok_or(Error::InvalidImageInfo(info))?;
+
+        Ok(Self {
+            info,
+            pixels: vec![0; len],
+            state: State {
+                matrix: Matrix::IDENTITY,
+                clip: None,
+            },
+            saved: vec![],
+            glyphs: GlyphCache::default(),
+        })
+    }
+}
+
+impl Device for Raster {
+    fn image_info(&self) -> &ImageInfo {
+        &self.info
+    }
+
+    fn matrix(&self) -> &Matrix {
+        &self.state.matrix
+    }
+
+    fn set_matrix(&mut self, matrix: Matrix) {
+        self.state.matrix = matrix;
+    }
+
+    fn save(&mut self) {
+        self.saved.push(self.state.clone());
+    }
+
+    fn restore(&mut self) {
+        if let Some(state) = self.saved.pop() {
+            self.state = state;
+        }
+    }
+
+    fn save_count(&self) -> usize {
+        self.saved.len() + 1
+    }
+
+    fn clip_path(&mut self, path: &Path, op: ClipOp, anti_alias: bool) -> Result<()> {
+        let ImageInfo { width, height, .. } = self.info;
+
+        let path = path.transformed(&self.state.matrix);
+        let mut coverage = Mask::new(width, height, 0);
+        fill(
+            &EdgeList::from_path(&path),
+            path.fill_type(),
+            anti_alias,
+            width,
+            height,
+            &mut MaskBlitter {
+                mask: &mut coverage,
+            },
+        );
+
+        let clip = self
+            .state
+            .clip
+            .get_or_insert_with(|| Arc::new(Mask::new(width, height, u8::MAX)));
+        Arc::make_mut(clip).clip(&coverage, op);
+
+        Ok(())
+    }
+
+    fn draw_path(&mut self, path: &Path, paint: &Paint) -> Result<()> {
+        let effected;
+        let path = match paint.path_effect() {
+            Some(effect) => {
+                effected = effect.apply(path);
+                &effected
+            }
+            None => path,
+        };
+
+        let matrix = self.state.matrix;
+
+        if let PaintStyle::Fill | PaintStyle::FillAndStroke(_) = paint.style() {
+            let edges = EdgeList::from_path(&path.transformed(&matrix));
+            self.fill(&edges, path.fill_type(), paint);
+        }
+
+        if let Some(style) = paint.stroke_style() {
+            let edges = stroke_edges(path, &style, &matrix);
+            self.fill(&edges, PathFillType::Winding, paint);
+        }
+
+        Ok(())
+    }
+
+    /// Filled glyphs are blitted from coverage masks rasterized once and cached,
+    /// other paints and transforms outline the glyphs.
+    fn draw_glyph_run(&mut self, run: &GlyphRun<'_>, origin: Point, paint: &Paint) -> Result<()> {
+        let font = run.font();
+        let matrix = self.state.matrix;
+        let Some(scale) = glyph_cache::mask_scale(font, &matrix, paint) else {
+            return self.draw_path(&run.to_path(origin), paint);
+        };
+
+        // glyphs that do not fit into the atlas are drawn as paths
+        let mut masks = vec![];
+        let mut path = Path::new();
+        for (&glyph, p) in run.glyphs().iter().zip(run.positions()) {
+            let p = Point::new(origin.x + p.x, origin.y + p.y);
+            match self
+                .glyphs
+                .get(font, glyph, scale, matrix.map_point(p), paint.anti_alias)
+            {
+                CachedGlyph::Mask(mask) => masks.push(mask),
+                CachedGlyph::Empty => {}
+                CachedGlyph::Full => {
+                    font.append_outline(glyph, p, &mut path);
+                }
+            }
+        }
+
+        self.with_source(paint, |raster, source| {
+            raster.blit_masks(&masks, source, paint.blend_mode());
+        });
+        self.glyphs.unpin();
+
+        if !path.is_empty() {
+            self.draw_path(&path, paint)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_image_rect(
+        &mut self,
+        image: &Image,
+        src: &Rect,
+        dst: &Rect,
+        sampling: &SamplingOptions,
+        paint: &Paint,
+    ) -> Result<()> {
+        let bounds = Rect::from_ltrb(0., 0., image.width() as f32, image.height() as f32);
+        let (Some(to_dst), Some(clipped)) =
+            (Matrix::rect_to_rect(src, dst), src.intersect(&bounds))
+        else {
+            return Ok(());
+        };
+
+        let matrix = self.state.matrix.concat(&to_dst);
+        let Some(context) = ImageContext::new(
+            image,
+            clipped,
+            [TileMode::Clamp; 2],
+            &matrix,
+            sampling,
+            paint.color().a,
+            self.info.color_space,
+        ) else {
+            return Ok(());
+        };
+
+        let mut path = Path::new();
+        path.add_rect(&clipped);
+        let edges = EdgeList::from_path(&path.transformed(&matrix));
+        self.blit(
+            &edges,
+            PathFillType::Winding,
+            paint.anti_alias,
+            Source::Image(&context),
+            paint.blend_mode(),
+        );
+
+        Ok(())
+    }
+
+    fn read_pixels(&mut self, dst: &mut PixmapMut<'_>, src_x: i32, src_y: i32) -> Result<bool> {
+        let src = Pixmap::new(self.info, &self.pixels, self.info.min_row_bytes())?;
+
+        Ok(src.read_pixels(dst, src_x, src_y))
+    }
+}
+
+/// Outline a stroke as a path to fill.
+///
+/// The stroke is built in local coordinates so `matrix` transforms its width as well.
+fn stroke_edges(path: &Path, style: &StrokeStyle, matrix: &Matrix) -> EdgeList {
+    let outline = path.stroke(style, TOLERANCE / matrix.max_scale().max(f32::EPSILON));
+
+    EdgeList::from_path(&outline.transformed(matrix))
+}
@@ -0,0 +1,120 @@
+use dauber_core::geom::point::Point;
+use dauber_core::path::{Path, PathSegment};
+
+/// Maximum distance between a curve and its flattened approximation, in pixels.
+pub(crate) const TOLERANCE: f32 = 0.1;
+
+/// A line segment with `y0 < y1`; `winding` remembers the original direction.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Edge {
+    pub x0: f32,
+    pub y0: f32,
+    pub x1: f32,
+    pub y1: f32,
+    pub winding: i32,
+}
+
+impl Edge {
+    /// The x coordinate where the edge crosses the horizontal line at `y`.
+    #[inline]
+    pub fn x_at(&self, y: f32) -> f32 {
+        self.x0 + (y - self.y0) * (self.x1 - self.x0) / (self.y1 - self.y0)
+    }
+}
+
+/// Closed polygons ready to be scan converted.
+#[derive(Default)]
+pub(crate) struct EdgeList {
+    pub edges: Vec<Edge>,
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+impl EdgeList {
+    pub fn new() -> Self {
+        Self {
+            edges: vec![],
+            min_x: f32::INFINITY,
+            min_y: f32::INFINITY,
+            max_x: f32::NEG_INFINITY,
+            max_y: f32::NEG_INFINITY,
+        }
+    }
+
+    /// Flatten `path`, implicitly closing every sub path.
+    pub fn from_path(path: &Path) -> Self {
+        let mut list = Self::new();
+
+        let mut first = None::<Point>;
+        let mut last = Point::default();
+
+        for seg in path.flatten(TOLERANCE) {
+            match seg {
+                PathSegment::Move { to } => {
+                    if let Some(first) = first {
+                        list.push_line(last, first);
+                    }
+                    first = Some(to);
+                    last = to;
+                }
+                PathSegment::Line { from, to } => {
+                    if first.is_none() {
+                        first = Some(from);
+                    }
+                    list.push_line(from, to);
+                    last = to;
+                }
+                PathSegment::Close => {
+                    if let Some(first) = first.take() {
+                        list.push_line(last, first);
+                        last = first;
+                    }
+                }
+                _ => unreachable!("flattened paths only contain lines"),
+            }
+        }
+
+        if let Some(first) = first {
+            list.push_line(last, first);
+        }
+
+        list
+    }
+
+    pub fn push_line(&mut self, from: Point, to: Point) {
+        if from.y == to.y || ![from.x, from.y, to.x, to.y].iter().all(|v| v.is_finite()) {
+            return;
+        }
+
+        let edge = if from.y < to.y {
+            Edge {
+                x0: from.x,
+                y0: from.y,
+                x1: to.x,
+                y1: to.y,
+                winding: 1,
+            }
+        } else {
+            Edge {
+                x0: to.x,
+                y0: to.y,
+                x1: from.x,
+                y1: from.y,
+                winding: -1,
+            }
+        };
+
+        self.min_x = self.min_x.min(edge.x0.min(edge.x1));
+        self.max_x = self.max_x.max(edge.x0.max(edge.x1));
+        self.min_y = self.min_y.min(edge.y0);
+        self.max_y = self.max_y.max(edge.y1);
+        self.edges.push(edge);
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+}
@@ -0,0 +1,478 @@
+//! Glyph coverage rasterized once and packed into an atlas, shared by the devices.
+
+use crate::edge::EdgeList;
+use crate::mask::{Mask, MaskBlitter};
+use crate::scan::fill;
+use dauber_core::geom::matrix::Matrix;
+use dauber_core::geom::point::Point;
+use dauber_core::glyph::{GlyphId, GlyphSource};
+use dauber_core::paint::{Paint, PaintStyle};
+use dauber_core::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::Range;
+
+/// Glyphs larger than this many device pixels per em are drawn as paths.
+pub const MAX_GLYPH_SIZE: f32 = 256.0;
+
+/// Positions along x within a pixel glyphs are rasterized at.
+const SUBPIXELS: f32 = 4.0;
+
+/// Side length of the atlas of [`GlyphCache::default`].
+const DEFAULT_ATLAS_SIZE: u32 = 1024;
+
+/// The scale glyphs of `font` drawn with `matrix` and `paint` are cached at,
+/// `None` if they have to be drawn as paths.
+///
+/// Masks only hold fills, scaled the same along both axes without rotation.
+pub fn mask_scale(font: &dyn GlyphSource, matrix: &Matrix, paint: &Paint) -> Option<f32> {
+    let scale = matrix.sx;
+    let axis_aligned = matrix.kx == 0.
+        && matrix.ky == 0.
+        && matrix.sy == scale
+        && scale > 0.
+        && !matrix.has_perspective();
+    let fill = matches!(paint.style(), PaintStyle::Fill) && paint.path_effect().is_none();
+    let size = font.size() * scale;
+
+    (axis_aligned && fill && size > 0. && size <= MAX_GLYPH_SIZE).then_some(scale)
+}
+
+/// A rectangle of the atlas, in pixels.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AtlasRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The coverage of a glyph in the atlas and the device pixel its top left corner lands on.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GlyphMask {
+    pub left: i32,
+    pub top: i32,
+    pub atlas: AtlasRect,
+}
+
+/// What [`GlyphCache::get`] found for a glyph.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CachedGlyph {
+    Mask(GlyphMask),
+    /// The glyph covers nothing, like a space.
+    Empty,
+    /// There is no room for the glyph, even after evicting the glyphs not used since
+    /// the last [`unpin`](GlyphCache::unpin) that could make some.
+    Full,
+}
+
+/// Coverage masks of glyphs packed into a single channel atlas.
+///
+/// Glyphs are keyed by typeface, glyph id, size in device pixels, a quarter pixel
+/// offset along x and whether they are anti-aliased. When the atlas runs out of room
+/// the least recently used glyphs on shelves tall enough for the new one are evicted,
+/// or the whole atlas is cleared if that is not enough. Glyphs used since the last
+/// [`unpin`](Self::unpin) are never evicted, a device may still have to draw them.
+pub struct GlyphCache {
+    width: u32,
+    height: u32,
+    /// Allocated with the first glyph.
+    data: Vec<u8>,
+    /// Rows of glyphs of one height at most, from top to bottom.
+    shelves: Vec<Shelf>,
+    glyphs: HashMap<GlyphKey, Entry>,
+    /// The glyphs by when they were last used, least recently used first.
+    lru: BTreeMap<u64, GlyphKey>,
+    /// Counts every use of a glyph.
+    clock: u64,
+    /// Glyphs used after this are pinned.
+    pinned_after: u64,
+    /// Written since the last [`take_dirty`](Self::take_dirty), as left, top, right and bottom.
+    dirty: Option<[u32; 4]>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    typeface: u32,
+    glyph: GlyphId,
+    /// Bits of the size in device pixels per em.
+    size: u32,
+    subpixel: u8,
+    anti_alias: bool,
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// `None` for glyphs covering nothing.
+    mask: Option<Slot>,
+    used: u64,
+}
+
+/// A glyph in the atlas, placed relative to the pixel its origin is rounded to.
+#[derive(Copy, Clone, Debug)]
+struct Slot {
+    left: i32,
+    top: i32,
+    atlas: AtlasRect,
+}
+
+#[derive(Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    /// Unused spans along x, sorted and never touching.
+    free: Vec<Range<u32>>,
+}
+
+impl GlyphCache {
+    /// An empty cache with an atlas of `width` by `height` pixels.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![],
+            shelves: vec![],
+            glyphs: HashMap::new(),
+            lru: BTreeMap::new(),
+            clock: 0,
+            pinned_after: 0,
+            dirty: None,
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Coverage of the whole atlas, row by row without padding.
+    ///
+    /// Empty until the first glyph is cached.
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Row `y` of `rect`.
+    #[inline]
+    pub fn row(&self, rect: &AtlasRect, y: u32) -> &[u8] {
+        let start = ((rect.y + y) * self.width + rect.x) as usize;
+        &self.data[start..start + rect.width as usize]
+    }
+
+    /// Number of glyphs in the cache, including those covering nothing.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    /// The mask of `glyph` drawn with its origin at `position` in device pixels,
+    /// rasterizing it unless it is cached.
+    ///
+    /// `scale` maps the pixels of `font` to device pixels. The origin is rounded to a
+    /// quarter pixel along x and a whole pixel along y. The glyph stays pinned until
+    /// the next [`unpin`](Self::unpin).
+    pub fn get(
+        &mut self,
+        font: &dyn GlyphSource,
+        glyph: GlyphId,
+        scale: f32,
+        position: Point,
+        anti_alias: bool,
+    ) -> CachedGlyph {
+        let quarters = (position.x * SUBPIXELS).round();
+        let x = (quarters / SUBPIXELS).floor();
+        let subpixel = (quarters - x * SUBPIXELS) as u8;
+        let y = position.y.round();
+
+        let key = GlyphKey {
+            typeface: font.typeface_id(),
+            glyph,
+            size: (font.size() * scale).to_bits(),
+            subpixel,
+            anti_alias,
+        };
+
+        self.clock += 1;
+        let slot = match self.glyphs.get_mut(&key) {
+            Some(entry) => {
+                self.lru.remove(&entry.used);
+                self.lru.insert(self.clock, key);
+                entry.used = self.clock;
+                entry.mask
+            }
+            None => match self.insert(key, font, scale) {
+                Some(mask) => mask,
+                None => return CachedGlyph::Full,
+            },
+        };
+
+        match slot {
+            Some(Slot { left, top, atlas }) => CachedGlyph::Mask(GlyphMask {
+                left: x as i32 + left,
+                top: y as i32 + top,
+                atlas,
+            }),
+            None => CachedGlyph::Empty,
+        }
+    }
+
+    /// Allow every glyph to be evicted again, once the masks handed out are drawn.
+    #[inline]
+    pub fn unpin(&mut self) {
+        self.pinned_after = self.clock;
+    }
+
+    /// The part of the atlas written since the last call, `None` if nothing was.
+    pub fn take_dirty(&mut self) -> Option<AtlasRect> {
+        self.dirty.take().map(|[l, t, r, b]| AtlasRect {
+            x: l,
+            y: t,
+            width: r - l,
+            height: b - t,
+        })
+    }
+
+    /// Rasterize the glyph of `key` and store it, `None` if it does not fit.
+    fn insert(
+        &mut self,
+        key: GlyphKey,
+        font: &dyn GlyphSource,
+        scale: f32,
+    ) -> Option<Option<Slot>> {
+        let mut path = Path::new();
+        font.append_outline(key.glyph, Point::new(0., 0.), &mut path);
+        let subpixel = key.subpixel as f32 / SUBPIXELS;
+        path.transform(&Matrix::from_row(scale, 0., subpixel, 0., scale, 0.));
+
+        let edges = EdgeList::from_path(&path);
+        let mask = if edges.is_empty() {
+            None
+        } else {
+            let left = edges.min_x.floor();
+            let top = edges.min_y.floor();
+            let width = (edges.max_x.ceil() - left) as u32;
+            let height = (edges.max_y.ceil() - top) as u32;
+            let atlas = self.allocate(width.max(1), height.max(1))?;
+
+            let mut coverage = Mask::new(atlas.width, atlas.height, 0);
+            path.transform(&Matrix::translate(-left, -top));
+            fill(
+                &EdgeList::from_path(&path),
+                path.fill_type(),
+                key.anti_alias,
+                atlas.width,
+                atlas.height,
+                &mut MaskBlitter {
+                    mask: &mut coverage,
+                },
+            );
+            self.write(&atlas, &coverage);
+
+            Some(Slot {
+                left: left as i32,
+                top: top as i32,
+                atlas,
+            })
+        };
+
+        self.glyphs.insert(
+            key,
+            Entry {
+                mask,
+                used: self.clock,
+            },
+        );
+        self.lru.insert(self.clock, key);
+
+        Some(mask)
+    }
+
+    /// Find room for a mask, evicting the least recently used glyphs if needed.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<AtlasRect> {
+        if width > self.width || height > self.height {
+            return None;
+        }
+        if let Some(rect) = self.pack(width, height) {
+            return Some(rect);
+        }
+
+        // shelves keep their height, only masks on shelves at least as tall make room
+        let mut from = 0;
+        while let Some((used, key)) = self
+            .lru
+            .range(from..=self.pinned_after)
+            .map(|(&used, &key)| (used, key))
+            .find(|(_, key)| match self.glyphs[key].mask {
+                Some(slot) => self.shelf_height(slot.atlas.y) >= height,
+                // masks covering nothing are free to drop on the way
+                None => true,
+            })
+        {
+            from = used + 1;
+            self.lru.remove(&used);
+            let entry = self
+                .glyphs
+                .remove(&key)
+                .expect("the order holds cached glyphs");
+            let Some(slot) = entry.mask else {
+                continue;
+            };
+
+            self.release(&slot.atlas);
+            if let Some(rect) = self.pack(width, height) {
+                return Some(rect);
+            }
+        }
+
+        // start over with an empty atlas unless a glyph is pinned
+        let pinned = self
+            .lru
+            .last_key_value()
+            .is_some_and(|(&used, _)| used > self.pinned_after);
+        if pinned {
+            return None;
+        }
+        self.glyphs.clear();
+        self.lru.clear();
+        self.shelves.clear();
+
+        self.pack(width, height)
+    }
+
+    /// The height of the shelf at `y`.
+    fn shelf_height(&self, y: u32) -> u32 {
+        let i = self.shelves.partition_point(|shelf| shelf.y < y);
+        self.shelves[i].height
+    }
+
+    /// Place a mask on the lowest shelf it fits on, opening a new shelf if there is none.
+    fn pack(&mut self, width: u32, height: u32) -> Option<AtlasRect> {
+        let shelf = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= height)
+            .filter(|shelf| shelf.free.iter().any(|span| span.len() as u32 >= width))
+            .min_by_key(|shelf| shelf.height);
+
+        let shelf = match shelf {
+            Some(shelf) => shelf,
+            None => {
+                let y = self
+                    .shelves
+                    .last()
+                    .map_or(0, |shelf| shelf.y + shelf.height);
+                // a little headroom lets glyphs of similar sizes share the shelf
+                let shelf_height = height
+                    .next_multiple_of(4)
+                    .min(self.height - y.min(self.height));
+                if shelf_height < height {
+                    return None;
+                }
+
+                let row = 0..self.width;
+                self.shelves.push(Shelf {
+                    y,
+                    height: shelf_height,
+                    free: vec![row],
+                });
+                self.shelves.last_mut().expect("a shelf was just added")
+            }
+        };
+
+        let i = shelf
+            .free
+            .iter()
+            .position(|span| span.len() as u32 >= width)
+            .expect("the shelf has room");
+        let x = shelf.free[i].start;
+        shelf.free[i].start += width;
+        if shelf.free[i].is_empty() {
+            shelf.free.remove(i);
+        }
+
+        Some(AtlasRect {
+            x,
+            y: shelf.y,
+            width,
+            height,
+        })
+    }
+
+    /// Return the room of an evicted mask to its shelf, dropping empty shelves at the bottom.
+    fn release(&mut self, rect: &AtlasRect) {
+        let shelf = self
+            .shelves
+            .iter_mut()
+            .find(|shelf| shelf.y == rect.y)
+            .expect("masks lie on a shelf");
+
+        let span = rect.x..rect.x + rect.width;
+        let i = shelf.free.partition_point(|free| free.end <= span.start);
+        shelf.free.insert(i, span);
+
+        // merge with the spans on either side
+        if i + 1 < shelf.free.len() && shelf.free[i].end == shelf.free[i + 1].start {
+            shelf.free[i].end = shelf.free.remove(i + 1).end;
+        }
+        if i > 0 && shelf.free[i - 1].end == shelf.free[i].start {
+            shelf.free[i - 1].end = shelf.free.remove(i).end;
+        }
+
+        let width = self.width;
+        while self
+            .shelves
+            .last()
+            .is_some_and(|shelf| matches!(shelf.free[..], [ref row] if *row == (0..width)))
+        {
+            self.shelves.pop();
+        }
+    }
+
+    fn write(&mut self, rect: &AtlasRect, coverage: &Mask) {
+        if self.data.is_empty() {
+            self.data = vec![0; self.width as usize * self.height as usize];
+        }
+
+        for y in 0..rect.height {
+            let start = ((rect.y + y) * self.width + rect.x) as usize;
+            self.data[start..start + rect.width as usize].copy_from_slice(coverage.row(
+                0,
+                y,
+                rect.width as usize,
+            ));
+        }
+
+        let [l, t, r, b] = [rect.x, rect.y, rect.x + rect.width, rect.y + rect.height];
+        self.dirty = Some(match self.dirty {
+            Some([dl, dt, dr, db]) => [dl.min(l), dt.min(t), dr.max(r), db.max(b)],
+            None => [l, t, r, b],
+        });
+    }
+}
+
+impl Default for GlyphCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_ATLAS_SIZE, DEFAULT_ATLAS_SIZE)
+    }
+}
+
+impl fmt::Debug for GlyphCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GlyphCache")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("glyphs", &self.glyphs.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -1 +1,10 @@
+pub mod device;
+pub mod glyph_cache;
 
+mod blend;
+mod blit;
+mod edge;
+mod mask;
+mod sampler;
+mod scan;
+mod shader;
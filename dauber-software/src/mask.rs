@@ -0,0 +1,59 @@
+use crate::blit::Blitter;
+use dauber_core::clip::ClipOp;
+
+/// One coverage value per pixel.
+#[derive(Clone, Debug)]
+pub(crate) struct Mask {
+    width: u32,
+    data: Vec<u8>,
+}
+
+impl Mask {
+    pub fn new(width: u32, height: u32, coverage: u8) -> Self {
+        Self {
+            width,
+            data: vec![coverage; width as usize * height as usize],
+        }
+    }
+
+    #[inline]
+    pub fn row(&self, x: u32, y: u32, len: usize) -> &[u8] {
+        let start = (y * self.width + x) as usize;
+        &self.data[start..start + len]
+    }
+
+    /// Combine with the coverage of another shape.
+    pub fn clip(&mut self, coverage: &Mask, op: ClipOp) {
+        for (d, &c) in self.data.iter_mut().zip(&coverage.data) {
+            let c = match op {
+                ClipOp::Intersect => c,
+                ClipOp::Difference => u8::MAX - c,
+            };
+            *d = mul_div_255(*d, c);
+        }
+    }
+}
+
+/// `a * b / 255`, rounded.
+#[inline]
+pub(crate) fn mul_div_255(a: u8, b: u8) -> u8 {
+    let v = a as u32 * b as u32 + 128;
+    ((v + (v >> 8)) >> 8) as u8
+}
+
+/// Records coverage into a mask.
+pub(crate) struct MaskBlitter<'a> {
+    pub mask: &'a mut Mask,
+}
+
+impl Blitter for MaskBlitter<'_> {
+    fn blit_h(&mut self, x: u32, y: u32, width: u32) {
+        let start = (y * self.mask.width + x) as usize;
+        self.mask.data[start..start + width as usize].fill(u8::MAX);
+    }
+
+    fn blit_anti_h(&mut self, x: u32, y: u32, coverage: &[u8]) {
+        let start = (y * self.mask.width + x) as usize;
+        self.mask.data[start..start + coverage.len()].copy_from_slice(coverage);
+    }
+}
@@ -0,0 +1,290 @@
+use dauber_core::geom::matrix::Matrix;
+use dauber_core::geom::point;
+use dauber_core::geom::point::Point;
+use dauber_core::geom::rect::Rect;
+use dauber_core::image::Image;
+use dauber_core::image_info::{ColorSpace, ImageInfo};
+use dauber_core::pixel;
+use dauber_core::pixmap::Pixmap;
+use dauber_core::sampling::{CubicResampler, FilterMode, SamplingOptions};
+use dauber_core::shader::TileMode;
+
+/// An image prepared for sampling at device pixels.
+pub(crate) struct ImageContext<'a> {
+    /// The mipmap level samples come from, and the next smaller one if they are blended.
+    level: Level<'a>,
+    next: Option<(Level<'a>, f32)>,
+    /// Maps device coordinates to pixels of the full image.
+    inverse: Matrix,
+    /// Samples stay within these bounds, in pixels of the full image.
+    bounds: Rect,
+    /// How the bounds repeat along x and y.
+    tile_modes: [TileMode; 2],
+    filter: Filter,
+    /// Alpha of the paint color.
+    alpha: f32,
+}
+
+enum Filter {
+    Nearest,
+    Linear,
+    Cubic(CubicResampler),
+}
+
+/// The pixels of an image or one of its mipmap levels, read as they are sampled.
+struct Level<'a> {
+    width: u32,
+    height: u32,
+    pixmap: Pixmap<'a>,
+    /// The color space of the device, pixels are converted to it when read.
+    color_space: ColorSpace,
+    /// Size of the level relative to the full image.
+    scale: (f32, f32),
+}
+
+impl<'a> ImageContext<'a> {
+    /// `None` when `matrix`, mapping image pixels to the device, cannot be inverted.
+    pub fn new(
+        image: &'a Image,
+        bounds: Rect,
+        tile_modes: [TileMode; 2],
+        matrix: &Matrix,
+        sampling: &SamplingOptions,
+        alpha: f32,
+        color_space: ColorSpace,
+    ) -> Option<Self> {
+        let inverse = matrix.invert()?;
+        // the mipmap is only built once a smaller level would be sampled
+        let mipmap = if sampling.level(&inverse, 1) > 0.0 {
+            image.mipmap()
+        } else {
+            &[]
+        };
+        let lod = sampling.level(&inverse, mipmap.len());
+
+        let level_at = |i: usize| {
+            let level = if i == 0 { image } else { &mipmap[i - 1] };
+            Level::new(level, image, color_space)
+        };
+        let i = lod.floor() as usize;
+        let weight = lod - lod.floor();
+
+        Some(Self {
+            level: level_at(i),
+            next: (weight > 0.0).then(|| (level_at(i + 1), weight)),
+            inverse,
+            bounds,
+            tile_modes,
+            filter: match (sampling.cubic, sampling.filter) {
+                (Some(cubic), _) => Filter::Cubic(cubic),
+                (None, FilterMode::Nearest) => Filter::Nearest,
+                (None, FilterMode::Linear) => Filter::Linear,
+            },
+            alpha: alpha.clamp(0.0, 1.0),
+        })
+    }
+
+    /// Premultiplied colors at the centers of the pixels starting at (`x`, `y`).
+    pub fn shade_row(&self, x: u32, y: u32, out: &mut [[f32; 4]]) {
+        for (i, color) in out.iter_mut().enumerate() {
+            let p = self
+                .inverse
+                .map_point(point((x + i as u32) as f32 + 0.5, y as f32 + 0.5));
+
+            let mut c = self.sample(&self.level, p);
+            if let Some((next, weight)) = &self.next {
+                let n = self.sample(next, p);
+                c = [0, 1, 2, 3].map(|i| c[i] + (n[i] - c[i]) * weight);
+            }
+
+            *color = c.map(|v| v * self.alpha);
+        }
+    }
+
+    /// The color at `p` in pixels of the full image.
+    fn sample(&self, level: &Level<'_>, p: Point) -> [f32; 4] {
+        let (sx, sy) = level.scale;
+        let [mode_x, mode_y] = self.tile_modes;
+        let x = Axis::new(
+            p.x * sx,
+            self.bounds.l * sx,
+            self.bounds.r * sx,
+            level.width,
+            mode_x,
+        );
+        let y = Axis::new(
+            p.y * sy,
+            self.bounds.t * sy,
+            self.bounds.b * sy,
+            level.height,
+            mode_y,
+        );
+
+        match self.filter {
+            Filter::Nearest => level.at(x.nearest(), y.nearest()).unwrap_or_default(),
+            Filter::Linear => {
+                let (x0, tx) = x.split();
+                let (y0, ty) = y.split();
+                let wx = [1.0 - tx, tx];
+                let wy = [1.0 - ty, ty];
+
+                level.weighted(
+                    |i, j| (x.tap(x0 + i as i32), y.tap(y0 + j as i32), wx[i] * wy[j]),
+                    2,
+                )
+            }
+            Filter::Cubic(cubic) => {
+                let (x0, tx) = x.split();
+                let (y0, ty) = y.split();
+                let wx = cubic.weights(tx);
+                let wy = cubic.weights(ty);
+
+                let [r, g, b, a] = level.weighted(
+                    |i, j| {
+                        (
+                            x.tap(x0 + i as i32 - 1),
+                            y.tap(y0 + j as i32 - 1),
+                            wx[i] * wy[j],
+                        )
+                    },
+                    4,
+                );
+                // the negative lobes can overshoot
+                let a = a.clamp(0.0, 1.0);
+                [r.clamp(0.0, a), g.clamp(0.0, a), b.clamp(0.0, a), a]
+            }
+        }
+    }
+}
+
+impl<'a> Level<'a> {
+    fn new(level: &'a Image, full: &Image, color_space: ColorSpace) -> Self {
+        let ImageInfo { width, height, .. } = *level.info();
+
+        Self {
+            width,
+            height,
+            pixmap: level.pixmap(),
+            color_space,
+            scale: (
+                width as f32 / full.width() as f32,
+                height as f32 / full.height() as f32,
+            ),
+        }
+    }
+
+    /// The premultiplied pixel at (`x`, `y`) in the color space of the device, `None` for
+    /// pixels left out by a decal.
+    fn at(&self, x: Option<u32>, y: Option<u32>) -> Option<[f32; 4]> {
+        let info = self.pixmap.info();
+        let bpp = info.bytes_per_pixel();
+        let at = y? as usize * self.pixmap.row_bytes() + x? as usize * bpp;
+        let color = pixel::load(
+            info.color_type,
+            info.alpha_type,
+            &self.pixmap.pixels()[at..at + bpp],
+        );
+
+        if info.color_space == self.color_space {
+            return Some(color);
+        }
+        let unpremul = pixel::unpremultiply(color).into();
+        let converted = info.color_space.convert(unpremul, self.color_space);
+        Some(pixel::premultiply(converted.to_array()))
+    }
+
+    /// Sum the `n`x`n` pixels `tap` picks with their weights.
+    fn weighted(
+        &self,
+        tap: impl Fn(usize, usize) -> (Option<u32>, Option<u32>, f32),
+        n: usize,
+    ) -> [f32; 4] {
+        let mut sum = [0.0; 4];
+        for j in 0..n {
+            for i in 0..n {
+                let (x, y, w) = tap(i, j);
+                let Some(c) = self.at(x, y) else {
+                    continue;
+                };
+                for (s, c) in sum.iter_mut().zip(c) {
+                    *s += c * w;
+                }
+            }
+        }
+        sum
+    }
+}
+
+/// A sample coordinate along one axis of a level, with the pixels it may read.
+struct Axis {
+    v: f32,
+    /// The bounds, in which pixel centers are sampled.
+    lo: f32,
+    hi: f32,
+    /// The first and last pixel touching the bounds.
+    first: i32,
+    last: i32,
+    /// How the pixels repeat past the bounds.
+    mode: TileMode,
+}
+
+impl Axis {
+    fn new(v: f32, lo: f32, hi: f32, size: u32, mode: TileMode) -> Self {
+        let first = (lo.floor() as i32).clamp(0, size as i32 - 1);
+        let last = (hi.ceil() as i32 - 1).clamp(first, size as i32 - 1);
+
+        Self {
+            v,
+            lo,
+            hi,
+            first,
+            last,
+            mode,
+        }
+    }
+
+    /// The pixel `v` falls into.
+    #[inline]
+    fn nearest(&self) -> Option<u32> {
+        self.tap(self.v.floor() as i32)
+    }
+
+    /// The pixel before `v` and how far past its center `v` is.
+    ///
+    /// When clamping, `v` is kept at least half a pixel inside the bounds.
+    #[inline]
+    fn split(&self) -> (i32, f32) {
+        let v = if self.mode != TileMode::Clamp {
+            self.v
+        } else if self.hi - self.lo < 1.0 {
+            (self.lo + self.hi) * 0.5
+        } else {
+            self.v.clamp(self.lo + 0.5, self.hi - 0.5)
+        };
+        let floor = (v - 0.5).floor();
+
+        (floor as i32, v - 0.5 - floor)
+    }
+
+    /// Pixel `i` moved into the bounds by the tile mode, `None` outside of a decal.
+    #[inline]
+    fn tap(&self, i: i32) -> Option<u32> {
+        let n = self.last - self.first + 1;
+        let i = match self.mode {
+            TileMode::Clamp => i.clamp(self.first, self.last),
+            TileMode::Repeat => self.first + (i - self.first).rem_euclid(n),
+            TileMode::Mirror => {
+                let m = (i - self.first).rem_euclid(2 * n);
+                self.first + if m < n { m } else { 2 * n - 1 - m }
+            }
+            TileMode::Decal => {
+                if i < self.first || i > self.last {
+                    return None;
+                }
+                i
+            }
+        };
+
+        Some(i as u32)
+    }
+}
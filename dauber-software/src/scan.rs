@@ -0,0 +1,319 @@
+use crate::blit::Blitter;
+use crate::edge::EdgeList;
+use dauber_core::path::PathFillType;
+
+#[inline]
+fn is_inside(fill_type: PathFillType, winding: i32) -> bool {
+    let inside = if fill_type.is_even_odd() {
+        winding & 1 != 0
+    } else {
+        winding != 0
+    };
+
+    inside != fill_type.is_inverse()
+}
+
+/// Coverage for a fractional winding number.
+#[inline]
+fn coverage(fill_type: PathFillType, winding: f32) -> f32 {
+    let coverage = if fill_type.is_even_odd() {
+        let w = winding.abs() % 2.0;
+        if w > 1.0 {
+            2.0 - w
+        } else {
+            w
+        }
+    } else {
+        winding.abs().min(1.0)
+    };
+
+    if fill_type.is_inverse() {
+        1.0 - coverage
+    } else {
+        coverage
+    }
+}
+
+/// Fill `edges` with exact coverage if `anti_alias` is set,
+/// by sampling pixel centers otherwise.
+pub(crate) fn fill(
+    edges: &EdgeList,
+    fill_type: PathFillType,
+    anti_alias: bool,
+    width: u32,
+    height: u32,
+    blitter: &mut impl Blitter,
+) {
+    if anti_alias {
+        fill_edges_aa(edges, fill_type, width, height, blitter);
+    } else {
+        fill_edges(edges, fill_type, width, height, blitter);
+    }
+}
+
+/// Fill `edges` by sampling every pixel at its center.
+pub(crate) fn fill_edges(
+    edges: &EdgeList,
+    fill_type: PathFillType,
+    width: u32,
+    height: u32,
+    blitter: &mut impl Blitter,
+) {
+    let (start_y, end_y) = if fill_type.is_inverse() {
+        (0, height)
+    } else if edges.is_empty() {
+        return;
+    } else {
+        (
+            (edges.min_y - 0.5).ceil().max(0.0) as u32,
+            ((edges.max_y - 0.5).ceil().max(0.0) as u32).min(height),
+        )
+    };
+
+    let mut sorted: Vec<_> = edges.edges.iter().collect();
+    sorted.sort_by(|a, b| a.y0.total_cmp(&b.y0));
+
+    let mut next = 0;
+    let mut active = Vec::new();
+    let mut crossings = Vec::<(f32, i32)>::new();
+
+    for y in start_y..end_y {
+        let sample_y = y as f32 + 0.5;
+
+        while next < sorted.len() && sorted[next].y0 <= sample_y {
+            active.push(sorted[next]);
+            next += 1;
+        }
+        active.retain(|edge| edge.y1 > sample_y);
+
+        crossings.clear();
+        crossings.extend(
+            active
+                .iter()
+                .map(|edge| (edge.x_at(sample_y), edge.winding)),
+        );
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut winding = 0;
+        let mut span_start = f32::NEG_INFINITY;
+        for &(x, dir) in &crossings {
+            let was_inside = is_inside(fill_type, winding);
+            winding += dir;
+            let inside = is_inside(fill_type, winding);
+
+            if !was_inside && inside {
+                span_start = x;
+            } else if was_inside && !inside {
+                blit_span(span_start, x, y, width, blitter);
+            }
+        }
+
+        if is_inside(fill_type, winding) {
+            blit_span(span_start, f32::INFINITY, y, width, blitter);
+        }
+    }
+}
+
+/// Blit the pixels whose centers lie within `start..end`.
+fn blit_span(start: f32, end: f32, y: u32, width: u32, blitter: &mut impl Blitter) {
+    let x0 = (start - 0.5).ceil().clamp(0.0, width as f32) as u32;
+    let x1 = (end - 0.5).ceil().clamp(0.0, width as f32) as u32;
+
+    if x1 > x0 {
+        blitter.blit_h(x0, y, x1 - x0);
+    }
+}
+
+/// Fill `edges` with exact area coverage.
+///
+/// Every edge deposits the signed area it covers into an accumulation buffer,
+/// a running sum over each row then yields the winding number of every pixel,
+/// fractional at the boundary of the shape.
+pub(crate) fn fill_edges_aa(
+    edges: &EdgeList,
+    fill_type: PathFillType,
+    width: u32,
+    height: u32,
+    blitter: &mut impl Blitter,
+) {
+    let inverse = fill_type.is_inverse();
+
+    let (min_x, min_y, max_x, max_y) = if edges.is_empty() {
+        (0.0, 0.0, 0.0, 0.0)
+    } else {
+        (
+            edges.min_x.floor().clamp(0.0, width as f32),
+            edges.min_y.floor().clamp(0.0, height as f32),
+            edges.max_x.ceil().clamp(0.0, width as f32),
+            edges.max_y.ceil().clamp(0.0, height as f32),
+        )
+    };
+
+    if min_x >= max_x || min_y >= max_y {
+        if inverse {
+            for y in 0..height {
+                blitter.blit_h(0, y, width);
+            }
+        }
+        return;
+    }
+
+    let (min_x, min_y, max_x, max_y) = (min_x as u32, min_y as u32, max_x as u32, max_y as u32);
+
+    let mut acc = Accumulator::new((max_x - min_x) as usize, (max_y - min_y) as usize);
+
+    for edge in &edges.edges {
+        let p0 = (edge.x0 - min_x as f32, edge.y0 - min_y as f32);
+        let p1 = (edge.x1 - min_x as f32, edge.y1 - min_y as f32);
+
+        if edge.winding < 0 {
+            acc.clipped_line(p1, p0);
+        } else {
+            acc.clipped_line(p0, p1);
+        }
+    }
+
+    if inverse {
+        for y in (0..min_y).chain(max_y..height) {
+            blitter.blit_h(0, y, width);
+        }
+    }
+
+    let mut row = vec![0u8; acc.width];
+    for (i, cells) in acc.cells.chunks_exact(acc.stride).enumerate() {
+        let y = min_y + i as u32;
+
+        let mut winding = 0.0;
+        for (alpha, cell) in row.iter_mut().zip(cells) {
+            winding += cell;
+            *alpha = (coverage(fill_type, winding) * 255.0 + 0.5) as u8;
+        }
+
+        blitter.blit_anti_h(min_x, y, &row);
+
+        // the winding outside the bounds is always zero
+        if inverse {
+            if min_x > 0 {
+                blitter.blit_h(0, y, min_x);
+            }
+            if max_x < width {
+                blitter.blit_h(max_x, y, width - max_x);
+            }
+        }
+    }
+}
+
+struct Accumulator {
+    width: usize,
+    height: usize,
+    /// one spare cell per row catching coverage right of the last pixel
+    stride: usize,
+    cells: Vec<f32>,
+}
+
+impl Accumulator {
+    fn new(width: usize, height: usize) -> Self {
+        let stride = width + 1;
+        Self {
+            width,
+            height,
+            stride,
+            cells: vec![0.0; stride * height],
+        }
+    }
+
+    /// Split the line where it leaves `0..=width` horizontally,
+    /// the parts outside are pushed onto the nearest vertical border
+    /// so they still contribute their winding.
+    fn clipped_line(&mut self, p0: (f32, f32), p1: (f32, f32)) {
+        let right = self.width as f32;
+
+        let mut ts = [0.0, 1.0, 1.0, 1.0];
+        let mut n = 1;
+        for border in [0.0, right] {
+            let t = (border - p0.0) / (p1.0 - p0.0);
+            if t > 0.0 && t < 1.0 {
+                ts[n] = t;
+                n += 1;
+            }
+        }
+        ts[n] = 1.0;
+        ts[1..n].sort_by(f32::total_cmp);
+
+        let at = |t: f32| {
+            let x = p0.0 + (p1.0 - p0.0) * t;
+            let y = p0.1 + (p1.1 - p0.1) * t;
+            (x.clamp(0.0, right), y)
+        };
+
+        for pair in ts[..=n].windows(2) {
+            self.line(at(pair[0]), at(pair[1]));
+        }
+    }
+
+    /// Accumulate the signed area right of a line with `0 <= x <= width`.
+    fn line(&mut self, p0: (f32, f32), p1: (f32, f32)) {
+        if p0.1 == p1.1 {
+            return;
+        }
+
+        let (dir, p0, p1) = if p0.1 < p1.1 {
+            (1.0, p0, p1)
+        } else {
+            (-1.0, p1, p0)
+        };
+
+        let dxdy = (p1.0 - p0.0) / (p1.1 - p0.1);
+        let mut x = p0.0;
+        if p0.1 < 0.0 {
+            x -= p0.1 * dxdy;
+        }
+
+        let start_y = p0.1.max(0.0) as usize;
+        let end_y = (p1.1.ceil().max(0.0) as usize).min(self.height);
+
+        for y in start_y..end_y {
+            let row = y * self.stride;
+            let dy = ((y + 1) as f32).min(p1.1) - (y as f32).max(p0.1);
+            let x_next = (x + dxdy * dy).clamp(0.0, self.width as f32);
+            let d = dy * dir;
+
+            let (x0, x1) = if x < x_next { (x, x_next) } else { (x_next, x) };
+            let x0_floor = x0.floor();
+            let x0i = x0_floor as usize;
+            let x1_ceil = x1.ceil();
+            let x1i = x1_ceil as usize;
+
+            if x1i <= x0i + 1 {
+                // the line stays within a single pixel of this row
+                let xmf = 0.5 * (x + x_next) - x0_floor;
+                self.cells[row + x0i] += d - d * xmf;
+                if x0i < self.width {
+                    self.cells[row + x0i + 1] += d * xmf;
+                }
+            } else {
+                let s = (x1 - x0).recip();
+                let x0f = x0 - x0_floor;
+                let a0 = 0.5 * s * (1.0 - x0f) * (1.0 - x0f);
+                let x1f = x1 - x1_ceil + 1.0;
+                let am = 0.5 * s * x1f * x1f;
+
+                self.cells[row + x0i] += d * a0;
+                if x1i == x0i + 2 {
+                    self.cells[row + x0i + 1] += d * (1.0 - a0 - am);
+                } else {
+                    let a1 = s * (1.5 - x0f);
+                    self.cells[row + x0i + 1] += d * (a1 - a0);
+                    for xi in x0i + 2..x1i - 1 {
+                        self.cells[row + xi] += d * s;
+                    }
+                    let a2 = a1 + (x1i - x0i - 3) as f32 * s;
+                    self.cells[row + x1i - 1] += d * (1.0 - a2 - am);
+                }
+                self.cells[row + x1i] += d * am;
+            }
+
+            x = x_next;
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use crate::blit::premultiply;
+use dauber_core::color::Color;
+use dauber_core::geom::matrix::Matrix;
+use dauber_core::geom::point;
+use dauber_core::geom::point::Point;
+use dauber_core::image_info::ColorSpace;
+use dauber_core::shader::{GradientStop, Shader, TileMode};
+use std::f32::consts::TAU;
+
+/// A shader prepared for evaluation at device pixels.
+pub(crate) struct ShaderContext {
+    kind: Kind,
+    /// Maps device coordinates into the space `kind` is evaluated in.
+    inverse: Matrix,
+    stops: Vec<GradientStop>,
+    tile_mode: TileMode,
+    /// Alpha of the paint color.
+    alpha: f32,
+}
+
+enum Kind {
+    /// `t` is the x coordinate.
+    Linear,
+    /// `t` is the distance from the origin.
+    Radial,
+    /// `t` is the angle around the origin, scaled and offset.
+    Sweep { scale: f32, bias: f32 },
+    TwoPointConical {
+        start: Point,
+        start_radius: f32,
+        end: Point,
+        end_radius: f32,
+    },
+}
+
+impl ShaderContext {
+    /// `None` when the shader cannot be mapped to device space or is not a gradient.
+    ///
+    /// The stops are interpolated in `color_space`, the color space of the device.
+    pub fn new(
+        shader: &Shader,
+        matrix: &Matrix,
+        alpha: f32,
+        color_space: ColorSpace,
+    ) -> Option<Self> {
+        let gradient = shader.gradient()?;
+        let inverse = matrix.concat(&gradient.local_matrix).invert()?;
+
+        let kind = match *shader {
+            Shader::LinearGradient { .. } => Kind::Linear,
+            Shader::RadialGradient { .. } => Kind::Radial,
+            Shader::SweepGradient {
+                start_angle,
+                end_angle,
+                ..
+            } => {
+                let range = end_angle - start_angle;
+                Kind::Sweep {
+                    scale: 360.0 / range,
+                    bias: -start_angle / range,
+                }
+            }
+            Shader::TwoPointConicalGradient {
+                start,
+                start_radius,
+                end,
+                end_radius,
+                ..
+            } => Kind::TwoPointConical {
+                start,
+                start_radius,
+                end,
+                end_radius,
+            },
+            Shader::Image { .. } => return None,
+        };
+
+        Some(Self {
+            kind,
+            inverse: shader.unit_matrix().concat(&inverse),
+            stops: gradient
+                .normalized_stops()
+                .into_iter()
+                .map(|stop| GradientStop {
+                    color: ColorSpace::Srgb.convert(stop.color, color_space),
+                    ..stop
+                })
+                .collect(),
+            tile_mode: gradient.tile_mode,
+            alpha,
+        })
+    }
+
+    /// Premultiplied colors at the centers of the pixels starting at (`x`, `y`).
+    pub fn shade_row(&self, x: u32, y: u32, out: &mut [[f32; 4]]) {
+        for (i, color) in out.iter_mut().enumerate() {
+            let p = self
+                .inverse
+                .map_point(point((x + i as u32) as f32 + 0.5, y as f32 + 0.5));
+
+            *color = match self.t(p).and_then(|t| self.tile_mode.apply(t)) {
+                Some(t) => {
+                    let color = self.color_at(t);
+                    premultiply(color.with_a(color.a * self.alpha))
+                }
+                None => [0.0; 4],
+            };
+        }
+    }
+
+    fn t(&self, p: Point) -> Option<f32> {
+        match self.kind {
+            Kind::Linear => Some(p.x),
+            Kind::Radial => Some(p.x.hypot(p.y)),
+            Kind::Sweep { scale, bias } => {
+                let turn = p.y.atan2(p.x) / TAU;
+                Some((turn - turn.floor()) * scale + bias)
+            }
+            Kind::TwoPointConical {
+                start,
+                start_radius,
+                end,
+                end_radius,
+            } => two_point_conical(p, start, start_radius, end, end_radius),
+        }
+    }
+
+    /// Interpolate the unpremultiplied stop colors at `t` in 0..=1.
+    fn color_at(&self, t: f32) -> Color {
+        let stops = &self.stops;
+        let i = stops.partition_point(|stop| stop.offset < t);
+
+        if i == 0 {
+            return stops[0].color;
+        }
+        if i == stops.len() {
+            return stops[i - 1].color;
+        }
+
+        let (a, b) = (stops[i - 1], stops[i]);
+        let w = (t - a.offset) / (b.offset - a.offset);
+        let (a, b) = (a.color.to_array(), b.color.to_array());
+
+        [0, 1, 2, 3].map(|c| a[c] + (b[c] - a[c]) * w).into()
+    }
+}
+
+/// The largest `t` whose circle, interpolated from the start to the end circle,
+/// passes through `p` with a radius that is not negative.
+fn two_point_conical(
+    p: Point,
+    start: Point,
+    start_radius: f32,
+    end: Point,
+    end_radius: f32,
+) -> Option<f32> {
+    // |p - start - t * cd| = start_radius + t * dr, as a t² - 2 b t + c = 0
+    let cd = end - start;
+    let pd = p - start;
+    let dr = end_radius - start_radius;
+
+    let a = cd.dot(cd) - dr * dr;
+    let b = pd.dot(cd) + start_radius * dr;
+    let c = pd.dot(pd) - start_radius * start_radius;
+
+    let valid = |t: f32| start_radius + t * dr >= 0.0;
+
+    if a.abs() <= 1e-6 * (cd.dot(cd) + dr * dr) {
+        let t = c / (2.0 * b);
+        return (t.is_finite() && valid(t)).then_some(t);
+    }
+
+    let disc = b * b - a * c;
+    if disc < 0.0 {
+        return None;
+    }
+
+    let s = disc.sqrt();
+    let (t0, t1) = ((b + s) / a, (b - s) / a);
+    let (hi, lo) = (t0.max(t1), t0.min(t1));
+
+    if valid(hi) {
+        Some(hi)
+    } else if valid(lo) {
+        Some(lo)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,796 @@
+use dauber_core::blend_mode::BlendMode;
+use dauber_core::color::Color;
+use dauber_core::device::{Device, DeviceFactory};
+use dauber_core::geom::matrix::Matrix;
+use dauber_core::geom::point;
+use dauber_core::geom::point::Point;
+use dauber_core::geom::rect::Rect;
+use dauber_core::glyph::{GlyphId, GlyphRun, GlyphSource};
+use dauber_core::image::Image;
+use dauber_core::image_info::{AlphaType, ColorSpace, ColorType, ImageInfo};
+use dauber_core::paint::{Paint, PaintStyle, StrokeCap, StrokeJoin};
+use dauber_core::path::{Path, PathFillType};
+use dauber_core::path_effect::PathEffect;
+use dauber_core::sampling::{CubicResampler, FilterMode, MipmapMode, SamplingOptions};
+use dauber_core::shader::{Gradient, Shader, TileMode};
+use dauber_core::Error;
+use dauber_software::device::Raster;
+use dauber_software::glyph_cache::{AtlasRect, CachedGlyph, GlyphCache};
+
+fn pixel(raster: &Raster, x: u32, y: u32) -> [u8; 4] {
+    let i = ((y * raster.image_info().width + x) * 4) as usize;
+    raster.pixels()[i..i + 4].try_into().unwrap()
+}
+
+fn assert_near(actual: [u8; 4], expected: [u8; 4]) {
+    let near = actual
+        .iter()
+        .zip(expected)
+        .all(|(&a, e)| a.abs_diff(e) <= 2);
+    assert!(near, "{actual:?} is not near {expected:?}");
+}
+
+#[test]
+fn fill() {
+    let mut raster = Raster::new(ImageInfo::new_wh(64, 64));
+    raster.clear(Color::BLACK);
+
+    let mut paint = Paint::new();
+    paint.set_color(Color::GREEN);
+
+    let mut path = Path::new();
+    path.move_to(point(8., 8.));
+    path.line_to(point(24., 8.));
+    path.line_to(point(24., 24.));
+    path.line_to(point(8., 24.));
+    path.close();
+
+    raster.draw_path(&path, &paint).unwrap();
+
+    let mut path = Path::new();
+    paint.set_color(Color::RED);
+    path.add_circle(point(44., 44.), 10.);
+
+    raster.draw_path(&path, &paint).unwrap();
+
+    assert_eq!(pixel(&raster, 8, 8), [0, 255, 0, 255]);
+    assert_eq!(pixel(&raster, 23, 23), [0, 255, 0, 255]);
+    assert_eq!(pixel(&raster, 24, 16), [0, 0, 0, 255]);
+    assert_eq!(pixel(&raster, 16, 7), [0, 0, 0, 255]);
+
+    assert_eq!(pixel(&raster, 44, 44), [255, 0, 0, 255]);
+    assert_eq!(pixel(&raster, 44, 35), [255, 0, 0, 255]);
+    assert_eq!(pixel(&raster, 44, 32), [0, 0, 0, 255]);
+    assert_eq!(pixel(&raster, 36, 36), [0, 0, 0, 255]);
+}
+
+#[test]
+fn translucent() {
+    let mut raster = Raster::new(ImageInfo::new_wh(4, 4));
+    raster.clear(Color::WHITE);
+
+    let mut paint = Paint::new();
+    paint.set_color(Color::BLACK.with_a(0.5));
+
+    let mut path = Path::new();
+    path.move_to(point(0., 0.));
+    path.line_to(point(4., 0.));
+    path.line_to(point(4., 4.));
+    path.line_to(point(0., 4.));
+
+    raster.draw_path(&path, &paint).unwrap();
+
+    assert_eq!(pixel(&raster, 1, 1), [128, 128, 128, 255]);
+}
+
+#[test]
+fn stroke() {
+    let mut raster = Raster::new(ImageInfo::new_wh(32, 32));
+
+    let mut paint = Paint::new();
+    paint.set_style(PaintStyle::Stroke(4.));
+    paint.set_color(Color::BLUE);
+
+    let mut path = Path::new();
+    path.move_to(point(4., 16.));
+    path.cubic_to(point(10., 0.), point(22., 32.), point(28., 16.));
+
+    raster.draw_path(&path, &paint).unwrap();
+
+    assert_eq!(pixel(&raster, 16, 16), [0, 0, 255, 255]);
+    assert_eq!(pixel(&raster, 4, 4), [0, 0, 0, 0]);
+    assert_eq!(pixel(&raster, 2, 16), [0, 0, 0, 0]);
+}
+
+#[test]
+fn stroke_cap_join() {
+    let mut path = Path::new();
+    path.move_to(point(8., 8.));
+    path.line_to(point(24., 8.));
+    path.line_to(point(24., 24.));
+
+    let white = [255; 4];
+    let none = [0; 4];
+
+    // (cap, join, start cap pixel, miter corner pixel, bevel corner pixel, round cap corner pixel)
+    for (cap, join, start, miter, bevel, corner) in [
+        (StrokeCap::Butt, StrokeJoin::Miter, none, white, white, none),
+        (
+            StrokeCap::Square,
+            StrokeJoin::Bevel,
+            white,
+            none,
+            white,
+            white,
+        ),
+        (
+            StrokeCap::Round,
+            StrokeJoin::Round,
+            white,
+            none,
+            white,
+            none,
+        ),
+    ] {
+        let mut raster = Raster::new(ImageInfo::new_wh(32, 32));
+
+        let mut paint = Paint::new();
+        paint.set_style(PaintStyle::Stroke(8.));
+        paint.set_color(Color::WHITE);
+        paint.set_stroke_cap(cap);
+        paint.set_stroke_join(join);
+
+        raster.draw_path(&path, &paint).unwrap();
+
+        assert_eq!(pixel(&raster, 5, 8), start, "{cap:?}");
+        assert_eq!(pixel(&raster, 27, 5), miter, "{join:?}");
+        assert_eq!(pixel(&raster, 25, 6), bevel, "{join:?}");
+        assert_eq!(pixel(&raster, 4, 4), corner, "{cap:?}");
+    }
+
+    // a limit of one bevels every join
+    let mut raster = Raster::new(ImageInfo::new_wh(32, 32));
+    let mut paint = Paint::new();
+    paint.set_style(PaintStyle::Stroke(8.));
+    paint.set_color(Color::WHITE);
+    paint.set_miter_limit(1.);
+
+    raster.draw_path(&path, &paint).unwrap();
+    assert_eq!(pixel(&raster, 27, 5), none);
+}
+
+#[test]
+fn anti_alias() {
+    let mut raster = Raster::new(ImageInfo::new_wh(16, 16));
+
+    let mut paint = Paint::new();
+    paint.set_color(Color::WHITE);
+    paint.set_anti_alias(true);
+
+    // hangs off the left border, its right edge splits a column in half
+    let mut path = Path::new();
+    path.move_to(point(-4., 2.));
+    path.line_to(point(4.5, 2.));
+    path.line_to(point(4.5, 6.25));
+    path.line_to(point(-4., 6.25));
+    path.close();
+
+    raster.draw_path(&path, &paint).unwrap();
+
+    assert_eq!(pixel(&raster, 0, 2), [255; 4]);
+    assert_eq!(pixel(&raster, 3, 5), [255; 4]);
+    assert_eq!(pixel(&raster, 4, 3), [128; 4]);
+    assert_eq!(pixel(&raster, 2, 6), [64; 4]);
+    assert_eq!(pixel(&raster, 4, 6), [32; 4]);
+    assert_eq!(pixel(&raster, 5, 3), [0; 4]);
+
+    let mut raster = Raster::new(ImageInfo::new_wh(64, 64));
+    let mut path = Path::new();
+    path.move_to(point(32., 12.));
+    path.line_to(point(52., 32.));
+    path.line_to(point(32., 52.));
+    path.line_to(point(12., 32.));
+    path.close();
+
+    raster.draw_path(&path, &paint).unwrap();
+
+    let area = raster
+        .pixels()
+        .chunks_exact(4)
+        .map(|px| px[3] as f32 / 255.0)
+        .sum::<f32>();
+    assert!((area - 800.0).abs() < 0.5, "{area}");
+    assert!(raster
+        .pixels()
+        .chunks_exact(4)
+        .any(|px| px[3] > 0 && px[3] < 255));
+}
+
+#[test]
+fn fill_type() {
+    let mut path = Path::new();
+    for (l, t) in [(4., 4.), (12., 12.)] {
+        path.move_to(point(l, t));
+        path.line_to(point(l + 16., t));
+        path.line_to(point(l + 16., t + 16.));
+        path.line_to(point(l, t + 16.));
+        path.close();
+    }
+
+    for anti_alias in [false, true] {
+        let mut paint = Paint::new();
+        paint.set_color(Color::WHITE);
+        paint.set_anti_alias(anti_alias);
+
+        let mut raster = Raster::new(ImageInfo::new_wh(64, 64));
+        raster.draw_path(&path, &paint).unwrap();
+        assert_eq!(pixel(&raster, 16, 16), [255; 4]);
+
+        let mut raster = Raster::new(ImageInfo::new_wh(64, 64));
+        path.set_fill_type(PathFillType::EvenOdd);
+        raster.draw_path(&path, &paint).unwrap();
+        assert_eq!(pixel(&raster, 16, 16), [0; 4]);
+        assert_eq!(pixel(&raster, 8, 8), [255; 4]);
+
+        let mut raster = Raster::new(ImageInfo::new_wh(64, 64));
+        path.set_fill_type(PathFillType::InverseWinding);
+        raster.draw_path(&path, &paint).unwrap();
+        assert_eq!(pixel(&raster, 16, 16), [0; 4]);
+        assert_eq!(pixel(&raster, 0, 0), [255; 4]);
+        assert_eq!(pixel(&raster, 2, 16), [255; 4]);
+        assert_eq!(pixel(&raster, 63, 63), [255; 4]);
+
+        let mut raster = Raster::new(ImageInfo::new_wh(64, 64));
+        path.set_fill_type(PathFillType::InverseEvenOdd);
+        raster.draw_path(&path, &paint).unwrap();
+        assert_eq!(pixel(&raster, 16, 16), [255; 4]);
+        assert_eq!(pixel(&raster, 8, 8), [0; 4]);
+        assert_eq!(pixel(&raster, 40, 8), [255; 4]);
+
+        path.set_fill_type(PathFillType::Winding);
+    }
+}
+
+#[test]
+fn dash() {
+    let mut raster = Raster::new(ImageInfo::new_wh(32, 32));
+
+    let mut paint = Paint::new();
+    paint.set_style(PaintStyle::Stroke(4.));
+    paint.set_color(Color::WHITE);
+    paint.set_path_effect(Some(PathEffect::dash([4., 4.], 0.)));
+
+    let mut path = Path::new();
+    path.move_to(point(0., 8.));
+    path.line_to(point(32., 8.));
+    raster.draw_path(&path, &paint).unwrap();
+
+    assert_eq!(pixel(&raster, 2, 8), [255; 4]);
+    assert_eq!(pixel(&raster, 6, 8), [0; 4]);
+    assert_eq!(pixel(&raster, 10, 8), [255; 4]);
+
+    // dotted with round caps
+    paint.set_stroke_cap(StrokeCap::Round);
+    paint.set_path_effect(Some(PathEffect::dash([0., 8.], 4.)));
+
+    let mut path = Path::new();
+    path.move_to(point(0., 24.));
+    path.line_to(point(32., 24.));
+    raster.draw_path(&path, &paint).unwrap();
+
+    assert_eq!(pixel(&raster, 4, 24), [255; 4]);
+    assert_eq!(pixel(&raster, 8, 24), [0; 4]);
+    assert_eq!(pixel(&raster, 12, 24), [255; 4]);
+    assert_eq!(pixel(&raster, 0, 24), [0; 4]);
+}
+
+#[test]
+fn gradient() {
+    let mut raster = Raster::new(ImageInfo::new_wh(32, 8));
+    let mut rect = Path::new();
+    rect.add_rect(&Rect::from_ltrb(0., 0., 32., 8.));
+
+    let linear = |tile_mode| {
+        Shader::linear_gradient(
+            point(8., 0.),
+            point(24., 0.),
+            Gradient::evenly_spaced(&[Color::RED, Color::BLUE], tile_mode),
+        )
+    };
+
+    let mut paint = Paint::new();
+    paint.set_shader(Some(linear(TileMode::Clamp)));
+    raster.draw_path(&rect, &paint).unwrap();
+
+    assert_eq!(pixel(&raster, 2, 4), [255, 0, 0, 255]);
+    assert_near(pixel(&raster, 12, 4), [183, 0, 72, 255]);
+    assert_eq!(pixel(&raster, 28, 4), [0, 0, 255, 255]);
+
+    raster.clear(Color::TRANSPARENT);
+    paint.set_shader(Some(linear(TileMode::Decal)));
+    raster.draw_path(&rect, &paint).unwrap();
+
+    assert_eq!(pixel(&raster, 2, 4), [0; 4]);
+    assert_near(pixel(&raster, 12, 4), [183, 0, 72, 255]);
+    assert_eq!(pixel(&raster, 28, 4), [0; 4]);
+
+    paint.set_shader(Some(linear(TileMode::Repeat)));
+    raster.draw_path(&rect, &paint).unwrap();
+    assert_near(pixel(&raster, 28, 4), [183, 0, 72, 255]);
+
+    paint.set_shader(Some(linear(TileMode::Mirror)));
+    raster.draw_path(&rect, &paint).unwrap();
+    assert_near(pixel(&raster, 28, 4), [72, 0, 183, 255]);
+
+    // the alpha of the paint color fades the shader
+    raster.clear(Color::TRANSPARENT);
+    paint.set_color(Color::BLACK.with_a(0.5));
+    paint.set_shader(Some(linear(TileMode::Clamp)));
+    raster.draw_path(&rect, &paint).unwrap();
+    assert_near(pixel(&raster, 2, 4), [128, 0, 0, 128]);
+}
+
+#[test]
+fn radial_sweep_gradient() {
+    let mut raster = Raster::new(ImageInfo::new_wh(32, 32));
+    let mut rect = Path::new();
+    rect.add_rect(&Rect::from_ltrb(0., 0., 32., 32.));
+
+    let colors = Gradient::evenly_spaced(&[Color::RED, Color::BLUE], TileMode::Clamp);
+
+    let mut paint = Paint::new();
+    paint.set_shader(Some(Shader::radial_gradient(
+        point(16., 16.),
+        8.,
+        colors.clone(),
+    )));
+    raster.draw_path(&rect, &paint).unwrap();
+
+    assert_near(pixel(&raster, 16, 16), [233, 0, 22, 255]);
+    assert_eq!(pixel(&raster, 30, 16), [0, 0, 255, 255]);
+
+    // a quarter turn, clockwise from the positive x axis
+    paint.set_shader(Some(Shader::sweep_gradient(
+        point(16., 16.),
+        0.,
+        90.,
+        colors.clone(),
+    )));
+    raster.draw_path(&rect, &paint).unwrap();
+
+    assert_near(pixel(&raster, 28, 16), [249, 0, 6, 255]);
+    assert_near(pixel(&raster, 26, 26), [128, 0, 128, 255]);
+    assert_eq!(pixel(&raster, 4, 4), [0, 0, 255, 255]);
+
+    // the start circle inside the end circle is a radial gradient
+    paint.set_shader(Some(Shader::two_point_conical_gradient(
+        point(16., 16.),
+        0.,
+        point(16., 16.),
+        8.,
+        colors,
+    )));
+    raster.draw_path(&rect, &paint).unwrap();
+
+    assert_near(pixel(&raster, 16, 16), [233, 0, 22, 255]);
+    assert_eq!(pixel(&raster, 30, 16), [0, 0, 255, 255]);
+}
+
+#[test]
+fn blend_mode() {
+    let mut raster = Raster::new(ImageInfo::new_wh(4, 4));
+    let mut rect = Path::new();
+    rect.add_rect(&Rect::from_ltrb(0., 0., 4., 4.));
+
+    let mut paint = Paint::new();
+    let mut blend = |dst: [f32; 4], src: [f32; 4], mode| {
+        raster.clear(Color::from(dst));
+        paint.set_color(Color::from(src));
+        paint.set_blend_mode(mode);
+        raster.draw_path(&rect, &paint).unwrap();
+        pixel(&raster, 2, 2)
+    };
+
+    let dst = [0.5, 0.5, 1.0, 1.0];
+    let src = [0.8, 0.5, 0.2, 1.0];
+
+    assert_eq!(blend(dst, src, BlendMode::Clear), [0; 4]);
+    assert_eq!(blend(dst, src, BlendMode::Dst), [128, 128, 255, 255]);
+    assert_near(blend(dst, src, BlendMode::Multiply), [102, 64, 51, 255]);
+    assert_near(blend(dst, src, BlendMode::Screen), [230, 191, 255, 255]);
+    assert_near(blend(dst, src, BlendMode::Difference), [77, 0, 204, 255]);
+    assert_near(blend(dst, src, BlendMode::Darken), [128, 128, 51, 255]);
+    assert_near(blend(dst, src, BlendMode::Lighten), [204, 128, 255, 255]);
+
+    // luminosity of a gray source onto a saturated destination keeps the hue
+    let [r, g, b, a] = blend(
+        [1.0, 0.0, 0.0, 1.0],
+        [0.5, 0.5, 0.5, 1.0],
+        BlendMode::Luminosity,
+    );
+    assert!(r > g && g == b && a == 255);
+
+    // translucent sources only keep the destination where it overlaps
+    let half = [1.0, 1.0, 1.0, 0.5];
+    assert_near(blend(dst, half, BlendMode::DstIn), [64, 64, 128, 128]);
+    assert_near(blend(dst, half, BlendMode::DstOut), [64, 64, 128, 128]);
+    assert_near(blend(dst, half, BlendMode::SrcIn), [128, 128, 128, 128]);
+    assert_eq!(blend(dst, half, BlendMode::SrcOut), [0; 4]);
+}
+
+#[test]
+fn invalid_image_info() {
+    let empty = Raster::try_new(ImageInfo::new_wh(0, 16));
+    assert!(matches!(empty, Err(Error::InvalidImageInfo(_))));
+
+    let raster = Raster::try_new(ImageInfo::new_wh(4, 4)).unwrap();
+    assert!(raster.encode_to_png().is_ok());
+}
+
+#[test]
+fn color_type() {
+    let draw = |info: ImageInfo| {
+        let mut raster = Raster::new(info);
+        raster.clear(Color::BLUE);
+
+        let mut paint = Paint::new();
+        paint.set_color(Color::RED.with_a(0.5));
+        let mut rect = Path::new();
+        rect.add_rect(&Rect::from_ltrb(0., 0., 2., 4.));
+        raster.draw_path(&rect, &paint).unwrap();
+
+        let bpp = info.bytes_per_pixel();
+        let px = |x: usize| raster.pixels()[x * bpp..(x + 1) * bpp].to_vec();
+        (px(0), px(3))
+    };
+    let info = ImageInfo::new_wh(4, 4);
+
+    let (red, blue) = draw(info.with_color_type(ColorType::Bgra8888));
+    assert_near(red.try_into().unwrap(), [128, 0, 128, 255]);
+    assert_eq!(blue, [255, 0, 0, 255]);
+
+    let (_, blue) = draw(info.with_color_type(ColorType::Rgb565));
+    assert_eq!(blue, 0x001f_u16.to_le_bytes());
+
+    let (red, _) = draw(info.with_color_type(ColorType::Gray8));
+    assert!(red[0].abs_diff(((0.299 * 0.5 + 0.114 * 0.5) * 255.0) as u8) <= 1);
+
+    let (red, blue) = draw(info.with_color_type(ColorType::Alpha8));
+    assert_eq!((red, blue), (vec![255], vec![255]));
+
+    let (red, _) = draw(info.with_color_type(ColorType::RgbaF16));
+    let [r, g, b, a] = dauber_core::pixel::load(ColorType::RgbaF16, AlphaType::Premul, &red);
+    assert!((r - 0.5).abs() < 0.01 && g == 0.0 && (b - 0.5).abs() < 0.01 && a == 1.0);
+
+    let raster = Raster::new(info.with_color_type(ColorType::RgbaF16));
+    assert!(raster.encode_to_png().is_ok());
+}
+
+#[test]
+fn unpremul() {
+    let mut raster = Raster::new(ImageInfo::new_wh(4, 4).with_alpha_type(AlphaType::Unpremul));
+
+    let mut paint = Paint::new();
+    paint.set_color(Color::RED.with_a(0.5));
+    let mut rect = Path::new();
+    rect.add_rect(&Rect::from_ltrb(0., 0., 2., 4.));
+    raster.draw_path(&rect, &paint).unwrap();
+    assert_near(pixel(&raster, 0, 0), [255, 0, 0, 128]);
+
+    // blending goes through premultiplied colors
+    paint.set_color(Color::BLUE.with_a(0.5));
+    raster.draw_path(&rect, &paint).unwrap();
+    assert_near(pixel(&raster, 0, 0), [85, 0, 170, 191]);
+}
+
+#[test]
+fn color_space() {
+    let info = ImageInfo::new_wh(1, 1);
+
+    let mut linear = Raster::new(info.with_color_space(ColorSpace::LinearSrgb));
+    linear.clear(Color::GRAY);
+    // sRGB 0.5 is about 0.214 in linear light
+    assert_near(pixel(&linear, 0, 0), [55, 55, 55, 255]);
+
+    let mut p3 = Raster::new(info.with_color_space(ColorSpace::DisplayP3));
+    p3.clear(Color::RED);
+    let [r, g, b, a] = pixel(&p3, 0, 0);
+    assert!(r < 255 && g > 0 && b > 0 && a == 255);
+}
+
+#[test]
+fn draw_image() {
+    // red, green / blue, white
+    let image = Image::from_pixels(
+        ImageInfo::new_wh(2, 2),
+        vec![
+            255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 255,
+        ],
+    )
+    .unwrap();
+    let src = Rect::from_ltrb(0., 0., 2., 2.);
+    let dst = Rect::from_ltrb(0., 0., 8., 8.);
+    let paint = Paint::new();
+    let draw = |sampling: SamplingOptions| {
+        let mut raster = Raster::new(ImageInfo::new_wh(8, 8));
+        raster
+            .draw_image_rect(&image, &src, &dst, &sampling, &paint)
+            .unwrap();
+        raster
+    };
+
+    let nearest = draw(SamplingOptions::nearest());
+    assert_eq!(pixel(&nearest, 3, 0), [255, 0, 0, 255]);
+    assert_eq!(pixel(&nearest, 4, 3), [0, 255, 0, 255]);
+    assert_eq!(pixel(&nearest, 7, 7), [255, 255, 255, 255]);
+
+    // the pixel centers of the image land at 2 and 6, the edges stay clamped
+    let linear = draw(SamplingOptions::linear());
+    assert_eq!(pixel(&linear, 0, 0), [255, 0, 0, 255]);
+    // 4.5 maps to 1.125, 0.625 past the center of red
+    assert_near(pixel(&linear, 4, 1), [96, 159, 0, 255]);
+    assert_near(pixel(&linear, 7, 7), [255, 255, 255, 255]);
+
+    // Catmull-Rom goes through the pixel centers, Mitchell blurs them
+    let catmull_rom = draw(SamplingOptions::cubic(CubicResampler::CATMULL_ROM));
+    let mitchell = draw(SamplingOptions::cubic(CubicResampler::MITCHELL));
+    let mut raster = Raster::new(ImageInfo::new_wh(8, 8));
+    let dst = Rect::from_ltrb(-0.5, -0.5, 7.5, 7.5);
+    raster
+        .draw_image_rect(
+            &image,
+            &src,
+            &dst,
+            &SamplingOptions::cubic(CubicResampler::CATMULL_ROM),
+            &paint,
+        )
+        .unwrap();
+    assert_eq!(pixel(&raster, 1, 1), [255, 0, 0, 255]);
+    assert_ne!(pixel(&catmull_rom, 2, 2), pixel(&mitchell, 2, 2));
+
+    // only the right column is sampled
+    let mut raster = Raster::new(ImageInfo::new_wh(8, 8));
+    raster
+        .draw_image_rect(
+            &image,
+            &Rect::from_ltrb(1., 0., 2., 2.),
+            &Rect::from_ltrb(0., 0., 8., 8.),
+            &SamplingOptions::linear(),
+            &paint,
+        )
+        .unwrap();
+    assert_eq!(pixel(&raster, 0, 0), [0, 255, 0, 255]);
+    assert_eq!(pixel(&raster, 7, 7), [255, 255, 255, 255]);
+}
+
+#[test]
+fn mipmap() {
+    // a black and white checkerboard
+    let size = 64;
+    let pixels = (0..size * size)
+        .flat_map(|i| {
+            let v = if (i % size + i / size) % 2 == 0 {
+                255
+            } else {
+                0
+            };
+            [v, v, v, 255]
+        })
+        .collect();
+    let image = Image::from_pixels(ImageInfo::new_wh(size, size), pixels).unwrap();
+
+    let mut raster = Raster::new(ImageInfo::new_wh(8, 8));
+    raster.set_matrix(Matrix::scale(0.125, 0.125));
+    let src = Rect::from_ltrb(0., 0., 64., 64.);
+    let paint = Paint::new();
+
+    // without a mipmap the samples hit single squares
+    raster
+        .draw_image_rect(&image, &src, &src, &SamplingOptions::nearest(), &paint)
+        .unwrap();
+    assert!(matches!(
+        pixel(&raster, 3, 3),
+        [0, 0, 0, 255] | [255, 255, 255, 255]
+    ));
+
+    for mipmap in [MipmapMode::Nearest, MipmapMode::Linear] {
+        let sampling = SamplingOptions::new(FilterMode::Linear, mipmap);
+        raster
+            .draw_image_rect(&image, &src, &src, &sampling, &paint)
+            .unwrap();
+        assert_near(pixel(&raster, 3, 3), [128, 128, 128, 255]);
+    }
+}
+
+#[test]
+fn image_shader() {
+    // red, green / blue, white
+    let image = Image::from_pixels(
+        ImageInfo::new_wh(2, 2),
+        vec![
+            255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 255,
+        ],
+    )
+    .unwrap();
+    let mut path = Path::new();
+    path.add_rect(&Rect::from_ltrb(0., 0., 16., 16.));
+    let draw = |tile_x, tile_y| {
+        let mut paint = Paint::new();
+        paint.set_shader(Some(Shader::image(
+            image.clone(),
+            tile_x,
+            tile_y,
+            SamplingOptions::nearest(),
+            Matrix::scale(2., 2.).concat(&Matrix::translate(2., 2.)),
+        )));
+
+        let mut raster = Raster::new(ImageInfo::new_wh(16, 16));
+        raster.draw_path(&path, &paint).unwrap();
+        raster
+    };
+    let (red, green, blue, white) = (
+        [255, 0, 0, 255],
+        [0, 255, 0, 255],
+        [0, 0, 255, 255],
+        [255, 255, 255, 255],
+    );
+
+    // the image covers 4..8 in both directions
+    let clamp = draw(TileMode::Clamp, TileMode::Clamp);
+    assert_eq!(pixel(&clamp, 0, 0), red);
+    assert_eq!(pixel(&clamp, 15, 0), green);
+    assert_eq!(pixel(&clamp, 15, 15), white);
+
+    let repeat = draw(TileMode::Repeat, TileMode::Clamp);
+    assert_eq!(pixel(&repeat, 8, 0), red);
+    assert_eq!(pixel(&repeat, 8, 15), blue);
+    assert_eq!(pixel(&repeat, 2, 15), white);
+
+    let mirror = draw(TileMode::Mirror, TileMode::Mirror);
+    assert_eq!(pixel(&mirror, 8, 4), green);
+    assert_eq!(pixel(&mirror, 10, 10), red);
+    assert_eq!(pixel(&mirror, 0, 8), white);
+    assert_eq!(pixel(&mirror, 2, 8), blue);
+
+    // nothing beside the image along x, clamped along y
+    let decal = draw(TileMode::Decal, TileMode::Clamp);
+    assert_eq!(pixel(&decal, 2, 5), [0, 0, 0, 0]);
+    assert_eq!(pixel(&decal, 4, 0), red);
+    assert_eq!(pixel(&decal, 7, 15), white);
+    assert_eq!(pixel(&decal, 9, 15), [0, 0, 0, 0]);
+}
+
+/// Every glyph but the first is a circle three tenths of an em wide, sitting on the baseline.
+#[derive(Debug)]
+struct Dots(f32);
+
+impl GlyphSource for Dots {
+    fn typeface_id(&self) -> u32 {
+        1
+    }
+
+    fn size(&self) -> f32 {
+        self.0
+    }
+
+    fn append_outline(&self, glyph: GlyphId, origin: Point, path: &mut Path) -> bool {
+        if glyph == GlyphId::NOTDEF {
+            return false;
+        }
+
+        let r = 0.3 * self.0;
+        path.add_circle(point(origin.x + r, origin.y - r), r);
+        true
+    }
+}
+
+#[test]
+fn glyph_run() {
+    let info = ImageInfo::new_wh(96, 48);
+    let font = Dots(20.);
+    let glyphs = [1, 0, 2, 1].map(GlyphId);
+    let positions = [
+        point(0., 0.),
+        point(10., 0.),
+        point(14.25, 0.),
+        point(27.5, 0.),
+    ];
+    let run = GlyphRun::new(&font, &glyphs, &positions);
+
+    let mut anti_aliased = Paint::new();
+    anti_aliased.anti_alias = true;
+    let mut gradient = anti_aliased.clone();
+    gradient.set_shader(Some(Shader::linear_gradient(
+        point(0., 0.),
+        point(96., 0.),
+        Gradient::evenly_spaced(&[Color::RED, Color::BLUE], TileMode::Clamp),
+    )));
+
+    // cached masks cover the same pixels as the outlines
+    for (matrix, paint) in [
+        (Matrix::IDENTITY, Paint::new()),
+        (Matrix::IDENTITY, anti_aliased),
+        (Matrix::scale(2., 2.), gradient),
+    ] {
+        let mut masked = Raster::new(info);
+        let mut outlined = Raster::new(info);
+        for raster in [&mut masked, &mut outlined] {
+            raster.clear(Color::WHITE);
+            raster.set_matrix(matrix);
+        }
+
+        for _ in 0..2 {
+            masked.draw_glyph_run(&run, point(4., 20.), &paint).unwrap();
+        }
+        for _ in 0..2 {
+            outlined
+                .draw_path(&run.to_path(point(4., 20.)), &paint)
+                .unwrap();
+        }
+
+        for y in 0..info.height {
+            for x in 0..info.width {
+                assert_near(pixel(&masked, x, y), pixel(&outlined, x, y));
+            }
+        }
+    }
+}
+
+#[test]
+fn glyph_cache() {
+    let font = Dots(10.);
+    let mut cache = GlyphCache::new(16, 16);
+    let get = |cache: &mut GlyphCache, glyph| match cache.get(
+        &font,
+        GlyphId(glyph),
+        1.,
+        point(0., 0.),
+        true,
+    ) {
+        CachedGlyph::Mask(mask) => Some(mask.atlas),
+        _ => None,
+    };
+
+    assert_eq!(
+        cache.get(&font, GlyphId::NOTDEF, 1., point(0., 0.), true),
+        CachedGlyph::Empty
+    );
+
+    // two shelves of two 6 by 6 masks
+    let atlas: Vec<AtlasRect> = (1..=4).map(|g| get(&mut cache, g).unwrap()).collect();
+    assert_eq!(
+        atlas.iter().map(|a| (a.x, a.y)).collect::<Vec<_>>(),
+        [(0, 0), (6, 0), (0, 8), (6, 8)]
+    );
+    let center = atlas[0].width / 2;
+    assert_eq!(cache.row(&atlas[0], center)[center as usize], 255);
+    assert!(cache.take_dirty().is_some());
+    assert!(cache.take_dirty().is_none());
+
+    // glyphs used since the last unpin stay, even when there is no room
+    assert_eq!(
+        cache.get(&font, GlyphId(5), 1., point(0., 0.), true),
+        CachedGlyph::Full
+    );
+
+    // the least recently used glyph makes room
+    cache.unpin();
+    assert_eq!(get(&mut cache, 1), Some(atlas[0]));
+    cache.unpin();
+    assert_eq!(get(&mut cache, 5), Some(atlas[1]));
+    assert_eq!(get(&mut cache, 2), Some(atlas[2]));
+    assert_eq!(cache.len(), 4);
+
+    // a mask taller than every shelf evicts nothing while a glyph is pinned
+    let tall = |cache: &mut GlyphCache| cache.get(&font, GlyphId(1), 2., point(0., 0.), true);
+    assert_eq!(tall(&mut cache), CachedGlyph::Full);
+    assert_eq!(cache.len(), 4);
+
+    // and clears the atlas once nothing is
+    cache.unpin();
+    let CachedGlyph::Mask(mask) = tall(&mut cache) else {
+        panic!("the atlas was not cleared");
+    };
+    assert_eq!((mask.atlas.x, mask.atlas.y, mask.atlas.height), (0, 0, 12));
+    assert_eq!(cache.len(), 1);
+}
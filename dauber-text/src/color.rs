@@ -0,0 +1,535 @@
+use crate::font::Font;
+use crate::typeface::GlyphId;
+use dauber_core::blend_mode::BlendMode;
+use dauber_core::clip::ClipOp;
+use dauber_core::color::Color;
+use dauber_core::device::{Device, DeviceFactory};
+use dauber_core::error::Result;
+use dauber_core::geom::matrix::Matrix;
+use dauber_core::geom::point::Point;
+use dauber_core::geom::rect::Rect;
+use dauber_core::image::Image;
+use dauber_core::image_info::{ColorType, ImageInfo};
+use dauber_core::paint::{Paint, PaintStyle};
+use dauber_core::path::Path;
+use dauber_core::sampling::{FilterMode, MipmapMode, SamplingOptions};
+use dauber_core::shader::{Gradient, GradientStop, Shader, TileMode};
+use dauber_software::device::Raster;
+use owned_ttf_parser::colr::{self, ClipBox, ColorStop, CompositeMode, GradientExtend, Painter};
+use owned_ttf_parser::{Face, NormalizedCoordinate, RasterImageFormat, RgbaColor, Transform};
+
+/// Turns outlines in font units with y pointing down, as [`Font::unit_path`] makes them,
+/// back into the y up space of the paint graph.
+const FLIP: Matrix = Matrix::scale(1.0, -1.0);
+
+/// A decoded sbix or CBDT bitmap.
+#[derive(Clone, Debug)]
+pub(crate) struct Bitmap {
+    image: Image,
+    /// Where the bitmap goes relative to the glyph origin, in pixels of its strike
+    /// with y pointing down.
+    bounds: Rect,
+    /// Pixels per em of the strike.
+    ppem: f32,
+}
+
+/// The PNG or BGRA bitmap of `glyph` in the strike closest to `ppem`.
+///
+/// Monochrome and gray bitmaps are left to the outlines.
+pub(crate) fn decode_bitmap(face: &Face<'_>, glyph: GlyphId, ppem: u16) -> Option<Bitmap> {
+    let raster = face.glyph_raster_image(owned_ttf_parser::GlyphId(glyph.0), ppem)?;
+    let image = match raster.format {
+        RasterImageFormat::PNG => Image::decode(raster.data).ok()?,
+        RasterImageFormat::BitmapPremulBgra32 => {
+            let info = ImageInfo::new_wh(raster.width.into(), raster.height.into())
+                .with_color_type(ColorType::Bgra8888);
+            Image::from_pixels(info, raster.data.to_vec()).ok()?
+        }
+        _ => return None,
+    };
+
+    // both tables place the bottom left corner, with y pointing up
+    let (width, height) = (raster.width as f32, raster.height as f32);
+    let bounds = Rect::from_xywh(raster.x as f32, -(raster.y as f32) - height, width, height);
+
+    Some(Bitmap {
+        image,
+        bounds,
+        ppem: raster.pixels_per_em.max(1) as f32,
+    })
+}
+
+/// Draw `glyph` of `font` in color with its origin at `origin`, `false` if it has no color.
+///
+/// COLR is preferred over bitmaps when a glyph has both.
+pub(crate) fn draw(
+    font: &Font,
+    glyph: GlyphId,
+    origin: Point,
+    paint: &Paint,
+    device: &mut dyn Device,
+) -> Result<bool> {
+    let face = font.typeface().face();
+    let mut paint = paint.clone();
+    paint.style = PaintStyle::Fill;
+    paint.shader = None;
+    paint.path_effect = None;
+
+    let id = owned_ttf_parser::GlyphId(glyph.0);
+    if face.is_color_glyph(id) {
+        let matrix = *device.matrix();
+        let save_count = device.save_count();
+        let scale = font.scale();
+        let foreground = {
+            let [r, g, b, _] = paint.color.to_array().map(|v| (v * 255.0).round() as u8);
+            RgbaColor::new(r, g, b, u8::MAX)
+        };
+
+        let transform = matrix
+            .concat(&Matrix::translate(origin.x, origin.y))
+            .concat(&Matrix::scale(scale, -scale));
+        let coords = face.variation_coordinates();
+
+        // nothing is drawn outside the clip box, or the box every glyph of the face
+        // fits in when there is none, or the device
+        let glyph_box = match face
+            .tables()
+            .colr
+            .and_then(|colr| colr.clip_box(id, coords))
+        {
+            Some(b) => Rect::from_ltrb(b.x_min, b.y_min, b.x_max, b.y_max),
+            None => {
+                let b = face.global_bounding_box();
+                Rect::from_ltrb(
+                    b.x_min.into(),
+                    b.y_min.into(),
+                    b.x_max.into(),
+                    b.y_max.into(),
+                )
+            }
+        };
+        let info = device.image_info();
+        let device_box = Rect::from_xywh(0., 0., info.width as f32, info.height as f32);
+        let Some(bounds) = transform.map_rect(&glyph_box).intersect(&device_box) else {
+            return Ok(true);
+        };
+        let bounds = Rect::from_ltrb(
+            bounds.l.floor(),
+            bounds.t.floor(),
+            bounds.r.ceil(),
+            bounds.b.ceil(),
+        );
+
+        let mut painter = ColorPainter {
+            font,
+            coords,
+            device: &mut *device,
+            paint,
+            transform,
+            transforms: vec![],
+            outline: None,
+            clips: vec![],
+            bounds,
+            layers: vec![],
+            failed_layers: 0,
+            result: Ok(()),
+        };
+        face.paint_color_glyph(id, 0, foreground, &mut painter);
+        let result = painter.result;
+
+        while device.save_count() > save_count {
+            device.restore();
+        }
+        device.set_matrix(matrix);
+
+        return result.map(|_| true);
+    }
+
+    let ppem = (font.size() * device.matrix().max_scale()).round();
+    let Some(bitmap) = font
+        .typeface()
+        .bitmap(glyph, ppem.clamp(1.0, u16::MAX as f32) as u16)
+    else {
+        return Ok(false);
+    };
+
+    let scale = font.size() / bitmap.ppem;
+    let Rect { l, t, r, b } = bitmap.bounds;
+    let dst = Rect::from_ltrb(
+        origin.x + l * scale,
+        origin.y + t * scale,
+        origin.x + r * scale,
+        origin.y + b * scale,
+    );
+    let src = Rect::from_xywh(
+        0.,
+        0.,
+        bitmap.image.width() as f32,
+        bitmap.image.height() as f32,
+    );
+    let sampling = SamplingOptions::new(FilterMode::Linear, MipmapMode::Linear);
+    device.draw_image_rect(&bitmap.image, &src, &dst, &sampling, &paint)?;
+
+    Ok(true)
+}
+
+/// A clip pushed by the paint graph.
+struct Clip {
+    path: Path,
+    /// Maps `path` to the device.
+    matrix: Matrix,
+    /// The depth of the layer it is clipped on, restored when popped.
+    applied: Option<usize>,
+}
+
+/// Paints of a composite, blended with `mode` onto what is beneath once they are all drawn.
+struct Layer {
+    raster: Raster,
+    mode: BlendMode,
+}
+
+/// Draws a COLR glyph on a device, clipping and transforming it as the paint graph goes.
+///
+/// Clips are applied to the device only once something is painted inside a nested one:
+/// the innermost clip is filled directly, so its edges are anti-aliased once.
+/// The device has no layers, so the paints of a composite are drawn on a raster covering
+/// the glyph and blended onto the device or the enclosing raster when it is popped,
+/// leaving what is already drawn beneath the glyph to source over.
+struct ColorPainter<'a, 'd> {
+    font: &'a Font,
+    coords: &'a [NormalizedCoordinate],
+    device: &'d mut dyn Device,
+    /// The color, blend mode and anti-aliasing every paint starts from.
+    paint: Paint,
+    /// Maps the paint graph, in font units with y pointing up, to the device.
+    transform: Matrix,
+    transforms: Vec<Matrix>,
+    /// The glyph outlined last, in font units with y pointing down,
+    /// until it is painted or pushed as a clip.
+    outline: Option<Path>,
+    clips: Vec<Clip>,
+    /// The pixels of the device the glyph may cover.
+    bounds: Rect,
+    layers: Vec<Layer>,
+    /// Layers pushed after an error, without a raster.
+    failed_layers: usize,
+    /// The first error of the device, nothing is drawn after it.
+    result: Result<()>,
+}
+
+impl ColorPainter<'_, '_> {
+    fn fill(&mut self, paint: colr::Paint<'_>) -> Result<()> {
+        let nested = match self.outline {
+            Some(_) => self.clips.len(),
+            None => self.clips.len().saturating_sub(1),
+        };
+        let depth = self.layers.len();
+        for i in 0..nested {
+            if self.clips[i].applied.is_none() {
+                let (path, matrix) = (self.clips[i].path.clone(), self.clips[i].matrix);
+                let anti_alias = self.paint.anti_alias;
+
+                self.target().save();
+                self.set_matrix(matrix);
+                self.target()
+                    .clip_path(&path, ClipOp::Intersect, anti_alias)?;
+                self.clips[i].applied = Some(depth);
+            }
+        }
+
+        let (path, matrix) = match (self.outline.take(), self.clips.last()) {
+            (Some(path), _) => (path, self.transform.concat(&FLIP)),
+            (None, Some(clip)) => (clip.path.clone(), clip.matrix),
+            // an unclipped paint covers everything the glyph may
+            (None, None) => {
+                let mut path = Path::new();
+                path.add_rect(&self.bounds);
+                (path, Matrix::IDENTITY)
+            }
+        };
+
+        let Some(paint) = self.to_paint(paint, &matrix) else {
+            return Ok(());
+        };
+        self.set_matrix(matrix);
+        self.target().draw_path(&path, &paint)
+    }
+
+    /// The raster of the innermost layer, the device outside of composites.
+    fn target(&mut self) -> &mut dyn Device {
+        match self.layers.last_mut() {
+            Some(layer) => &mut layer.raster,
+            None => &mut *self.device,
+        }
+    }
+
+    /// Set `matrix`, which maps to the device, on the target.
+    fn set_matrix(&mut self, matrix: Matrix) {
+        let matrix = if self.layers.is_empty() {
+            matrix
+        } else {
+            Matrix::translate(-self.bounds.l, -self.bounds.t).concat(&matrix)
+        };
+        self.target().set_matrix(matrix);
+    }
+
+    /// Blend the innermost layer onto what is beneath it.
+    fn composite(&mut self, layer: Layer) -> Result<()> {
+        let info = *layer.raster.image_info();
+        let image = Image::from_pixels(info, layer.raster.pixels().to_vec())?;
+
+        let mut paint = self.paint.clone();
+        paint.color = paint.color.with_a(1.0);
+        // composites always start with a source over layer, which lands on the device
+        // like any other paint
+        if !self.layers.is_empty() {
+            paint.blend_mode = layer.mode;
+        }
+
+        let src = Rect::from_xywh(0., 0., info.width as f32, info.height as f32);
+        let dst = self.bounds;
+        self.set_matrix(Matrix::IDENTITY);
+        self.target()
+            .draw_image_rect(&image, &src, &dst, &SamplingOptions::default(), &paint)
+    }
+
+    /// The paint filling a path drawn with `matrix`, `None` if nothing would show.
+    fn to_paint(&self, paint: colr::Paint<'_>, matrix: &Matrix) -> Option<Paint> {
+        let mut out = self.paint.clone();
+        // layers are isolated, their own blend mode applies once they are popped
+        if !self.layers.is_empty() {
+            out.blend_mode = BlendMode::SrcOver;
+        }
+
+        // gradients are defined in the space of the paint graph
+        let local_matrix = matrix.invert()?.concat(&self.transform);
+        let palette = 0;
+
+        out.shader = Some(match paint {
+            colr::Paint::Solid(color) => {
+                let color = to_color(color);
+                out.color = color.with_a(color.a * self.paint.color.a);
+                return Some(out);
+            }
+            colr::Paint::LinearGradient(g) => {
+                let (gradient, first, last) = gradient(g.stops(palette, self.coords), g.extend)?;
+                let (p0, p1, p2) = (
+                    Point::new(g.x0, g.y0),
+                    Point::new(g.x1, g.y1),
+                    Point::new(g.x2, g.y2),
+                );
+
+                // colors are constant along p0 to p2, so the gradient runs from p0
+                // to p1 projected onto the normal of that line
+                let normal = p2 - p0;
+                let normal = dauber_core::geom::vector(-normal.y, normal.x);
+                let d = p1 - p0;
+                let p3 = match normal.dot(normal) {
+                    n if n > 0.0 => p0 + normal * (d.dot(normal) / n),
+                    _ => p1,
+                };
+
+                Shader::linear_gradient(
+                    p0.lerp(p3, first),
+                    p0.lerp(p3, last),
+                    gradient.with_local_matrix(local_matrix),
+                )
+            }
+            colr::Paint::RadialGradient(g) => {
+                let (gradient, first, last) = gradient(g.stops(palette, self.coords), g.extend)?;
+                let (c0, c1) = (Point::new(g.x0, g.y0), Point::new(g.x1, g.y1));
+                let radius = |t: f32| (g.r0 + (g.r1 - g.r0) * t).max(0.0);
+
+                Shader::two_point_conical_gradient(
+                    c0.lerp(c1, first),
+                    radius(first),
+                    c0.lerp(c1, last),
+                    radius(last),
+                    gradient.with_local_matrix(local_matrix),
+                )
+            }
+            colr::Paint::SweepGradient(g) => {
+                let (gradient, first, last) = gradient(g.stops(palette, self.coords), g.extend)?;
+                // in half turns, counter-clockwise with y pointing up
+                let angle = |t: f32| (g.start_angle + (g.end_angle - g.start_angle) * t) * 180.0;
+
+                Shader::sweep_gradient(
+                    Point::new(g.center_x, g.center_y),
+                    angle(first),
+                    angle(last),
+                    gradient.with_local_matrix(local_matrix),
+                )
+            }
+        });
+
+        Some(out)
+    }
+}
+
+impl<'a> Painter<'a> for ColorPainter<'a, '_> {
+    fn outline_glyph(&mut self, glyph_id: owned_ttf_parser::GlyphId) {
+        self.outline = Some(self.font.unit_path(GlyphId(glyph_id.0)).unwrap_or_default());
+    }
+
+    fn paint(&mut self, paint: colr::Paint<'a>) {
+        if self.result.is_ok() {
+            self.result = self.fill(paint);
+        }
+    }
+
+    fn push_clip(&mut self) {
+        self.clips.push(Clip {
+            path: self.outline.take().unwrap_or_default(),
+            matrix: self.transform.concat(&FLIP),
+            applied: None,
+        });
+    }
+
+    fn push_clip_box(&mut self, clip_box: ClipBox) {
+        let mut path = Path::new();
+        path.add_rect(&Rect::from_ltrb(
+            clip_box.x_min,
+            clip_box.y_min,
+            clip_box.x_max,
+            clip_box.y_max,
+        ));
+
+        self.clips.push(Clip {
+            path,
+            matrix: self.transform,
+            applied: None,
+        });
+    }
+
+    fn pop_clip(&mut self) {
+        // clips applied on a popped layer went with it
+        if let Some(Clip {
+            applied: Some(depth),
+            ..
+        }) = self.clips.pop()
+        {
+            if depth == self.layers.len() {
+                self.target().restore();
+            }
+        }
+    }
+
+    fn push_layer(&mut self, mode: CompositeMode) {
+        let info = ImageInfo::new_wh(self.bounds.width() as u32, self.bounds.height() as u32)
+            .with_color_space(self.device.image_info().color_space);
+
+        // nothing is drawn after an error, the layer is only counted to be popped
+        if self.result.is_err() {
+            self.failed_layers += 1;
+            return;
+        }
+        match Raster::try_new(info) {
+            Ok(raster) => self.layers.push(Layer {
+                raster,
+                mode: blend_mode(mode),
+            }),
+            Err(e) => {
+                self.result = Err(e);
+                self.failed_layers += 1;
+            }
+        }
+    }
+
+    fn pop_layer(&mut self) {
+        if self.failed_layers > 0 {
+            self.failed_layers -= 1;
+            return;
+        }
+        let Some(layer) = self.layers.pop() else {
+            return;
+        };
+        let depth = self.layers.len() + 1;
+        for clip in &mut self.clips {
+            if clip.applied == Some(depth) {
+                clip.applied = None;
+            }
+        }
+
+        if self.result.is_ok() {
+            self.result = self.composite(layer);
+        }
+    }
+
+    fn push_transform(&mut self, t: Transform) {
+        self.transforms.push(self.transform);
+        self.transform = self
+            .transform
+            .concat(&Matrix::from_row(t.a, t.c, t.e, t.b, t.d, t.f));
+    }
+
+    fn pop_transform(&mut self) {
+        if let Some(transform) = self.transforms.pop() {
+            self.transform = transform;
+        }
+    }
+}
+
+/// The stops of a color line spread over 0 to 1, with where the first and the last one
+/// were on the gradient geometry.
+fn gradient(
+    stops: impl Iterator<Item = ColorStop>,
+    extend: GradientExtend,
+) -> Option<(Gradient, f32, f32)> {
+    let mut stops: Vec<_> = stops
+        .map(|stop| GradientStop::new(stop.stop_offset, to_color(stop.color)))
+        .collect();
+    stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+
+    let (first, last) = (stops.first()?.offset, stops.last()?.offset);
+    if last > first {
+        for stop in &mut stops {
+            stop.offset = (stop.offset - first) / (last - first);
+        }
+    }
+
+    let tile_mode = match extend {
+        GradientExtend::Pad => TileMode::Clamp,
+        GradientExtend::Repeat => TileMode::Repeat,
+        GradientExtend::Reflect => TileMode::Mirror,
+    };
+
+    Some((Gradient::new(stops, tile_mode), first, last))
+}
+
+#[inline]
+fn to_color(color: RgbaColor) -> Color {
+    Color::from_rgba_u8(color.red, color.green, color.blue, color.alpha)
+}
+
+fn blend_mode(mode: CompositeMode) -> BlendMode {
+    match mode {
+        CompositeMode::Clear => BlendMode::Clear,
+        CompositeMode::Source => BlendMode::Src,
+        CompositeMode::Destination => BlendMode::Dst,
+        CompositeMode::SourceOver => BlendMode::SrcOver,
+        CompositeMode::DestinationOver => BlendMode::DstOver,
+        CompositeMode::SourceIn => BlendMode::SrcIn,
+        CompositeMode::DestinationIn => BlendMode::DstIn,
+        CompositeMode::SourceOut => BlendMode::SrcOut,
+        CompositeMode::DestinationOut => BlendMode::DstOut,
+        CompositeMode::SourceAtop => BlendMode::SrcATop,
+        CompositeMode::DestinationAtop => BlendMode::DstATop,
+        CompositeMode::Xor => BlendMode::Xor,
+        CompositeMode::Plus => BlendMode::Plus,
+        CompositeMode::Screen => BlendMode::Screen,
+        CompositeMode::Overlay => BlendMode::Overlay,
+        CompositeMode::Darken => BlendMode::Darken,
+        CompositeMode::Lighten => BlendMode::Lighten,
+        CompositeMode::ColorDodge => BlendMode::ColorDodge,
+        CompositeMode::ColorBurn => BlendMode::ColorBurn,
+        CompositeMode::HardLight => BlendMode::HardLight,
+        CompositeMode::SoftLight => BlendMode::SoftLight,
+        CompositeMode::Difference => BlendMode::Difference,
+        CompositeMode::Exclusion => BlendMode::Exclusion,
+        CompositeMode::Multiply => BlendMode::Multiply,
+        CompositeMode::Hue => BlendMode::Hue,
+        CompositeMode::Saturation => BlendMode::Saturation,
+        CompositeMode::Color => BlendMode::Color,
+        CompositeMode::Luminosity => BlendMode::Luminosity,
+    }
+}
@@ -0,0 +1,281 @@
+use crate::color;
+use crate::typeface::{GlyphId, Typeface};
+use dauber_core::device::Device;
+use dauber_core::error::Result;
+use dauber_core::geom::point::Point;
+use dauber_core::geom::rect::Rect;
+use dauber_core::glyph::GlyphSource;
+use dauber_core::paint::Paint;
+use dauber_core::path::Path;
+use owned_ttf_parser::OutlineBuilder;
+
+/// A typeface at a size, measuring and outlining glyphs in pixels.
+///
+/// Coordinates follow the canvas: y points down and glyphs sit on the baseline at y = 0.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Font {
+    typeface: Typeface,
+    /// Pixels per em.
+    size: f32,
+}
+
+impl Font {
+    #[inline]
+    pub fn new(typeface: Typeface, size: f32) -> Self {
+        Self { typeface, size }
+    }
+
+    #[inline]
+    pub fn typeface(&self) -> &Typeface {
+        &self.typeface
+    }
+
+    #[inline]
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    #[inline]
+    pub fn set_size(&mut self, size: f32) {
+        self.size = size;
+    }
+
+    /// Pixels per font unit.
+    #[inline]
+    pub fn scale(&self) -> f32 {
+        self.size / self.typeface.units_per_em() as f32
+    }
+
+    pub fn metrics(&self) -> FontMetrics {
+        let face = self.typeface.face();
+        let scale = self.scale();
+        let line = |metrics: Option<owned_ttf_parser::LineMetrics>| {
+            metrics.map(|m| (-m.position as f32 * scale, m.thickness as f32 * scale))
+        };
+
+        FontMetrics {
+            ascent: -face.ascender() as f32 * scale,
+            descent: -face.descender() as f32 * scale,
+            leading: face.line_gap() as f32 * scale,
+            cap_height: face.capital_height().map(|h| h as f32 * scale),
+            x_height: face.x_height().map(|h| h as f32 * scale),
+            underline: line(face.underline_metrics()),
+            strikeout: line(face.strikeout_metrics()),
+        }
+    }
+
+    /// The glyph `c` maps to, [`GlyphId::NOTDEF`] if the typeface does not cover it.
+    #[inline]
+    pub fn glyph_id(&self, c: char) -> GlyphId {
+        self.typeface.glyph_id(c).unwrap_or(GlyphId::NOTDEF)
+    }
+
+    /// The glyph of every character in `text`, without shaping.
+    pub fn glyph_ids(&self, text: &str) -> Vec<GlyphId> {
+        text.chars().map(|c| self.glyph_id(c)).collect()
+    }
+
+    pub fn glyph_metrics(&self, glyph: GlyphId) -> GlyphMetrics {
+        let face = self.typeface.face();
+        let id = owned_ttf_parser::GlyphId(glyph.0);
+        let scale = self.scale();
+
+        GlyphMetrics {
+            advance: face.glyph_hor_advance(id).unwrap_or(0) as f32 * scale,
+            bounds: face.glyph_bounding_box(id).map(|b| {
+                Rect::from_ltrb(
+                    b.x_min as f32 * scale,
+                    -b.y_max as f32 * scale,
+                    b.x_max as f32 * scale,
+                    -b.y_min as f32 * scale,
+                )
+            }),
+        }
+    }
+
+    /// The outline of `glyph` with its origin at (0, 0), `None` if it has none,
+    /// like a space or a bitmap glyph.
+    pub fn glyph_path(&self, glyph: GlyphId) -> Option<Path> {
+        let mut builder = PathBuilder::new(self.scale());
+        self.outline(glyph, &mut builder)?;
+
+        Some(builder.path)
+    }
+
+    /// The outlines of the characters in `text` placed one after another from `origin`,
+    /// without shaping or kerning.
+    pub fn text_path(&self, text: &str, origin: Point) -> Path {
+        let mut builder = PathBuilder::new(self.scale());
+        builder.origin = origin;
+
+        for glyph in self.glyph_ids(text) {
+            self.outline(glyph, &mut builder);
+            builder.origin.x += self.glyph_metrics(glyph).advance;
+        }
+
+        builder.path
+    }
+
+    /// Whether `glyph` is drawn in color, from COLR layers or paints or from a PNG or BGRA
+    /// bitmap in sbix or CBDT.
+    pub fn is_color_glyph(&self, glyph: GlyphId) -> bool {
+        let ppem = self.size.round().clamp(1.0, u16::MAX as f32) as u16;
+
+        self.typeface
+            .face()
+            .is_color_glyph(owned_ttf_parser::GlyphId(glyph.0))
+            || self.typeface.bitmap(glyph, ppem).is_some()
+    }
+
+    /// Draw `glyph` in color with its origin at `origin`, transformed by the device matrix,
+    /// `false` without drawing anything if it is not a [color glyph](Font::is_color_glyph).
+    ///
+    /// COLR glyphs use the first palette, with the paint color as the foreground color.
+    /// Bitmaps come from the strike closest to the size on the device. The alpha of the
+    /// paint color, its blend mode and anti-aliasing apply, its style, shader and path
+    /// effect are ignored.
+    pub fn draw_color_glyph(
+        &self,
+        glyph: GlyphId,
+        origin: Point,
+        paint: &Paint,
+        device: &mut dyn Device,
+    ) -> Result<bool> {
+        color::draw(self, glyph, origin, paint, device)
+    }
+
+    /// The outline of `glyph` in font units, with y pointing down.
+    pub(crate) fn unit_path(&self, glyph: GlyphId) -> Option<Path> {
+        let mut builder = PathBuilder::new(1.0);
+        self.outline(glyph, &mut builder)?;
+
+        Some(builder.path)
+    }
+
+    fn outline(&self, glyph: GlyphId, builder: &mut PathBuilder) -> Option<()> {
+        let outlined = self
+            .typeface
+            .face()
+            .outline_glyph(owned_ttf_parser::GlyphId(glyph.0), builder);
+        // CFF2 charstrings end without closing their last contour
+        if builder.open {
+            builder.close();
+        }
+
+        outlined.map(|_| ())
+    }
+}
+
+impl GlyphSource for Font {
+    #[inline]
+    fn typeface_id(&self) -> u32 {
+        self.typeface.unique_id()
+    }
+
+    #[inline]
+    fn size(&self) -> f32 {
+        self.size
+    }
+
+    fn append_outline(&self, glyph: GlyphId, origin: Point, path: &mut Path) -> bool {
+        let mut builder = PathBuilder::new(self.scale());
+        builder.path = std::mem::take(path);
+        builder.origin = origin;
+        let outlined = self.outline(glyph, &mut builder).is_some();
+        *path = builder.path;
+
+        outlined
+    }
+}
+
+/// Vertical metrics of a font in pixels, relative to the baseline with y pointing down.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FontMetrics {
+    /// The top of the tallest glyphs the font is designed for, negative.
+    pub ascent: f32,
+    /// The bottom of the deepest glyphs the font is designed for, positive.
+    pub descent: f32,
+    /// Extra space between the descent of one line and the ascent of the next.
+    pub leading: f32,
+    pub cap_height: Option<f32>,
+    pub x_height: Option<f32>,
+    /// Position of the top and thickness of the underline.
+    pub underline: Option<(f32, f32)>,
+    /// Position of the top and thickness of the strikeout.
+    pub strikeout: Option<(f32, f32)>,
+}
+
+impl FontMetrics {
+    /// The distance between the baselines of two lines.
+    #[inline]
+    pub fn line_height(&self) -> f32 {
+        self.descent - self.ascent + self.leading
+    }
+}
+
+/// Horizontal metrics and bounds of a glyph in pixels.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GlyphMetrics {
+    /// How far the pen moves after the glyph.
+    pub advance: f32,
+    /// The bounds of the outline relative to the origin, `None` without an outline.
+    pub bounds: Option<Rect>,
+}
+
+/// Collects outlines in font units into a path in pixels with y pointing down.
+struct PathBuilder {
+    path: Path,
+    scale: f32,
+    /// Where the origin of the glyph being outlined is placed.
+    origin: Point,
+    /// A contour was started and not closed yet.
+    open: bool,
+}
+
+impl PathBuilder {
+    #[inline]
+    fn new(scale: f32) -> Self {
+        Self {
+            path: Path::new(),
+            scale,
+            origin: Point::new(0., 0.),
+            open: false,
+        }
+    }
+
+    #[inline]
+    fn point(&self, x: f32, y: f32) -> Point {
+        Point::new(
+            self.origin.x + x * self.scale,
+            self.origin.y - y * self.scale,
+        )
+    }
+}
+
+impl OutlineBuilder for PathBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let p = self.point(x, y);
+        self.path.move_to(p);
+        self.open = true;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let p = self.point(x, y);
+        self.path.line_to(p);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (ctrl, to) = (self.point(x1, y1), self.point(x, y));
+        self.path.quad_to(ctrl, to);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (ctrl1, ctrl2, to) = (self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+        self.path.cubic_to(ctrl1, ctrl2, to);
+    }
+
+    fn close(&mut self) {
+        self.path.close();
+        self.open = false;
+    }
+}
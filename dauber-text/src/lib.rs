@@ -0,0 +1,6 @@
+mod color;
+pub mod font;
+pub mod paragraph;
+mod shaper;
+pub mod text_blob;
+pub mod typeface;
@@ -0,0 +1,756 @@
+use crate::font::Font;
+use crate::shaper;
+use crate::text_blob::ShapedRun;
+use dauber_core::geom::point::Point;
+use dauber_core::geom::rect::Rect;
+use dauber_core::paint::Paint;
+use std::ops::Range;
+use unicode_bidi::{BidiInfo, Level};
+use unicode_linebreak::{linebreaks, BreakOpportunity};
+use unicode_script::Script;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How lines are placed within the width of a paragraph.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Right,
+    Center,
+    /// Stretch the spaces of wrapped lines to fill the width, the last line of
+    /// a paragraph is aligned to the start of its direction.
+    Justify,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TextDirection {
+    Ltr,
+    Rtl,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParagraphStyle {
+    pub align: TextAlign,
+    /// The base direction, taken from the first strong character of each paragraph when `None`.
+    pub direction: Option<TextDirection>,
+    /// The height of a line as a multiple of its largest font size, with the extra space split
+    /// above and below. The line height of the fonts when `None`.
+    pub line_height: Option<f32>,
+    /// Lines past this many are left out.
+    pub max_lines: Option<usize>,
+    /// Ends the last line when text is left out or the line does not fit, like `"…"`.
+    ///
+    /// Only applies with [`max_lines`](ParagraphStyle::max_lines).
+    pub ellipsis: Option<String>,
+}
+
+/// The font and paint of a span of text.
+#[derive(Clone, Debug)]
+pub struct TextStyle {
+    pub font: Font,
+    pub paint: Paint,
+}
+
+impl TextStyle {
+    #[inline]
+    pub fn new(font: Font) -> Self {
+        Self {
+            font,
+            paint: Paint::new(),
+        }
+    }
+}
+
+/// Collects styled text into a [`Paragraph`].
+#[derive(Clone, Debug)]
+pub struct ParagraphBuilder {
+    style: ParagraphStyle,
+    text: String,
+    styles: Vec<TextStyle>,
+    spans: Vec<Span>,
+    /// Indices into `styles`, the last one applies to text added next.
+    stack: Vec<usize>,
+}
+
+/// Text from `start` up to the next span is in `styles[style]`.
+#[derive(Copy, Clone, Debug)]
+struct Span {
+    start: usize,
+    style: usize,
+}
+
+impl ParagraphBuilder {
+    pub fn new(style: ParagraphStyle, text_style: TextStyle) -> Self {
+        Self {
+            style,
+            text: String::new(),
+            styles: vec![text_style],
+            spans: vec![],
+            stack: vec![0],
+        }
+    }
+
+    /// Use `style` for text added until the matching [`pop_style`](ParagraphBuilder::pop_style).
+    pub fn push_style(&mut self, style: TextStyle) -> &mut Self {
+        self.stack.push(self.styles.len());
+        self.styles.push(style);
+
+        self
+    }
+
+    /// Go back to the style before the last [`push_style`](ParagraphBuilder::push_style),
+    /// the style passed to [`new`](ParagraphBuilder::new) is never popped.
+    pub fn pop_style(&mut self) -> &mut Self {
+        if self.stack.len() > 1 {
+            self.stack.pop();
+        }
+
+        self
+    }
+
+    /// Append `text` in the current style, `\n` starts a new paragraph.
+    pub fn add_text(&mut self, text: &str) -> &mut Self {
+        let style = self.stack[self.stack.len() - 1];
+        if !text.is_empty() && self.spans.last().map(|s| s.style) != Some(style) {
+            self.spans.push(Span {
+                start: self.text.len(),
+                style,
+            });
+        }
+        self.text.push_str(text);
+
+        self
+    }
+
+    /// Shape the text, call [`Paragraph::layout`] before using the paragraph.
+    pub fn build(self) -> Paragraph {
+        let mut paragraph = Paragraph {
+            text: self.text,
+            style: self.style,
+            styles: self.styles,
+            spans: self.spans,
+            items: vec![],
+            advances: vec![],
+            width: 0.0,
+            height: 0.0,
+            longest_line: 0.0,
+            lines: vec![],
+            runs: vec![],
+            exceeded_max_lines: false,
+        };
+        paragraph.shape();
+
+        paragraph
+    }
+}
+
+/// Styled text broken into lines, aligned and reordered for display.
+///
+/// Positions are relative to the top left corner of the paragraph, offsets into the text
+/// are in bytes.
+#[derive(Clone, Debug)]
+pub struct Paragraph {
+    text: String,
+    style: ParagraphStyle,
+    styles: Vec<TextStyle>,
+    spans: Vec<Span>,
+    /// Shaped in logical order, split where the direction, script or style changes.
+    items: Vec<Item>,
+    /// The advances of the clusters before each byte, so text can be measured without
+    /// placing its glyphs.
+    advances: Vec<f32>,
+
+    width: f32,
+    height: f32,
+    longest_line: f32,
+    lines: Vec<Line>,
+    /// Placed glyphs with the index of their style.
+    runs: Vec<(ShapedRun, usize)>,
+    exceeded_max_lines: bool,
+}
+
+#[derive(Clone, Debug)]
+struct Item {
+    run: ShapedRun,
+    style: usize,
+    /// The pen position before each glyph and after the last one.
+    pens: Vec<f32>,
+}
+
+#[derive(Clone, Debug)]
+struct Line {
+    metrics: LineMetrics,
+    rtl: bool,
+    /// Glyphs of the same characters, in visual order.
+    clusters: Vec<Cluster>,
+}
+
+#[derive(Clone, Debug)]
+struct Cluster {
+    range: Range<usize>,
+    x: f32,
+    advance: f32,
+    rtl: bool,
+}
+
+/// Where a line of a paragraph is and what it is made of.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineMetrics {
+    /// The text of the line, with trailing whitespace and the line break.
+    pub range: Range<usize>,
+    pub top: f32,
+    pub height: f32,
+    pub baseline: f32,
+    /// The largest ascent of the fonts on the line, negative.
+    pub ascent: f32,
+    /// The largest descent of the fonts on the line.
+    pub descent: f32,
+    /// Where the glyphs start after alignment.
+    pub left: f32,
+    /// How far the glyphs extend, without trailing whitespace.
+    pub width: f32,
+    /// The line ends at a `\n` or the end of the text rather than being wrapped.
+    pub hard_break: bool,
+}
+
+/// A line before it is placed.
+struct Plan {
+    range: Range<usize>,
+    hard_break: bool,
+    rtl: bool,
+    /// The end of the text that is drawn, without trailing whitespace or what the ellipsis replaces.
+    end: usize,
+    /// Pieces of items in visual order.
+    pieces: Vec<(usize, Range<usize>)>,
+    ellipsis: Option<(ShapedRun, usize)>,
+    width: f32,
+}
+
+impl Paragraph {
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The width passed to [`layout`](Paragraph::layout).
+    #[inline]
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    /// The width of the widest line.
+    #[inline]
+    pub fn longest_line(&self) -> f32 {
+        self.longest_line
+    }
+
+    /// Text was left out because of [`ParagraphStyle::max_lines`].
+    #[inline]
+    pub fn did_exceed_max_lines(&self) -> bool {
+        self.exceeded_max_lines
+    }
+
+    pub fn lines(&self) -> impl ExactSizeIterator<Item = &LineMetrics> + '_ {
+        self.lines.iter().map(|line| &line.metrics)
+    }
+
+    /// Placed glyphs with their style, in no particular order.
+    pub fn runs(&self) -> impl ExactSizeIterator<Item = (&ShapedRun, &TextStyle)> + '_ {
+        self.runs
+            .iter()
+            .map(|(run, style)| (run, &self.styles[*style]))
+    }
+
+    /// Break the text into lines no wider than `width` where possible and place them.
+    ///
+    /// Lines break where UAX #14 allows, a word wider than `width` overflows.
+    /// Pass `f32::INFINITY` to only break at `\n`.
+    pub fn layout(&mut self, width: f32) {
+        self.width = width;
+        self.exceeded_max_lines = false;
+
+        let mut breaks = self.break_lines(width);
+        if let Some(max) = self.style.max_lines {
+            if breaks.len() > max {
+                breaks.truncate(max);
+                self.exceeded_max_lines = true;
+            }
+        }
+
+        let info = BidiInfo::new(&self.text, self.base_level());
+        let count = breaks.len();
+        let plans: Vec<_> = breaks
+            .into_iter()
+            .enumerate()
+            .map(|(i, (range, hard_break))| {
+                let ellipsize = i + 1 == count
+                    && self.style.max_lines.is_some()
+                    && (self.exceeded_max_lines || self.measure(self.trim(range.clone())) > width);
+                self.plan(&info, range, hard_break, ellipsize)
+            })
+            .collect();
+
+        self.longest_line = plans.iter().map(|plan| plan.width).fold(0.0, f32::max);
+        let available = if width.is_finite() {
+            width
+        } else {
+            self.longest_line
+        };
+
+        self.lines.clear();
+        self.runs.clear();
+        let mut top = 0.0;
+        for plan in plans {
+            top = self.place(plan, available, top);
+        }
+        self.height = top;
+    }
+
+    /// Where a caret before the character at `offset` is drawn, as a rect without width
+    /// spanning its line.
+    pub fn caret_rect(&self, offset: usize) -> Rect {
+        let offset = self.floor_char_boundary(offset);
+        let Some(line) = self
+            .lines
+            .iter()
+            .rev()
+            .find(|line| line.metrics.range.start <= offset)
+            .or(self.lines.first())
+        else {
+            return Rect::from_xywh(0.0, 0.0, 0.0, 0.0);
+        };
+        let m = &line.metrics;
+
+        let x = match line.clusters.iter().find(|c| c.range.contains(&offset)) {
+            Some(c) => {
+                let graphemes = self.text[c.range.clone()].graphemes(true).count();
+                let before = self.text[c.range.start..offset].graphemes(true).count();
+                let t = before as f32 / graphemes as f32;
+                c.x + c.advance * if c.rtl { 1.0 - t } else { t }
+            }
+            // past the glyphs, at the end of the line
+            None if line.rtl => m.left,
+            None => m.left + m.width,
+        };
+
+        Rect::from_xywh(x, m.top, 0.0, m.height)
+    }
+
+    /// The offset of the caret position closest to `point`.
+    pub fn hit_test(&self, point: Point) -> usize {
+        let Some(line) = self
+            .lines
+            .iter()
+            .find(|line| point.y < line.metrics.top + line.metrics.height)
+            .or(self.lines.last())
+        else {
+            return 0;
+        };
+
+        let distance = |c: &Cluster| (c.x - point.x).max(point.x - c.x - c.advance).max(0.0);
+        let Some(c) = line
+            .clusters
+            .iter()
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+        else {
+            return line.metrics.range.start;
+        };
+
+        let mut t = if c.advance > 0.0 {
+            ((point.x - c.x) / c.advance).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        if c.rtl {
+            t = 1.0 - t;
+        }
+
+        let boundaries: Vec<_> = self.text[c.range.clone()]
+            .grapheme_indices(true)
+            .map(|(i, _)| c.range.start + i)
+            .chain([c.range.end])
+            .collect();
+        boundaries[(t * (boundaries.len() - 1) as f32).round() as usize]
+    }
+
+    /// Itemize and shape the whole text.
+    fn shape(&mut self) {
+        let info = BidiInfo::new(&self.text, self.base_level());
+        let mut items = vec![];
+
+        for para in &info.paragraphs {
+            let mut start = para.range.start;
+            while start < para.range.end {
+                let level = info.levels[start];
+                let style = self.style_at(start);
+                let end = self.text[start..para.range.end]
+                    .char_indices()
+                    .map(|(i, _)| start + i)
+                    .find(|&i| info.levels[i] != level || self.style_at(i) != style)
+                    .unwrap_or(para.range.end);
+
+                for (range, script) in shaper::script_runs(&self.text, start..end) {
+                    let font = &self.styles[style].font;
+                    let run = shaper::shape(&self.text, range, script, level.is_rtl(), font, 0.0);
+                    let mut pens = vec![0.0];
+                    pens.extend(run.advances.iter().scan(0.0, |pen, &advance| {
+                        *pen += advance;
+                        Some(*pen)
+                    }));
+                    items.push(Item { run, style, pens });
+                }
+                start = end;
+            }
+        }
+
+        let mut advances = vec![0.0; self.text.len() + 1];
+        for item in &items {
+            for (&cluster, &advance) in item.run.clusters.iter().zip(&item.run.advances) {
+                advances[cluster + 1] += advance;
+            }
+        }
+        for i in 1..advances.len() {
+            advances[i] += advances[i - 1];
+        }
+
+        self.items = items;
+        self.advances = advances;
+    }
+
+    /// Line ranges and whether they end at a hard break.
+    fn break_lines(&self, width: f32) -> Vec<(Range<usize>, bool)> {
+        let mut lines = vec![];
+        let mut start = 0;
+        // the last break the current line may end at
+        let mut fit = None;
+
+        for (end, opportunity) in linebreaks(&self.text) {
+            if let Some(fit) = fit {
+                if self.measure(self.trim(start..end)) > width {
+                    lines.push((start..fit, false));
+                    start = fit;
+                }
+            }
+
+            if opportunity == BreakOpportunity::Mandatory {
+                lines.push((start..end, true));
+                start = end;
+                fit = None;
+            } else {
+                fit = Some(end);
+            }
+        }
+
+        // empty text and a final line break leave an empty line for the caret
+        let line_break = |c: char| {
+            matches!(
+                c,
+                '\n' | '\u{b}' | '\u{c}' | '\r' | '\u{85}' | '\u{2028}' | '\u{2029}'
+            )
+        };
+        if lines.is_empty() || self.text.ends_with(line_break) {
+            lines.push((self.text.len()..self.text.len(), true));
+        }
+
+        lines
+    }
+
+    /// Order the glyphs of a line and measure it.
+    fn plan(
+        &self,
+        info: &BidiInfo<'_>,
+        range: Range<usize>,
+        hard_break: bool,
+        ellipsize: bool,
+    ) -> Plan {
+        let mut content = self.trim(range.clone());
+        let para = info
+            .paragraphs
+            .iter()
+            .find(|p| p.range.contains(&range.start))
+            .or(info.paragraphs.last());
+        let rtl = para.is_some_and(|p| p.level.is_rtl());
+
+        let ellipsis = self.style.ellipsis.as_ref().filter(|_| ellipsize).map(|s| {
+            let style = self.style_at(content.end.max(content.start + 1) - 1);
+            let font = &self.styles[style].font;
+            let run = shaper::shape(s, 0..s.len(), Script::Common, rtl, font, 0.0);
+
+            content = self.truncate(content.clone(), self.width - run.advance);
+            (run, style)
+        });
+
+        let mut pieces = vec![];
+        if let (Some(para), false) = (para, content.is_empty()) {
+            let (levels, runs) = info.visual_runs(para, content.clone());
+            for run in runs {
+                // items cover the text in logical order
+                let first = self
+                    .items
+                    .partition_point(|item| item.run.range.end <= run.start);
+                let mut parts: Vec<_> = self.items[first..]
+                    .iter()
+                    .take_while(|item| item.run.range.start < run.end)
+                    .enumerate()
+                    .map(|(i, item)| {
+                        (
+                            first + i,
+                            run.start.max(item.run.range.start)..run.end.min(item.run.range.end),
+                        )
+                    })
+                    .collect();
+                if levels[run.start].is_rtl() {
+                    parts.reverse();
+                }
+                pieces.extend(parts);
+            }
+        }
+
+        let width = pieces
+            .iter()
+            .flat_map(|(i, piece)| &self.items[*i].run.advances[self.glyphs(*i, piece.clone())])
+            .sum::<f32>()
+            + ellipsis.as_ref().map_or(0.0, |(run, _)| run.advance);
+
+        Plan {
+            range,
+            hard_break,
+            rtl,
+            end: content.end,
+            pieces,
+            ellipsis,
+            width,
+        }
+    }
+
+    /// Align the glyphs of `plan` within `available` and place them on a line starting at `top`,
+    /// returns the top of the next line.
+    fn place(&mut self, plan: Plan, available: f32, top: f32) -> f32 {
+        let mut styles: Vec<_> = plan
+            .pieces
+            .iter()
+            .map(|(i, _)| self.items[*i].style)
+            .collect();
+        styles.extend(plan.ellipsis.as_ref().map(|(_, style)| *style));
+        if styles.is_empty() {
+            styles.push(self.style_at(plan.range.start));
+        }
+
+        let (mut ascent, mut descent, mut leading, mut size) = (0.0f32, 0.0f32, 0.0f32, 0.0f32);
+        for style in styles {
+            let font = &self.styles[style].font;
+            let metrics = font.metrics();
+            ascent = ascent.min(metrics.ascent);
+            descent = descent.max(metrics.descent);
+            leading = leading.max(metrics.leading);
+            size = size.max(font.size());
+        }
+        let (height, baseline) = match self.style.line_height {
+            Some(line_height) => {
+                let height = line_height * size;
+                (height, top + (height - (descent - ascent)) * 0.5 - ascent)
+            }
+            None => (descent - ascent + leading, top + leading * 0.5 - ascent),
+        };
+
+        let spaces = plan
+            .pieces
+            .iter()
+            .flat_map(|(i, piece)| self.glyphs(*i, piece.clone()).map(|j| (*i, j)))
+            .filter(|&(i, j)| self.is_space(i, j))
+            .count();
+        let justify = self.style.align == TextAlign::Justify
+            && !plan.hard_break
+            && plan.ellipsis.is_none()
+            && spaces > 0
+            && plan.width < available;
+        let extra = if justify {
+            (available - plan.width) / spaces as f32
+        } else {
+            0.0
+        };
+        let width = if justify { available } else { plan.width };
+
+        let left = match self.style.align {
+            TextAlign::Left => 0.0,
+            TextAlign::Right => available - width,
+            TextAlign::Center => (available - width) * 0.5,
+            TextAlign::Justify if plan.rtl => available - width,
+            TextAlign::Justify => 0.0,
+        };
+
+        let mut pen = left;
+        let mut clusters: Vec<Cluster> = vec![];
+        let ellipsis = plan.ellipsis.map(|(mut run, style)| {
+            // the ellipsis stands in for the text after the line
+            run.clusters.fill(plan.range.end);
+            run.range = plan.range.end..plan.range.end;
+            (run, style)
+        });
+        let place_ellipsis = |runs: &mut Vec<(ShapedRun, usize)>, pen: &mut f32| {
+            if let Some((mut run, style)) = ellipsis.clone() {
+                for p in &mut run.positions {
+                    *p = Point::new(p.x + *pen, p.y + baseline);
+                }
+                *pen += run.advance;
+                runs.push((run, style));
+            }
+        };
+
+        let mut runs = vec![];
+        if plan.rtl {
+            place_ellipsis(&mut runs, &mut pen);
+        }
+        for (i, piece) in &plan.pieces {
+            let item = &self.items[*i];
+            let source = &item.run;
+            let mut run = ShapedRun {
+                font: source.font.clone(),
+                glyphs: vec![],
+                positions: vec![],
+                advances: vec![],
+                clusters: vec![],
+                range: piece.clone(),
+                rtl: source.rtl,
+                advance: 0.0,
+            };
+
+            let start = pen;
+            for j in self.glyphs(*i, piece.clone()) {
+                let cluster = source.clusters[j];
+                let offset = source.positions[j];
+                let advance = source.advances[j] + if self.is_space(*i, j) { extra } else { 0.0 };
+
+                run.glyphs.push(source.glyphs[j]);
+                run.positions.push(Point::new(
+                    pen + offset.x - item.pens[j],
+                    baseline + offset.y,
+                ));
+                run.advances.push(advance);
+                run.clusters.push(cluster);
+
+                match clusters.last_mut() {
+                    Some(c) if c.range.start == cluster => c.advance += advance,
+                    _ => clusters.push(Cluster {
+                        range: cluster..cluster,
+                        x: pen,
+                        advance,
+                        rtl: source.rtl,
+                    }),
+                }
+                pen += advance;
+            }
+
+            run.advance = pen - start;
+            runs.push((run, item.style));
+        }
+        if !plan.rtl {
+            place_ellipsis(&mut runs, &mut pen);
+        }
+
+        // a cluster ends where the next one in logical order starts
+        let mut starts: Vec<_> = clusters.iter().map(|c| c.range.start).collect();
+        starts.sort_unstable();
+        for c in &mut clusters {
+            let next = starts.partition_point(|&s| s <= c.range.start);
+            c.range.end = starts.get(next).copied().unwrap_or(plan.end);
+        }
+
+        self.runs.extend(runs);
+        self.lines.push(Line {
+            metrics: LineMetrics {
+                range: plan.range,
+                top,
+                height,
+                baseline,
+                ascent,
+                descent,
+                left,
+                width: pen - left,
+                hard_break: plan.hard_break,
+            },
+            rtl: plan.rtl,
+            clusters,
+        });
+
+        top + height
+    }
+
+    /// Glyphs of `items[i]` shaped from `range`.
+    ///
+    /// Clusters only grow in logical order, so the glyphs of a range are next to each other.
+    fn glyphs(&self, i: usize, range: Range<usize>) -> Range<usize> {
+        let run = &self.items[i].run;
+        let glyph = |offset| {
+            if run.rtl {
+                run.clusters.partition_point(|&c| c >= offset)
+            } else {
+                run.clusters.partition_point(|&c| c < offset)
+            }
+        };
+
+        if run.rtl {
+            glyph(range.end)..glyph(range.start)
+        } else {
+            glyph(range.start)..glyph(range.end)
+        }
+    }
+
+    /// Whether glyph `j` of `items[i]` is a space that justification stretches.
+    fn is_space(&self, i: usize, j: usize) -> bool {
+        self.text[self.items[i].run.clusters[j]..].starts_with(' ')
+    }
+
+    /// The advance of the clusters starting in `range`.
+    #[inline]
+    fn measure(&self, range: Range<usize>) -> f32 {
+        self.advances[range.end] - self.advances[range.start]
+    }
+
+    /// `range` without trailing whitespace.
+    fn trim(&self, range: Range<usize>) -> Range<usize> {
+        let trimmed = self.text[range.clone()].trim_end();
+        range.start..range.start + trimmed.len()
+    }
+
+    /// The longest part of `range` ending at a grapheme boundary that fits in `width`,
+    /// without trailing whitespace.
+    fn truncate(&self, range: Range<usize>, width: f32) -> Range<usize> {
+        let end = self.text[range.clone()]
+            .grapheme_indices(true)
+            .map(|(i, g)| range.start + i + g.len())
+            .take_while(|&end| self.measure(range.start..end) <= width)
+            .last()
+            .unwrap_or(range.start);
+
+        self.trim(range.start..end)
+    }
+
+    fn style_at(&self, offset: usize) -> usize {
+        match self.spans.partition_point(|span| span.start <= offset) {
+            0 => self.spans.first().map_or(0, |span| span.style),
+            i => self.spans[i - 1].style,
+        }
+    }
+
+    fn base_level(&self) -> Option<Level> {
+        self.style.direction.map(|direction| match direction {
+            TextDirection::Ltr => Level::ltr(),
+            TextDirection::Rtl => Level::rtl(),
+        })
+    }
+
+    fn floor_char_boundary(&self, offset: usize) -> usize {
+        let mut offset = offset.min(self.text.len());
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        offset
+    }
+}
@@ -0,0 +1,122 @@
+use crate::font::Font;
+use crate::text_blob::ShapedRun;
+use crate::typeface::GlyphId;
+use dauber_core::geom::point::Point;
+use rustybuzz::ttf_parser::Tag;
+use rustybuzz::{Direction, UnicodeBuffer};
+use std::ops::Range;
+use unicode_bidi::BidiInfo;
+use unicode_script::{Script, UnicodeScript};
+
+/// Shape `text` as a single line, one run per direction and script, in visual order.
+pub(crate) fn shape_line(text: &str, font: &Font) -> Vec<ShapedRun> {
+    let info = BidiInfo::new(text, None);
+    let mut runs = vec![];
+    let mut x = 0.0;
+
+    for para in &info.paragraphs {
+        let (levels, level_runs) = info.visual_runs(para, para.range.clone());
+        for range in level_runs {
+            let rtl = levels[range.start].is_rtl();
+            let mut scripts = script_runs(text, range);
+            if rtl {
+                scripts.reverse();
+            }
+
+            for (range, script) in scripts {
+                let run = shape(text, range, script, rtl, font, x);
+                x += run.advance();
+                runs.push(run);
+            }
+        }
+    }
+
+    runs
+}
+
+/// Split `range` of `text` where the script changes.
+///
+/// Common and inherited characters, like spaces and combining marks, join the script
+/// around them.
+pub(crate) fn script_runs(text: &str, range: Range<usize>) -> Vec<(Range<usize>, Script)> {
+    let mut runs: Vec<(Range<usize>, Script)> = vec![];
+
+    for (i, c) in text[range.clone()].char_indices() {
+        let start = range.start + i;
+        let end = start + c.len_utf8();
+        let script = c.script();
+        let neutral = |s: Script| matches!(s, Script::Common | Script::Inherited | Script::Unknown);
+
+        match runs.last_mut() {
+            Some((run, s)) if *s == script || neutral(script) => run.end = end,
+            Some((run, s)) if neutral(*s) => {
+                *s = script;
+                run.end = end;
+            }
+            _ => runs.push((start..end, script)),
+        }
+    }
+
+    runs
+}
+
+/// Shape `range` of `text` in one direction and script with its pen starting at `x`.
+pub(crate) fn shape(
+    text: &str,
+    range: Range<usize>,
+    script: Script,
+    rtl: bool,
+    font: &Font,
+    x: f32,
+) -> ShapedRun {
+    let mut buffer = UnicodeBuffer::new();
+    for (i, c) in text[range.clone()].char_indices() {
+        buffer.add(c, (range.start + i) as u32);
+    }
+    // the text around the run still decides how its ends join
+    buffer.set_pre_context(&text[..range.start]);
+    buffer.set_post_context(&text[range.end..]);
+    buffer.set_direction(if rtl {
+        Direction::RightToLeft
+    } else {
+        Direction::LeftToRight
+    });
+    if let Some(script) =
+        rustybuzz::Script::from_iso15924_tag(Tag::from_bytes_lossy(script.short_name().as_bytes()))
+    {
+        buffer.set_script(script);
+    }
+    buffer.guess_segment_properties();
+
+    let output = rustybuzz::shape(font.typeface().shaper(), &[], buffer);
+    let scale = font.scale();
+    let mut glyphs = Vec::with_capacity(output.len());
+    let mut positions = Vec::with_capacity(output.len());
+    let mut advances = Vec::with_capacity(output.len());
+    let mut clusters = Vec::with_capacity(output.len());
+    let mut pen = x;
+
+    for (info, pos) in output.glyph_infos().iter().zip(output.glyph_positions()) {
+        glyphs.push(GlyphId(info.glyph_id as u16));
+        positions.push(Point::new(
+            pen + pos.x_offset as f32 * scale,
+            -pos.y_offset as f32 * scale,
+        ));
+        clusters.push(info.cluster as usize);
+
+        let advance = pos.x_advance as f32 * scale;
+        advances.push(advance);
+        pen += advance;
+    }
+
+    ShapedRun {
+        font: font.clone(),
+        glyphs,
+        positions,
+        advances,
+        clusters,
+        range,
+        rtl,
+        advance: pen - x,
+    }
+}
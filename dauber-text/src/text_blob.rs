@@ -0,0 +1,103 @@
+use crate::font::Font;
+use crate::shaper;
+use crate::typeface::GlyphId;
+use dauber_core::geom::point::Point;
+use dauber_core::glyph::GlyphRun;
+use std::ops::Range;
+
+/// Shaped text, drawable any number of times without shaping it again.
+///
+/// Glyphs are placed relative to the left end of the baseline at (0, 0).
+#[derive(Clone, Debug, Default)]
+pub struct TextBlob {
+    runs: Vec<ShapedRun>,
+}
+
+impl TextBlob {
+    /// Shape `text` as a single line with ligatures, kerning and mark positioning.
+    ///
+    /// Mixed directions are reordered by the Unicode bidirectional algorithm,
+    /// each run is shaped in its own direction and script.
+    pub fn from_text(text: &str, font: &Font) -> Self {
+        Self {
+            runs: shaper::shape_line(text, font),
+        }
+    }
+
+    /// Runs in visual order, from left to right.
+    #[inline]
+    pub fn runs(&self) -> &[ShapedRun] {
+        &self.runs
+    }
+
+    /// How far the pen moves over all runs.
+    pub fn advance(&self) -> f32 {
+        self.runs.iter().map(ShapedRun::advance).sum()
+    }
+}
+
+/// Glyphs shaped from part of a text in one font, direction and script.
+#[derive(Clone, Debug)]
+pub struct ShapedRun {
+    pub(crate) font: Font,
+    pub(crate) glyphs: Vec<GlyphId>,
+    /// Origins of the glyphs relative to the blob.
+    pub(crate) positions: Vec<Point>,
+    /// How far the pen moves after each glyph.
+    pub(crate) advances: Vec<f32>,
+    /// Byte offset of the first character each glyph was shaped from.
+    pub(crate) clusters: Vec<usize>,
+    /// The bytes of the text the run was shaped from.
+    pub(crate) range: Range<usize>,
+    pub(crate) rtl: bool,
+    pub(crate) advance: f32,
+}
+
+impl ShapedRun {
+    #[inline]
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    /// Glyphs in visual order.
+    #[inline]
+    pub fn glyphs(&self) -> &[GlyphId] {
+        &self.glyphs
+    }
+
+    /// Origins of the glyphs relative to the blob.
+    #[inline]
+    pub fn positions(&self) -> &[Point] {
+        &self.positions
+    }
+
+    /// Byte offset in the text of the first character each glyph was shaped from.
+    ///
+    /// Glyphs of one cluster, like a base and its marks, share the offset.
+    #[inline]
+    pub fn clusters(&self) -> &[usize] {
+        &self.clusters
+    }
+
+    /// The bytes of the text the run was shaped from.
+    #[inline]
+    pub fn text_range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    #[inline]
+    pub fn is_rtl(&self) -> bool {
+        self.rtl
+    }
+
+    /// How far the pen moves over the run.
+    #[inline]
+    pub fn advance(&self) -> f32 {
+        self.advance
+    }
+
+    #[inline]
+    pub fn glyph_run(&self) -> GlyphRun<'_> {
+        GlyphRun::new(&self.font, &self.glyphs, &self.positions)
+    }
+}
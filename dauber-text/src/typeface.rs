@@ -0,0 +1,169 @@
+use crate::color::{self, Bitmap};
+use dauber_core::error::{Error, Result};
+use owned_ttf_parser::{name_id, AsFaceRef, Face, OwnedFace};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+pub use dauber_core::glyph::GlyphId;
+
+/// A parsed TrueType or OpenType face, cheap to clone.
+///
+/// Outlines may come from `glyf`, `CFF` or `CFF2` tables. Variable fonts are used
+/// at their default instance.
+#[derive(Clone)]
+pub struct Typeface {
+    face: Arc<Faces>,
+    id: u32,
+    bitmaps: Arc<Mutex<Bitmaps>>,
+}
+
+type ShapingFace<'a> = rustybuzz::Face<'a>;
+
+self_cell::self_cell!(
+    /// A parsed face and the shaping tables built from it once.
+    struct Faces {
+        owner: OwnedFace,
+
+        #[covariant]
+        dependent: ShapingFace,
+    }
+);
+
+/// Color bitmaps by glyph and requested pixels per em, decoded on first use.
+type Bitmaps = HashMap<(GlyphId, u16), Option<Bitmap>>;
+
+impl Typeface {
+    /// Parse the face at `index` of a font file or collection.
+    pub fn from_data(data: Vec<u8>, index: u32) -> Result<Self> {
+        static NEXT_ID: AtomicU32 = AtomicU32::new(1);
+
+        let face = OwnedFace::from_vec(data, index).map_err(|e| Error::Font(e.into()))?;
+
+        Ok(Self {
+            face: Arc::new(Faces::new(face, |face| {
+                rustybuzz::Face::from_face(face.as_face_ref().clone())
+            })),
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            bitmaps: Arc::default(),
+        })
+    }
+
+    /// The number of faces in `data`, 1 unless it is a collection.
+    pub fn count(data: &[u8]) -> u32 {
+        owned_ttf_parser::fonts_in_collection(data).unwrap_or(1)
+    }
+
+    /// Identifies the face, clones share it.
+    #[inline]
+    pub fn unique_id(&self) -> u32 {
+        self.id
+    }
+
+    #[inline]
+    pub(crate) fn face(&self) -> &Face<'_> {
+        self.face.borrow_owner().as_face_ref()
+    }
+
+    #[inline]
+    pub(crate) fn shaper(&self) -> &rustybuzz::Face<'_> {
+        self.face.borrow_dependent()
+    }
+
+    /// The typographic family name if present, the family name otherwise.
+    pub fn family_name(&self) -> Option<String> {
+        let names = self.face().names();
+        [name_id::TYPOGRAPHIC_FAMILY, name_id::FAMILY]
+            .into_iter()
+            .find_map(|id| {
+                names
+                    .into_iter()
+                    .filter(|name| name.name_id == id && name.is_unicode())
+                    .find_map(|name| name.to_string())
+            })
+    }
+
+    #[inline]
+    pub fn units_per_em(&self) -> u16 {
+        self.face().units_per_em()
+    }
+
+    #[inline]
+    pub fn glyph_count(&self) -> u16 {
+        self.face().number_of_glyphs()
+    }
+
+    #[inline]
+    pub fn is_bold(&self) -> bool {
+        self.face().is_bold()
+    }
+
+    #[inline]
+    pub fn is_italic(&self) -> bool {
+        self.face().is_italic()
+    }
+
+    #[inline]
+    pub fn is_monospaced(&self) -> bool {
+        self.face().is_monospaced()
+    }
+
+    /// The glyph `c` maps to, `None` if the typeface does not cover it.
+    #[inline]
+    pub fn glyph_id(&self, c: char) -> Option<GlyphId> {
+        self.face().glyph_index(c).map(|id| GlyphId(id.0))
+    }
+
+    /// Whether some glyphs may be drawn in color, from COLR, sbix or CBDT.
+    pub fn has_color_glyphs(&self) -> bool {
+        let tables = self.face().tables();
+        tables.colr.is_some() || self.has_bitmaps()
+    }
+
+    #[inline]
+    fn has_bitmaps(&self) -> bool {
+        let tables = self.face().tables();
+        tables.sbix.is_some() || tables.cbdt.is_some()
+    }
+
+    /// The color bitmap of `glyph` for `ppem` pixels per em.
+    pub(crate) fn bitmap(&self, glyph: GlyphId, ppem: u16) -> Option<Bitmap> {
+        // emoji fonts hold thousands of bitmaps, start over once this many are decoded
+        const MAX_BITMAPS: usize = 256;
+
+        if !self.has_bitmaps() {
+            return None;
+        }
+
+        let mut bitmaps = self.bitmaps.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(bitmap) = bitmaps.get(&(glyph, ppem)) {
+            return bitmap.clone();
+        }
+        if bitmaps.len() >= MAX_BITMAPS {
+            bitmaps.clear();
+        }
+
+        let bitmap = color::decode_bitmap(self.face(), glyph, ppem);
+        bitmaps.insert((glyph, ppem), bitmap.clone());
+
+        bitmap
+    }
+}
+
+impl fmt::Debug for Typeface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Typeface")
+            .field("family_name", &self.family_name())
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+/// Typefaces are equal when they share their face.
+impl PartialEq for Typeface {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
@@ -0,0 +1,143 @@
+use dauber_core::color::Color;
+use dauber_core::device::{Device, DeviceFactory};
+use dauber_core::geom::matrix::Matrix;
+use dauber_core::geom::point;
+use dauber_core::image_info::ImageInfo;
+use dauber_core::paint::Paint;
+use dauber_software::device::Raster;
+use dauber_text::font::Font;
+use dauber_text::typeface::{GlyphId, Typeface};
+
+/// Color glyphs filling the em square, see generate.py.
+const COLOR: &[u8] = include_bytes!("fonts/test-color.ttf");
+const SBIX: &[u8] = include_bytes!("fonts/test-sbix.ttf");
+const GLYF: &[u8] = include_bytes!("fonts/test-glyf.ttf");
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const GREEN: [u8; 4] = [0, 255, 0, 255];
+const BLUE: [u8; 4] = [0, 0, 255, 255];
+const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+fn font(data: &[u8], size: f32) -> Font {
+    Font::new(Typeface::from_data(data.to_vec(), 0).unwrap(), size)
+}
+
+/// Draw the glyph of `c` on a 100x100 raster with the em square filling it.
+fn draw(font: &Font, c: char, paint: &Paint) -> Raster {
+    draw_over(font, c, paint, Color::TRANSPARENT)
+}
+
+/// Draw the glyph of `c` like [`draw`] on a raster filled with `background`.
+fn draw_over(font: &Font, c: char, paint: &Paint, background: Color) -> Raster {
+    let mut raster = Raster::new(ImageInfo::new_wh(100, 100));
+    raster.clear(background);
+    let scale = 100. / font.size();
+    raster.set_matrix(Matrix::scale(scale, scale));
+
+    let drawn = font
+        .draw_color_glyph(font.glyph_id(c), point(0., font.size()), paint, &mut raster)
+        .unwrap();
+    assert!(drawn, "{c} is not a color glyph");
+    assert_eq!(raster.save_count(), 1);
+    assert_eq!(*raster.matrix(), Matrix::scale(scale, scale));
+
+    raster
+}
+
+fn pixel(raster: &Raster, x: usize, y: usize) -> [u8; 4] {
+    raster.pixels()[(y * 100 + x) * 4..][..4]
+        .try_into()
+        .unwrap()
+}
+
+#[test]
+fn color_glyphs() {
+    let font = font(COLOR, 100.);
+    assert!(font.typeface().has_color_glyphs());
+    assert!(!self::font(GLYF, 100.).typeface().has_color_glyphs());
+
+    let color: Vec<_> = "ABCDFx"
+        .chars()
+        .map(|c| font.is_color_glyph(font.glyph_id(c)))
+        .collect();
+    assert_eq!(color, [true, true, true, true, true, false]);
+
+    // plain outlines are left to the caller
+    let mut raster = Raster::new(ImageInfo::new_wh(100, 100));
+    let x = font.glyph_id('x');
+    assert!(!font
+        .draw_color_glyph(x, point(0., 100.), &Paint::new(), &mut raster)
+        .unwrap());
+    assert!(!font
+        .draw_color_glyph(GlyphId::NOTDEF, point(0., 100.), &Paint::new(), &mut raster)
+        .unwrap());
+    assert!(raster.pixels().iter().all(|&v| v == 0));
+}
+
+#[test]
+fn colr_layers() {
+    // the paint color stands in for the foreground color, at any scale
+    let mut paint = Paint::new();
+    paint.set_color(Color::BLUE);
+    for size in [100., 50.] {
+        let raster = draw(&font(COLOR, size), 'A', &paint);
+        assert_eq!(pixel(&raster, 50, 75), RED);
+        assert_eq!(pixel(&raster, 50, 25), BLUE);
+    }
+
+    // the alpha of the paint applies to every layer, so the red one shows through
+    paint.set_color(Color::BLUE.with_a(0.5));
+    let raster = draw(&font(COLOR, 100.), 'A', &paint);
+    assert_eq!(pixel(&raster, 50, 75), [128, 0, 0, 128]);
+    assert_eq!(pixel(&raster, 50, 25), [64, 0, 128, 192]);
+}
+
+#[test]
+fn colr_paints() {
+    let font = font(COLOR, 100.);
+    let paint = Paint::new();
+
+    // red to blue from left to right, clipped to the bottom half
+    let raster = draw(&font, 'B', &paint);
+    let red = |x| pixel(&raster, x, 75)[0];
+    assert_eq!((red(0), red(49), red(99)), (254, 129, 1));
+    assert!((0..100).all(|x| pixel(&raster, x, 75) == [red(x), 0, 255 - red(x), 255]));
+    assert_eq!(pixel(&raster, 50, 25), CLEAR);
+
+    // half transparent blue moved onto the left half of green
+    let raster = draw(&font, 'C', &paint);
+    assert_eq!(pixel(&raster, 75, 50), GREEN);
+    assert_eq!(pixel(&raster, 25, 50), [0, 128, 127, 255]);
+
+    // the top half cut out of red
+    let raster = draw(&font, 'D', &paint);
+    assert_eq!(pixel(&raster, 50, 75), RED);
+    assert_eq!(pixel(&raster, 50, 25), CLEAR);
+
+    // without cutting into what is beneath the glyph
+    let raster = draw_over(&font, 'D', &paint, Color::GREEN);
+    assert_eq!(pixel(&raster, 50, 75), RED);
+    assert_eq!(pixel(&raster, 50, 25), GREEN);
+}
+
+#[test]
+fn bitmaps() {
+    let paint = Paint::new();
+
+    // a PNG in CBDT, green on the left and red on the right
+    let raster = draw(&font(COLOR, 100.), 'F', &paint);
+    assert_eq!(pixel(&raster, 20, 50), GREEN);
+    assert_eq!(pixel(&raster, 80, 50), RED);
+    assert_eq!(pixel(&raster, 80, 5), RED);
+
+    // a PNG in sbix, red on the top and blue on the bottom, drawn the same at any scale
+    let sbix = font(SBIX, 100.);
+    assert!(sbix.is_color_glyph(sbix.glyph_id('E')));
+    let raster = draw(&sbix, 'E', &paint);
+    assert_eq!(pixel(&raster, 50, 20), RED);
+    assert_eq!(pixel(&raster, 50, 80), BLUE);
+    assert_eq!(
+        draw(&font(SBIX, 25.), 'E', &paint).pixels(),
+        raster.pixels()
+    );
+}
@@ -0,0 +1,157 @@
+use dauber_core::color::Color;
+use dauber_core::device::{Device, DeviceFactory};
+use dauber_core::geom::point;
+use dauber_core::geom::point::Point;
+use dauber_core::geom::rect::Rect;
+use dauber_core::image_info::ImageInfo;
+use dauber_core::paint::Paint;
+use dauber_core::path::{Path, PathSegment};
+use dauber_core::Error;
+use dauber_software::device::Raster;
+use dauber_text::font::Font;
+use dauber_text::typeface::{GlyphId, Typeface};
+
+const GLYF: &[u8] = include_bytes!("fonts/test-glyf.ttf");
+const CFF: &[u8] = include_bytes!("fonts/test-cff.otf");
+const CFF2: &[u8] = include_bytes!("fonts/test-cff2.otf");
+
+/// The test fonts at 100 pixels per em, so one pixel is ten font units.
+fn font(data: &[u8]) -> Font {
+    Font::new(Typeface::from_data(data.to_vec(), 0).unwrap(), 100.)
+}
+
+/// The verbs of `path` as letters and its points, curves raised to cubics
+/// and lines back to the start of a closed contour left out.
+fn outline(path: &Path) -> (String, Vec<Point>) {
+    let mut verbs = String::new();
+    let mut points = vec![];
+    let mut start = point(0., 0.);
+
+    for segment in path.iter() {
+        match segment {
+            PathSegment::Move { to } => {
+                verbs.push('M');
+                points.push(to);
+                start = to;
+            }
+            PathSegment::Line { to, .. } => {
+                verbs.push('L');
+                points.push(to);
+            }
+            PathSegment::Quadratic { from, ctrl, to } => {
+                verbs.push('C');
+                points.push(from.lerp(ctrl, 2. / 3.));
+                points.push(to.lerp(ctrl, 2. / 3.));
+                points.push(to);
+            }
+            PathSegment::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => {
+                verbs.push('C');
+                points.extend([ctrl1, ctrl2, to]);
+            }
+            PathSegment::Close => {
+                if verbs.ends_with('L') && points.last() == Some(&start) {
+                    verbs.pop();
+                    points.pop();
+                }
+                verbs.push('Z');
+            }
+        }
+    }
+
+    (verbs, points)
+}
+
+#[test]
+fn typeface() {
+    for data in [GLYF, CFF, CFF2] {
+        let typeface = Typeface::from_data(data.to_vec(), 0).unwrap();
+        assert_eq!(typeface.family_name().as_deref(), Some("Dauber Test"));
+        assert_eq!(typeface.units_per_em(), 1000);
+        assert_eq!(typeface.glyph_count(), 3);
+        assert_eq!(typeface.glyph_id('A'), Some(GlyphId(1)));
+        assert_eq!(typeface.glyph_id('B'), Some(GlyphId(2)));
+        assert_eq!(typeface.glyph_id('C'), None);
+        assert!(!typeface.is_bold() && !typeface.is_italic());
+        assert_eq!(typeface, typeface.clone());
+        assert_eq!(Typeface::count(data), 1);
+    }
+
+    let result = Typeface::from_data(b"not a font".to_vec(), 0);
+    assert!(matches!(result, Err(Error::Font(_))));
+}
+
+#[test]
+fn metrics() {
+    let font = font(GLYF);
+    let metrics = font.metrics();
+    assert_eq!(metrics.ascent, -80.);
+    assert_eq!(metrics.descent, 20.);
+    assert_eq!(metrics.leading, 9.);
+    assert_eq!(metrics.line_height(), 109.);
+    assert_eq!(metrics.cap_height, Some(70.));
+    assert_eq!(metrics.x_height, Some(50.));
+    assert_eq!(metrics.underline, Some((10., 5.)));
+
+    let glyph = font.glyph_metrics(GlyphId(1));
+    assert_eq!(glyph.advance, 60.);
+    assert_eq!(glyph.bounds, Some(Rect::from_ltrb(10., -70., 50., 0.)));
+    assert_eq!(font.glyph_metrics(GlyphId::NOTDEF).bounds, None);
+    assert_eq!(
+        font.glyph_ids("AxB"),
+        [GlyphId(1), GlyphId::NOTDEF, GlyphId(2)]
+    );
+}
+
+#[test]
+fn glyph_path() {
+    let square = [
+        point(10., 0.),
+        point(50., 0.),
+        point(50., -70.),
+        point(10., -70.),
+    ];
+    let rounded = [
+        point(10., 0.),
+        point(50., 0.),
+        point(70., -24.),
+        point(70., -48.),
+        point(50., -72.),
+        point(10., -72.),
+    ];
+
+    for data in [GLYF, CFF, CFF2] {
+        let font = font(data);
+
+        let (verbs, points) = outline(&font.glyph_path(GlyphId(1)).unwrap());
+        assert_eq!(verbs, "MLLLZ");
+        assert_eq!(points, square);
+
+        let (verbs, points) = outline(&font.glyph_path(GlyphId(2)).unwrap());
+        assert_eq!(verbs, "MLCLZ");
+        assert!(points
+            .iter()
+            .zip(rounded)
+            .all(|(a, b)| (a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3));
+
+        assert!(font.glyph_path(GlyphId::NOTDEF).is_none());
+    }
+}
+
+#[test]
+fn draw_text() {
+    let font = Font::new(Typeface::from_data(CFF.to_vec(), 0).unwrap(), 10.);
+    let path = font.text_path("AB", point(2., 10.));
+
+    let mut raster = Raster::new(ImageInfo::new_wh(16, 12));
+    raster.clear(Color::WHITE);
+    raster.draw_path(&path, &Paint::new()).unwrap();
+
+    let pixel = |x: usize, y: usize| raster.pixels()[(y * 16 + x) * 4];
+    // 'A' covers 3..7, 'B' starts 6 pixels later
+    assert_eq!(pixel(4, 6), 0);
+    assert_eq!(pixel(10, 6), 0);
+    assert_eq!(pixel(8, 6), 255);
+    assert_eq!(pixel(4, 1), 255);
+}
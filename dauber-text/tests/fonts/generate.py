@@ -0,0 +1,585 @@
+#!/usr/bin/env python3
+"""Generate the minimal test fonts in this directory.
+
+test-glyf.ttf, test-cff.otf and test-cff2.otf map 'A' to a square and 'B' to a square
+with a rounded right side, on a 1000 unit em:
+
+    A: (100, 0) (500, 0) (500, 700) (100, 700)
+    B: (100, 0) (500, 0), a curve through (700, 360) to (500, 720), (100, 720)
+
+glyf stores the curve of 'B' as a quadratic with its control point at (800, 360),
+CFF and CFF2 as the same curve raised to a cubic.
+
+test-layout.ttf draws every glyph as a rectangle and carries just enough OpenType
+layout to exercise shaping, see LAYOUT_GLYPHS, gsub() and gpos().
+
+test-color.ttf has a glyph for every kind of color glyph, see COLOR_GLYPHS, colr() and
+cbdt(), and test-sbix.ttf maps 'E' to a bitmap in sbix, which takes precedence over CBDT.
+"""
+
+import struct
+import zlib
+from pathlib import Path
+
+UNITS_PER_EM = 1000
+ASCENDER, DESCENDER, LINE_GAP = 800, -200, 90
+FAMILY = "Dauber Test"
+
+
+class Glyph:
+    def __init__(self, char, advance, contours=(), charstring=None):
+        self.char = char
+        self.advance = advance
+        # lists of (x, y, on curve)
+        self.contours = contours
+        # CFF operators as (operands, operator), without endchar
+        self.charstring = charstring or []
+
+    @property
+    def bounds(self):
+        points = [p for contour in self.contours for p in contour]
+        if not points:
+            return None
+        xs, ys = [p[0] for p in points], [p[1] for p in points]
+        return min(xs), min(ys), max(xs), max(ys)
+
+
+def rect(l, t, r, b):
+    return [(l, t, 1), (r, t, 1), (r, b, 1), (l, b, 1)]
+
+
+OUTLINE_GLYPHS = [
+    Glyph(None, 600),
+    Glyph("A", 600, [rect(100, 0, 500, 700)], [([100, 0], 21), ([400, 0, 0, 700, -400, 0], 5)]),
+    Glyph(
+        "B",
+        600,
+        [[(100, 0, 1), (500, 0, 1), (800, 360, 0), (500, 720, 1), (100, 720, 1)]],
+        # (500, 0) to (500, 720) through (700, 240) and (700, 480)
+        [([100, 0], 21), ([400, 0], 5), ([200, 240, 0, 240, -200, 240], 8), ([-400, 0], 5)],
+    ),
+]
+
+# Glyph ids the layout tables refer to.
+SPACE, A, V, F, I, F_I, ACUTE, BEH, BEH_INIT, BEH_MEDI, BEH_FINA, ALEF, ONE, ELLIPSIS, PERIOD, B = range(1, 17)
+
+LAYOUT_GLYPHS = [
+    Glyph(None, 600),
+    Glyph(" ", 300),
+    Glyph("A", 600, [rect(50, 0, 550, 700)]),
+    Glyph("V", 600, [rect(50, 0, 550, 700)]),
+    Glyph("f", 400, [rect(50, 0, 350, 700)]),
+    Glyph("i", 300, [rect(50, 0, 250, 500)]),
+    # the f_i ligature
+    Glyph(None, 650, [rect(50, 0, 600, 700)]),
+    # combining acute accent, attached with its anchor at (50, 700)
+    Glyph("\u0301", 0, [rect(0, 750, 100, 850)]),
+    # Arabic beh, isolated, initial, medial and final
+    Glyph("\u0628", 500, [rect(50, 0, 450, 300)]),
+    Glyph(None, 400, [rect(0, 0, 400, 300)]),
+    Glyph(None, 300, [rect(0, 0, 300, 300)]),
+    Glyph(None, 450, [rect(0, 0, 400, 300)]),
+    # Hebrew alef
+    Glyph("\u05d0", 500, [rect(50, 0, 450, 600)]),
+    Glyph("1", 500, [rect(200, 0, 300, 700)]),
+    Glyph("\u2026", 800, [rect(50, 0, 150, 100), rect(350, 0, 450, 100), rect(650, 0, 750, 100)]),
+    Glyph(".", 250, [rect(75, 0, 175, 100)]),
+    Glyph("B", 600, [rect(50, 0, 550, 700)]),
+]
+
+# Glyph ids of test-color.ttf.
+COLR_V0, COLR_LINEAR, COLR_LAYERS, COLR_COMPOSITE, CBDT, PLAIN, FULL, RIGHT, TOP = range(1, 10)
+
+COLOR_GLYPHS = [
+    Glyph(None, 600),
+    # red with the foreground color on its top half, from COLR version 0
+    Glyph("A", 1000, [rect(0, 0, 1000, 1000)]),
+    # red to blue from left to right, clipped to the bottom half
+    Glyph("B", 1000),
+    # green with half transparent blue on its left half
+    Glyph("C", 1000),
+    # red with its top half cut out
+    Glyph("D", 1000),
+    # a 4x4 bitmap, green on the left and red on the right half
+    Glyph("F", 1000),
+    Glyph("x", 1000, [rect(0, 0, 1000, 1000)]),
+    # layers, without characters
+    Glyph(None, 1000, [rect(0, 0, 1000, 1000)]),
+    Glyph(None, 1000, [rect(500, 0, 1000, 1000)]),
+    Glyph(None, 1000, [rect(0, 500, 1000, 1000)]),
+]
+
+# Palette entries as RGBA, the foreground color stands in for the text color.
+RED, BLUE, GREEN = range(3)
+PALETTE = [(255, 0, 0, 255), (0, 0, 255, 255), (0, 255, 0, 255)]
+FOREGROUND = 0xFFFF
+
+SBIX_GLYPHS = [
+    Glyph(None, 600),
+    # a 4x4 bitmap, red on the top and blue on the bottom half
+    Glyph("E", 1000),
+]
+
+
+def table_checksum(data):
+    data += b"\0" * (-len(data) % 4)
+    return sum(struct.unpack(f">{len(data) // 4}I", data)) & 0xFFFFFFFF
+
+
+def sfnt(version, tables):
+    tags = sorted(tables)
+    n = len(tags)
+    entry_selector = n.bit_length() - 1
+    search_range = 16 << entry_selector
+    out = struct.pack(">4sHHHH", version, n, search_range, entry_selector, n * 16 - search_range)
+    offset = 12 + 16 * n
+    body = b""
+    for tag in tags:
+        data = tables[tag]
+        out += struct.pack(">4sIII", tag.encode(), table_checksum(data), offset + len(body), len(data))
+        body += data + b"\0" * (-len(data) % 4)
+    return out + body
+
+
+def head(glyphs):
+    bounds = [g.bounds for g in glyphs if g.bounds]
+    x_min, y_min = min(b[0] for b in bounds), min(b[1] for b in bounds)
+    x_max, y_max = max(b[2] for b in bounds), max(b[3] for b in bounds)
+    return struct.pack(
+        ">IIIIHHqqhhhhHHhhh",
+        0x00010000, 0x00010000, 0, 0x5F0F3CF5, 0b11, UNITS_PER_EM,
+        0, 0, x_min, y_min, x_max, y_max, 0, 8, 2, 0, 0,
+    )
+
+
+def hhea(glyphs):
+    advance_max = max(g.advance for g in glyphs)
+    return struct.pack(
+        ">IhhhHhhhhhh8xhH",
+        0x00010000, ASCENDER, DESCENDER, LINE_GAP, advance_max, 0, 0, advance_max, 1, 0, 0, 0, len(glyphs),
+    )
+
+
+def maxp(glyphs, truetype):
+    if truetype:
+        points = max(sum(len(c) for c in g.contours) for g in glyphs)
+        contours = max(len(g.contours) for g in glyphs)
+        return struct.pack(">IH13H", 0x00010000, len(glyphs), points, contours, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0)
+    return struct.pack(">IH", 0x00005000, len(glyphs))
+
+
+def hmtx(glyphs):
+    return b"".join(struct.pack(">Hh", g.advance, g.bounds[0] if g.bounds else 0) for g in glyphs)
+
+
+def cmap(glyphs):
+    # format 4 with a segment per character and the final one
+    chars = sorted((ord(g.char), gid) for gid, g in enumerate(glyphs) if g.char)
+    ends = [c for c, _ in chars] + [0xFFFF]
+    deltas = [(gid - c) % 0x10000 for c, gid in chars] + [1]
+    seg_count = len(ends)
+    entry_selector = seg_count.bit_length() - 1
+    search_range = 2 << entry_selector
+    sub = struct.pack(">HHHHHHH", 4, 0, 0, 2 * seg_count, search_range, entry_selector,
+                      2 * seg_count - search_range)
+    sub += struct.pack(f">{seg_count}H", *ends) + b"\0\0"
+    sub += struct.pack(f">{seg_count}H", *ends)
+    sub += struct.pack(f">{seg_count}H", *deltas)
+    sub += struct.pack(f">{seg_count}H", *[0] * seg_count)
+    sub = sub[:2] + struct.pack(">H", len(sub)) + sub[4:]
+    return struct.pack(">HHHHI", 0, 1, 3, 1, 12) + sub
+
+
+def os2(glyphs):
+    chars = [ord(g.char) for g in glyphs if g.char]
+    return struct.pack(
+        ">HhHHHhhhhhhhhhhh10s4I4sHHHhhhHHIIhhHHH",
+        4, 600, 400, 5, 0, 650, 600, 0, 75, 650, 600, 0, 350, 50, 250, 0,
+        b"\0" * 10, 1, 0, 0, 0, b"NONE", 0x40, min(chars), min(max(chars), 0xFFFF),
+        ASCENDER, DESCENDER, LINE_GAP, 800, 200, 1, 0,
+        500, 700, 0, 0x20, 0,
+    )
+
+
+def post():
+    return struct.pack(">IIhhIIIII", 0x00030000, 0, -100, 50, 0, 0, 0, 0, 0)
+
+
+def name():
+    records = [(1, FAMILY.encode("utf-16-be")), (2, "Regular".encode("utf-16-be"))]
+    out = struct.pack(">HHH", 0, len(records), 6 + 12 * len(records))
+    strings = b""
+    for name_id, s in records:
+        out += struct.pack(">HHHHHH", 3, 1, 0x409, name_id, len(s), len(strings))
+        strings += s
+    return out + strings
+
+
+def glyf(glyphs):
+    def simple(glyph):
+        if not glyph.contours:
+            return b""
+        ends, flags, xs, ys = [], b"", b"", b""
+        x = y = 0
+        for contour in glyph.contours:
+            for px, py, on in contour:
+                flags += bytes([on])
+                xs += struct.pack(">h", px - x)
+                ys += struct.pack(">h", py - y)
+                x, y = px, py
+            ends.append((ends[-1] + 1 if ends else 0) + len(contour) - 1)
+        header = struct.pack(">hhhhh", len(glyph.contours), *glyph.bounds)
+        data = header + struct.pack(f">{len(ends)}HH", *ends, 0) + flags + xs + ys
+        return data + b"\0" * (len(data) % 2)
+
+    data = [simple(g) for g in glyphs]
+    loca, offset = [], 0
+    for g in data:
+        loca.append(offset // 2)
+        offset += len(g)
+    loca.append(offset // 2)
+    return b"".join(data), struct.pack(f">{len(loca)}H", *loca)
+
+
+def charstring_number(v):
+    if -107 <= v <= 107:
+        return bytes([v + 139])
+    if 108 <= v <= 1131:
+        v -= 108
+        return bytes([(v >> 8) + 247, v & 0xFF])
+    if -1131 <= v <= -108:
+        v = -v - 108
+        return bytes([(v >> 8) + 251, v & 0xFF])
+    return b"\x1c" + struct.pack(">h", v)
+
+
+def charstring(glyph, cff2):
+    ops = glyph.charstring + ([] if cff2 else [([], 14)])
+    return b"".join(b"".join(charstring_number(v) for v in args) + bytes([op]) for args, op in ops)
+
+
+def index(items, count_format):
+    if not items:
+        return struct.pack(count_format, 0)
+    data = b"".join(items)
+    offsets = [1]
+    for item in items:
+        offsets.append(offsets[-1] + len(item))
+    return struct.pack(count_format + "B", len(items), 4) + struct.pack(f">{len(offsets)}I", *offsets) + data
+
+
+def dict_int(v):
+    # always five bytes, so offsets can be filled in without moving anything
+    return b"\x1d" + struct.pack(">i", v)
+
+
+def cff(glyphs):
+    charstrings = index([charstring(g, False) for g in glyphs], ">H")
+    private = dict_int(0) + bytes([20]) + dict_int(0) + bytes([21])
+
+    def top(charstrings_offset, private_offset):
+        return index([dict_int(charstrings_offset) + bytes([17])
+                      + dict_int(len(private)) + dict_int(private_offset) + bytes([18])], ">H")
+
+    header = bytes([1, 0, 4, 4])
+    names = index([b"DauberTest"], ">H")
+    strings = index([], ">H")
+    global_subrs = index([], ">H")
+    start = len(header) + len(names) + len(top(0, 0)) + len(strings) + len(global_subrs)
+    top_dict = top(start, start + len(charstrings))
+    return header + names + top_dict + strings + global_subrs + charstrings + private
+
+
+def cff2(glyphs):
+    charstrings = index([charstring(g, True) for g in glyphs], ">I")
+    private = dict_int(0) + bytes([22])
+    # an item variation store with a single empty item variation data, without axes
+    store = struct.pack(">HIHI", 1, 12, 1, 16) + struct.pack(">HH", 0, 0) + struct.pack(">HHH", 0, 0, 0)
+    variation_store = struct.pack(">H", len(store)) + store
+
+    def top(charstrings_offset, fd_array_offset, variation_store_offset):
+        return (dict_int(charstrings_offset) + bytes([17]) + dict_int(fd_array_offset) + bytes([12, 36])
+                + dict_int(variation_store_offset) + bytes([24]))
+
+    def font_dict(private_offset):
+        return index([dict_int(len(private)) + dict_int(private_offset) + bytes([18])], ">I")
+
+    top_len = len(top(0, 0, 0))
+    header = struct.pack(">BBBH", 2, 0, 5, top_len)
+    global_subrs = index([], ">I")
+    charstrings_offset = len(header) + top_len + len(global_subrs)
+    fd_array_offset = charstrings_offset + len(charstrings)
+    private_offset = fd_array_offset + len(font_dict(0))
+    variation_store_offset = private_offset + len(private)
+    return (header + top(charstrings_offset, fd_array_offset, variation_store_offset) + global_subrs
+            + charstrings + font_dict(private_offset) + private + variation_store)
+
+
+def u16(*values):
+    return struct.pack(f">{len(values)}H", *values)
+
+
+def with_offsets(header, children):
+    """`header` followed by 16 bit offsets to `children`, which are appended after it."""
+    offset = len(header) + 2 * len(children)
+    offsets = []
+    for child in children:
+        offsets.append(offset)
+        offset += len(child)
+    return header + u16(*offsets) + b"".join(children)
+
+
+def coverage(glyph_ids):
+    return u16(1, len(glyph_ids), *glyph_ids)
+
+
+def layout(scripts, features, lookups):
+    """A GSUB or GPOS table, `scripts` maps tags to feature indices, `features` lists
+    (tag, lookup indices) and `lookups` (type, subtable)."""
+    script_list = u16(len(scripts))
+    script_tables = []
+    for tag in sorted(scripts):
+        indices = scripts[tag]
+        # offset to the default LangSys right after the Script table
+        script_tables.append(u16(4, 0) + u16(0, 0xFFFF, len(indices), *indices))
+    offset = 2 + 6 * len(scripts)
+    for tag, table in zip(sorted(scripts), script_tables):
+        script_list += tag.encode() + u16(offset)
+        offset += len(table)
+    script_list += b"".join(script_tables)
+
+    feature_list = u16(len(features))
+    offset = 2 + 6 * len(features)
+    for tag, indices in features:
+        feature_list += tag.encode() + u16(offset)
+        offset += 4 + 2 * len(indices)
+    feature_list += b"".join(u16(0, len(indices), *indices) for _, indices in features)
+
+    lookup_list = with_offsets(u16(len(lookups)), [
+        with_offsets(u16(kind, 0, 1), [subtable]) for kind, subtable in lookups
+    ])
+
+    header_len = 10
+    return (u16(1, 0, header_len, header_len + len(script_list),
+                header_len + len(script_list) + len(feature_list))
+            + script_list + feature_list + lookup_list)
+
+
+def gsub():
+    def single(source, target):
+        return u16(2, 8, 1, target) + coverage([source])
+
+    # f i -> f_i
+    ligature_set = with_offsets(u16(1), [u16(F_I, 2, I)])
+    liga = u16(1, 8, 1, 8 + len(coverage([F]))) + coverage([F]) + ligature_set
+
+    features = [("fina", [3]), ("init", [1]), ("liga", [0]), ("medi", [2])]
+    lookups = [
+        (4, liga),
+        (1, single(BEH, BEH_INIT)),
+        (1, single(BEH, BEH_MEDI)),
+        (1, single(BEH, BEH_FINA)),
+    ]
+    return layout({"DFLT": [2], "arab": [0, 1, 3], "latn": [2]}, features, lookups)
+
+
+def gpos():
+    # A V is kerned by -100
+    pair_set = u16(1, V) + struct.pack(">h", -100)
+    kern = u16(1, 12, 0x0004, 0, 1, 12 + 6) + coverage([A]) + pair_set
+
+    # the acute accent attaches at (50, 700) to (300, 700) on A and V
+    mark_anchor = u16(1) + struct.pack(">hh", 50, 700)
+    mark_array = u16(1, 0, 6) + mark_anchor
+    base_anchor = u16(1) + struct.pack(">hh", 300, 700)
+    base_array = u16(2, 6, 6 + len(base_anchor)) + base_anchor + base_anchor
+    mark_coverage, base_coverage = coverage([ACUTE]), coverage([A, V])
+    offset = 12
+    mark = u16(1, offset, offset + len(mark_coverage), 1,
+               offset + len(mark_coverage) + len(base_coverage),
+               offset + len(mark_coverage) + len(base_coverage) + len(mark_array))
+    mark += mark_coverage + base_coverage + mark_array + base_array
+
+    features = [("kern", [0]), ("mark", [1])]
+    return layout({"DFLT": [0, 1], "arab": [0, 1], "latn": [0, 1]}, features, [(2, kern), (4, mark)])
+
+
+def gdef():
+    ranges = [(SPACE, I, 1), (F_I, F_I, 2), (ACUTE, ACUTE, 3), (BEH, B, 1)]
+    class_def = u16(2, len(ranges)) + b"".join(u16(*r) for r in ranges)
+    return u16(1, 0, 12, 0, 0, 0) + class_def
+
+
+def u8(*values):
+    return struct.pack(f">{len(values)}B", *values)
+
+
+def u24(value):
+    return struct.pack(">I", value)[1:]
+
+
+def f2dot14(value):
+    return struct.pack(">h", round(value * 16384))
+
+
+def paint_colr_layers(count, first):
+    return u8(1, count) + struct.pack(">I", first)
+
+
+def paint_solid(index, alpha=1.0):
+    return u8(2) + u16(index) + f2dot14(alpha)
+
+
+def paint_linear_gradient(stops, p0, p1, p2):
+    # padded, with the color line right after the paint
+    color_line = u8(0) + u16(len(stops)) + b"".join(f2dot14(t) + u16(i) + f2dot14(1) for t, i in stops)
+    return u8(4) + u24(16) + struct.pack(">6h", *p0, *p1, *p2) + color_line
+
+
+def paint_glyph(glyph_id, paint):
+    return u8(10) + u24(6) + u16(glyph_id) + paint
+
+
+def paint_translate(dx, dy, paint):
+    return u8(14) + u24(8) + struct.pack(">hh", dx, dy) + paint
+
+
+def paint_composite(source, mode, backdrop):
+    return u8(32) + u24(8) + u8(mode) + u24(8 + len(source)) + source + backdrop
+
+
+def colr():
+    base_glyphs = u16(COLR_V0, 0, 2)
+    layers = u16(FULL, RED, TOP, FOREGROUND)
+
+    paints = [
+        (COLR_LINEAR, paint_glyph(FULL, paint_linear_gradient([(0, RED), (1, BLUE)], (0, 0), (1000, 0), (0, 1000)))),
+        (COLR_LAYERS, paint_colr_layers(2, 0)),
+        # destination out
+        (COLR_COMPOSITE, paint_composite(paint_glyph(TOP, paint_solid(BLUE)), 8,
+                                         paint_glyph(FULL, paint_solid(RED)))),
+    ]
+    base_glyph_list = struct.pack(">I", len(paints))
+    offset = 4 + 6 * len(paints)
+    for glyph_id, paint in paints:
+        base_glyph_list += u16(glyph_id) + struct.pack(">I", offset)
+        offset += len(paint)
+    base_glyph_list += b"".join(paint for _, paint in paints)
+
+    layer_paints = [
+        paint_glyph(FULL, paint_solid(GREEN)),
+        paint_translate(-500, 0, paint_glyph(RIGHT, paint_solid(BLUE, 0.5))),
+    ]
+    layer_list = struct.pack(">I", len(layer_paints))
+    offset = 4 + 4 * len(layer_paints)
+    for paint in layer_paints:
+        layer_list += struct.pack(">I", offset)
+        offset += len(paint)
+    layer_list += b"".join(layer_paints)
+
+    # the linear gradient is clipped to the bottom half
+    clip_list = u8(1) + struct.pack(">I", 1) + u16(COLR_LINEAR, COLR_LINEAR) + u24(12)
+    clip_list += u8(1) + struct.pack(">4h", 0, 0, 1000, 500)
+
+    header_len = 34
+    offsets = [header_len, header_len + len(base_glyphs)]
+    offsets.append(offsets[1] + len(layers))
+    offsets.append(offsets[2] + len(base_glyph_list))
+    offsets.append(offsets[3] + len(layer_list))
+    header = struct.pack(">HHIIHIIIII", 1, 1, offsets[0], offsets[1], 2, offsets[2], offsets[3], offsets[4], 0, 0)
+    return header + base_glyphs + layers + base_glyph_list + layer_list + clip_list
+
+
+def cpal():
+    records = b"".join(bytes([b, g, r, a]) for r, g, b, a in PALETTE)
+    return u16(0, len(PALETTE), 1, len(PALETTE)) + struct.pack(">I", 14) + u16(0) + records
+
+
+def png(rows):
+    """An RGBA PNG from rows of (r, g, b, a)."""
+    def chunk(tag, data):
+        return struct.pack(">I", len(data)) + tag + data + struct.pack(">I", zlib.crc32(tag + data))
+
+    raw = b"".join(b"\0" + bytes(c for pixel in row for c in pixel) for row in rows)
+    header = struct.pack(">IIBBBBB", len(rows[0]), len(rows), 8, 6, 0, 0, 0)
+    return b"\x89PNG\r\n\x1a\n" + chunk(b"IHDR", header) + chunk(b"IDAT", zlib.compress(raw)) + chunk(b"IEND", b"")
+
+
+def halves(first, second, vertical):
+    """A 4x4 image split into two colors, left and right or top and bottom."""
+    if vertical:
+        return [[first] * 4] * 2 + [[second] * 4] * 2
+    return [[first] * 2 + [second] * 2] * 4
+
+
+def cbdt():
+    """CBDT and CBLC with a strike at 4 pixels per em, holding a PNG for the CBDT glyph."""
+    image = png(halves(PALETTE[GREEN], PALETTE[RED], False))
+    # small metrics with the bitmap standing on the baseline, then the PNG
+    glyph = u8(4, 4, 0, 4, 4) + struct.pack(">I", len(image)) + image
+    cbdt_data = u16(3, 0) + glyph
+
+    # format 1 offsets into CBDT for PNGs with small metrics
+    index_subtable = u16(1, 17) + struct.pack(">III", 4, 0, len(glyph))
+    subtable_list = u16(CBDT, CBDT) + struct.pack(">I", 8) + index_subtable
+    bitmap_size = struct.pack(">IIII", 8 + 48, len(subtable_list), 1, 0) + b"\0" * 24
+    bitmap_size += u16(CBDT, CBDT) + u8(4, 4, 32, 1)
+    cblc_data = u16(3, 0) + struct.pack(">I", 1) + bitmap_size + subtable_list
+    return cbdt_data, cblc_data
+
+
+def sbix(glyphs):
+    """A strike at 4 pixels per em with a PNG for every glyph with a character."""
+    images = [png(halves(PALETTE[RED], PALETTE[BLUE], True)) if g.char else b"" for g in glyphs]
+    data = [struct.pack(">hh", 0, 0) + b"png " + image if image else b"" for image in images]
+    offset = 4 + 4 * (len(data) + 1)
+    offsets = []
+    for d in data:
+        offsets.append(offset)
+        offset += len(d)
+    offsets.append(offset)
+    strike = u16(4, 72) + struct.pack(f">{len(offsets)}I", *offsets) + b"".join(data)
+    return u16(1, 1) + struct.pack(">II", 1, 12) + strike
+
+
+def common(glyphs, truetype):
+    return {
+        "head": head(glyphs), "hhea": hhea(glyphs), "maxp": maxp(glyphs, truetype), "hmtx": hmtx(glyphs),
+        "cmap": cmap(glyphs), "OS/2": os2(glyphs), "post": post(), "name": name(),
+    }
+
+
+def main():
+    out = Path(__file__).parent
+
+    glyf_data, loca = glyf(OUTLINE_GLYPHS)
+    tables = common(OUTLINE_GLYPHS, True) | {"glyf": glyf_data, "loca": loca}
+    (out / "test-glyf.ttf").write_bytes(sfnt(b"\0\1\0\0", tables))
+
+    tables = common(OUTLINE_GLYPHS, False) | {"CFF ": cff(OUTLINE_GLYPHS)}
+    (out / "test-cff.otf").write_bytes(sfnt(b"OTTO", tables))
+    tables = common(OUTLINE_GLYPHS, False) | {"CFF2": cff2(OUTLINE_GLYPHS)}
+    (out / "test-cff2.otf").write_bytes(sfnt(b"OTTO", tables))
+
+    glyf_data, loca = glyf(LAYOUT_GLYPHS)
+    tables = common(LAYOUT_GLYPHS, True) | {
+        "glyf": glyf_data, "loca": loca, "GSUB": gsub(), "GPOS": gpos(), "GDEF": gdef(),
+    }
+    (out / "test-layout.ttf").write_bytes(sfnt(b"\0\1\0\0", tables))
+
+    glyf_data, loca = glyf(COLOR_GLYPHS)
+    cbdt_data, cblc_data = cbdt()
+    tables = common(COLOR_GLYPHS, True) | {
+        "glyf": glyf_data, "loca": loca, "COLR": colr(), "CPAL": cpal(), "CBDT": cbdt_data, "CBLC": cblc_data,
+    }
+    (out / "test-color.ttf").write_bytes(sfnt(b"\0\1\0\0", tables))
+
+    # sbix needs an outline somewhere for the head bounds
+    glyphs = SBIX_GLYPHS + [Glyph(None, 1000, [rect(0, 0, 1000, 1000)])]
+    glyf_data, loca = glyf(glyphs)
+    tables = common(glyphs, True) | {"glyf": glyf_data, "loca": loca, "sbix": sbix(glyphs)}
+    (out / "test-sbix.ttf").write_bytes(sfnt(b"\0\1\0\0", tables))
+
+
+if __name__ == "__main__":
+    main()
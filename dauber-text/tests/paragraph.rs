@@ -0,0 +1,273 @@
+use dauber_core::color::Color;
+use dauber_core::geom::point;
+use dauber_text::font::Font;
+use dauber_text::paragraph::{
+    Paragraph, ParagraphBuilder, ParagraphStyle, TextAlign, TextDirection, TextStyle,
+};
+use dauber_text::typeface::Typeface;
+
+/// Rectangles for glyphs, see generate.py. At 100 pixels per em a space advances 30 pixels,
+/// 'A', 'B' and 'V' 60, "fi" 65, beh and alef 50 and the ellipsis 80.
+const LAYOUT: &[u8] = include_bytes!("fonts/test-layout.ttf");
+
+const A: u16 = 2;
+const B: u16 = 16;
+const ELLIPSIS: u16 = 14;
+
+fn style(size: f32) -> TextStyle {
+    TextStyle::new(Font::new(
+        Typeface::from_data(LAYOUT.to_vec(), 0).unwrap(),
+        size,
+    ))
+}
+
+fn paragraph(text: &str, style: ParagraphStyle, width: f32) -> Paragraph {
+    let mut builder = ParagraphBuilder::new(style, self::style(100.));
+    builder.add_text(text);
+    let mut paragraph = builder.build();
+    paragraph.layout(width);
+
+    paragraph
+}
+
+/// The glyphs of every line with their x, from left to right.
+fn glyphs(paragraph: &Paragraph) -> Vec<Vec<(u16, f32)>> {
+    paragraph
+        .lines()
+        .map(|line| {
+            let mut glyphs: Vec<_> = paragraph
+                .runs()
+                .flat_map(|(run, _)| run.glyphs().iter().zip(run.positions()))
+                .filter(|(_, p)| p.y == line.baseline)
+                .map(|(g, p)| (g.0, p.x))
+                .collect();
+            glyphs.sort_by(|a, b| a.1.total_cmp(&b.1));
+            glyphs
+        })
+        .collect()
+}
+
+#[test]
+fn line_breaking() {
+    let p = paragraph("AB AB AB", ParagraphStyle::default(), 150.);
+    let lines: Vec<_> = p.lines().collect();
+
+    assert_eq!(lines.len(), 3);
+    assert_eq!(
+        lines.iter().map(|l| l.range.clone()).collect::<Vec<_>>(),
+        [0..3, 3..6, 6..8]
+    );
+    assert_eq!(
+        lines.iter().map(|l| l.hard_break).collect::<Vec<_>>(),
+        [false, false, true]
+    );
+    // the trailing space does not count
+    assert!(lines.iter().all(|l| l.width == 120.));
+
+    // 80 above and 20 below the baseline, the line gap of 9 is split around them
+    assert_eq!((lines[0].ascent, lines[0].descent), (-80., 20.));
+    assert_eq!(
+        lines
+            .iter()
+            .map(|l| (l.top, l.baseline))
+            .collect::<Vec<_>>(),
+        [(0., 84.5), (109., 193.5), (218., 302.5)]
+    );
+    assert_eq!(p.height(), 327.);
+    assert_eq!(p.longest_line(), 120.);
+
+    let p = paragraph("AB\nB", ParagraphStyle::default(), f32::INFINITY);
+    let lines: Vec<_> = p.lines().map(|l| (l.range.clone(), l.hard_break)).collect();
+    assert_eq!(lines, [(0..3, true), (3..4, true)]);
+}
+
+#[test]
+fn empty_lines() {
+    // empty text and a final line break still have a line for the caret
+    let p = paragraph("", ParagraphStyle::default(), f32::INFINITY);
+    assert_eq!(p.lines().len(), 1);
+    assert_eq!(p.lines().next().unwrap().range, 0..0);
+    assert_eq!(p.height(), 109.);
+    let caret = p.caret_rect(0);
+    assert_eq!((caret.l, caret.t, caret.b), (0., 0., 109.));
+
+    let p = paragraph("A\n", ParagraphStyle::default(), f32::INFINITY);
+    let ranges: Vec<_> = p.lines().map(|l| l.range.clone()).collect();
+    assert_eq!(ranges, [0..2, 2..2]);
+    assert_eq!(p.height(), 218.);
+    let caret = p.caret_rect(2);
+    assert_eq!((caret.l, caret.t, caret.b), (0., 109., 218.));
+    assert_eq!(p.hit_test(point(40., 150.)), 2);
+
+    // the empty line takes the metrics of the style at the end of the text
+    let mut builder = ParagraphBuilder::new(ParagraphStyle::default(), style(100.));
+    builder.add_text("A").push_style(style(200.)).add_text("\n");
+    let mut p = builder.build();
+    p.layout(f32::INFINITY);
+    let last = p.lines().last().unwrap();
+    assert_eq!((last.range.clone(), last.height), (2..2, 218.));
+}
+
+#[test]
+fn alignment() {
+    let aligned = |align| {
+        let style = ParagraphStyle {
+            align,
+            ..Default::default()
+        };
+        paragraph("A B AB", style, 200.)
+    };
+
+    let lefts = |p: Paragraph| p.lines().map(|l| l.left).collect::<Vec<_>>();
+    assert_eq!(lefts(aligned(TextAlign::Left)), [0., 0.]);
+    assert_eq!(lefts(aligned(TextAlign::Right)), [50., 80.]);
+    assert_eq!(lefts(aligned(TextAlign::Center)), [25., 40.]);
+
+    // the space takes up what is left of the first line, the last line is not stretched
+    let justified = aligned(TextAlign::Justify);
+    assert_eq!(
+        glyphs(&justified),
+        [vec![(A, 0.), (1, 60.), (B, 140.)], vec![(A, 0.), (B, 60.)]]
+    );
+    let widths: Vec<_> = justified.lines().map(|l| l.width).collect();
+    assert_eq!(widths, [200., 120.]);
+}
+
+#[test]
+fn line_height() {
+    let style = ParagraphStyle {
+        line_height: Some(1.5),
+        ..Default::default()
+    };
+    let p = paragraph("A\nB", style, f32::INFINITY);
+    let lines: Vec<_> = p.lines().map(|l| (l.top, l.height, l.baseline)).collect();
+
+    // 50 pixels beyond ascent and descent, half of them above
+    assert_eq!(lines, [(0., 150., 105.), (150., 150., 255.)]);
+    assert_eq!(p.height(), 300.);
+}
+
+#[test]
+fn max_lines() {
+    let style = ParagraphStyle {
+        max_lines: Some(2),
+        ellipsis: Some("\u{2026}".into()),
+        ..Default::default()
+    };
+    let p = paragraph("AB AB AB", style.clone(), 150.);
+
+    // "AB" and the ellipsis are too wide, so 'B' makes room for it
+    assert!(p.did_exceed_max_lines());
+    assert_eq!(
+        glyphs(&p),
+        [vec![(A, 0.), (B, 60.)], vec![(A, 0.), (ELLIPSIS, 60.)]]
+    );
+
+    // a single line is shortened when it does not fit, even though nothing is left out
+    let style = ParagraphStyle {
+        max_lines: Some(1),
+        ..style
+    };
+    let p = paragraph("ABABAB", style.clone(), 150.);
+    assert!(!p.did_exceed_max_lines());
+    assert_eq!(glyphs(&p), [vec![(A, 0.), (ELLIPSIS, 60.)]]);
+
+    let p = paragraph("AB AB", style, 250.);
+    assert!(p.did_exceed_max_lines());
+    assert_eq!(glyphs(&p), [vec![(A, 0.), (B, 60.), (ELLIPSIS, 120.)]]);
+}
+
+#[test]
+fn mixed_styles() {
+    let mut red = style(200.);
+    red.paint.set_color(Color::RED);
+
+    let mut builder = ParagraphBuilder::new(ParagraphStyle::default(), style(100.));
+    builder
+        .add_text("A")
+        .push_style(red)
+        .add_text("B")
+        .pop_style()
+        .add_text("A");
+    let mut p = builder.build();
+    p.layout(f32::INFINITY);
+
+    // the larger font sets the metrics of the line
+    let line = p.lines().next().unwrap();
+    assert_eq!((line.ascent, line.descent, line.height), (-160., 40., 218.));
+    assert_eq!(glyphs(&p), [vec![(A, 0.), (B, 60.), (A, 180.)]]);
+
+    let mut runs: Vec<_> = p
+        .runs()
+        .map(|(run, style)| (run.text_range(), style.font.size(), style.paint.color()))
+        .collect();
+    runs.sort_by_key(|run| run.0.start);
+    assert_eq!(
+        runs,
+        [
+            (0..1, 100., Color::BLACK),
+            (1..2, 200., Color::RED),
+            (2..3, 100., Color::BLACK)
+        ]
+    );
+}
+
+#[test]
+fn bidi() {
+    // the right-to-left part reads from right to left within the line
+    let p = paragraph("A \u{5d0}\u{628}", ParagraphStyle::default(), f32::INFINITY);
+    assert_eq!(glyphs(&p), [vec![(A, 0.), (1, 60.), (8, 90.), (12, 140.)]]);
+
+    // in a right-to-left paragraph the first line starts on the right
+    let style = ParagraphStyle {
+        align: TextAlign::Right,
+        direction: Some(TextDirection::Rtl),
+        ..Default::default()
+    };
+    let p = paragraph("\u{5d0} \u{5d0} AB", style, 150.);
+    assert_eq!(
+        glyphs(&p),
+        [
+            vec![(12, 20.), (1, 70.), (12, 100.)],
+            vec![(A, 30.), (B, 90.)]
+        ]
+    );
+}
+
+#[test]
+fn caret_and_hit_test() {
+    let p = paragraph("AV fi\nB", ParagraphStyle::default(), f32::INFINITY);
+    let caret = |offset| {
+        let rect = p.caret_rect(offset);
+        (rect.l, rect.t, rect.b)
+    };
+
+    // 'V' is kerned, the caret splits the ligature in half
+    assert_eq!(caret(0), (0., 0., 109.));
+    assert_eq!(caret(1), (50., 0., 109.));
+    assert_eq!(caret(3), (140., 0., 109.));
+    assert_eq!(caret(4), (172.5, 0., 109.));
+    assert_eq!(caret(5), (205., 0., 109.));
+    assert_eq!(caret(6), (0., 109., 218.));
+    assert_eq!(caret(7), (60., 109., 218.));
+
+    assert_eq!(p.hit_test(point(-10., 50.)), 0);
+    assert_eq!(p.hit_test(point(20., 50.)), 0);
+    assert_eq!(p.hit_test(point(40., 50.)), 1);
+    assert_eq!(p.hit_test(point(165., 50.)), 4);
+    assert_eq!(p.hit_test(point(300., 50.)), 5);
+    assert_eq!(p.hit_test(point(40., 150.)), 7);
+    assert_eq!(p.hit_test(point(40., 500.)), 7);
+
+    // the leading edge of right-to-left characters is on their right
+    let p = paragraph(
+        "\u{628}\u{628}\u{628}",
+        ParagraphStyle::default(),
+        f32::INFINITY,
+    );
+    assert_eq!(p.caret_rect(0).l, 115.);
+    assert_eq!(p.caret_rect(2).l, 75.);
+    assert_eq!(p.caret_rect(6).l, 0.);
+    assert_eq!(p.hit_test(point(110., 50.)), 0);
+    assert_eq!(p.hit_test(point(5., 50.)), 6);
+}
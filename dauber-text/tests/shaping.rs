@@ -0,0 +1,121 @@
+use dauber_core::color::Color;
+use dauber_core::device::{Device, DeviceFactory};
+use dauber_core::geom::point;
+use dauber_core::geom::point::Point;
+use dauber_core::image_info::ImageInfo;
+use dauber_core::paint::Paint;
+use dauber_software::device::Raster;
+use dauber_text::font::Font;
+use dauber_text::text_blob::{ShapedRun, TextBlob};
+use dauber_text::typeface::{GlyphId, Typeface};
+
+/// Rectangles for glyphs, with a ligature, kerning, a mark and Arabic joining forms,
+/// see generate.py.
+const LAYOUT: &[u8] = include_bytes!("fonts/test-layout.ttf");
+
+const SPACE: u16 = 1;
+const A: u16 = 2;
+const V: u16 = 3;
+const F_I: u16 = 6;
+const ACUTE: u16 = 7;
+const BEH: u16 = 8;
+const ALEF: u16 = 12;
+
+/// The layout font at `size` pixels per em, at 100 one pixel is ten font units.
+fn font(size: f32) -> Font {
+    Font::new(Typeface::from_data(LAYOUT.to_vec(), 0).unwrap(), size)
+}
+
+fn glyphs(run: &ShapedRun) -> Vec<u16> {
+    run.glyphs().iter().map(|g| g.0).collect()
+}
+
+fn xs(run: &ShapedRun) -> Vec<f32> {
+    run.positions().iter().map(|p| p.x).collect()
+}
+
+#[test]
+fn ligatures_and_kerning() {
+    let blob = TextBlob::from_text("AV fi", &font(100.));
+    let [run] = blob.runs() else {
+        panic!("expected a single run");
+    };
+
+    // 'A' moves 'V' 10 pixels closer, "fi" becomes one glyph
+    assert_eq!(glyphs(run), [A, V, SPACE, F_I]);
+    assert_eq!(xs(run), [0., 50., 110., 140.]);
+    assert_eq!(run.clusters(), [0, 1, 2, 3]);
+    assert!(!run.is_rtl());
+    assert_eq!(blob.advance(), 205.);
+}
+
+#[test]
+fn marks() {
+    let blob = TextBlob::from_text("A\u{301}", &font(100.));
+    let run = &blob.runs()[0];
+
+    // the anchor of the accent at x = 5 meets the one of 'A' at x = 30
+    assert_eq!(glyphs(run), [A, ACUTE]);
+    assert_eq!(run.positions(), [point(0., 0.), point(25., 0.)]);
+    assert_eq!(run.clusters(), [0, 0]);
+    assert_eq!(blob.advance(), 60.);
+}
+
+#[test]
+fn arabic() {
+    let blob = TextBlob::from_text("\u{628}\u{628}\u{628}", &font(100.));
+    let [run] = blob.runs() else {
+        panic!("expected a single run");
+    };
+
+    // final, medial and initial forms from left to right
+    assert!(run.is_rtl());
+    assert_eq!(glyphs(run), [11, 10, 9]);
+    assert_eq!(xs(run), [0., 45., 75.]);
+    assert_eq!(run.clusters(), [4, 2, 0]);
+    assert_eq!(blob.advance(), 115.);
+}
+
+#[test]
+fn bidi() {
+    let text = "A \u{5d0}\u{628} V";
+    let blob = TextBlob::from_text(text, &font(100.));
+    let runs = blob.runs();
+
+    // the right-to-left run is split by script, and its parts swap places
+    let ranges: Vec<_> = runs.iter().map(ShapedRun::text_range).collect();
+    assert_eq!(ranges, [0..2, 4..6, 2..4, 6..8]);
+    assert_eq!(
+        runs.iter().map(ShapedRun::is_rtl).collect::<Vec<_>>(),
+        [false, true, true, false]
+    );
+
+    // beh stays isolated next to the Hebrew letter
+    let glyphs: Vec<_> = runs.iter().flat_map(glyphs).collect();
+    assert_eq!(glyphs, [A, SPACE, BEH, ALEF, SPACE, V]);
+    let xs: Vec<_> = runs.iter().flat_map(xs).collect();
+    assert_eq!(xs, [0., 60., 90., 140., 190., 220.]);
+}
+
+#[test]
+fn draw_glyph_run() {
+    let font = font(20.);
+    let blob = TextBlob::from_text("fi", &font);
+    assert_eq!(blob.runs()[0].glyphs(), [GlyphId(F_I)]);
+
+    let mut raster = Raster::new(ImageInfo::new_wh(20, 20));
+    raster.clear(Color::WHITE);
+    for run in blob.runs() {
+        raster
+            .draw_glyph_run(&run.glyph_run(), Point::new(2., 16.), &Paint::new())
+            .unwrap();
+    }
+
+    let pixel = |x: usize, y: usize| raster.pixels()[(y * 20 + x) * 4];
+    // the ligature covers 3..14, where 'f' and 'i' would leave 9..11 empty
+    assert_eq!(pixel(4, 10), 0);
+    assert_eq!(pixel(9, 10), 0);
+    assert_eq!(pixel(13, 10), 0);
+    assert_eq!(pixel(15, 10), 255);
+    assert_eq!(pixel(9, 1), 255);
+}
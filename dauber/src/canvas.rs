@@ -1,7 +1,24 @@
+use crate::clip::ClipOp;
 use crate::device::Device;
+use crate::error::Result;
+use crate::geom::matrix::Matrix;
 use crate::geom::point::Point;
+use crate::geom::rect::Rect;
+use crate::geom::rrect::RRect;
+use crate::image::Image;
 use crate::paint::Paint;
 use crate::path::Path;
+use crate::sampling::SamplingOptions;
+use std::ops::{Deref, DerefMut};
+
+#[cfg(feature = "text")]
+use crate::glyph::GlyphRun;
+#[cfg(feature = "text")]
+use dauber_text::{
+    font::Font,
+    paragraph::Paragraph,
+    text_blob::{ShapedRun, TextBlob},
+};
 
 pub struct Canvas<'a, D> {
     device: &'a mut D,
@@ -14,23 +31,288 @@ impl<'a, D: Device> Canvas<'a, D> {
     }
 
     #[inline]
-    pub fn draw_path(&mut self, path: &Path, paint: &Paint) -> &mut Self {
-        self.device.draw_path(path, paint);
+    pub fn draw_path(&mut self, path: &Path, paint: &Paint) -> Result<&mut Self> {
+        self.device.draw_path(path, paint)?;
 
-        self
+        Ok(self)
     }
 
-    pub fn draw_circle(&mut self, center: Point, radius: f32, paint: &Paint) -> &mut Self {
+    pub fn draw_circle(&mut self, center: Point, radius: f32, paint: &Paint) -> Result<&mut Self> {
         let mut path = Path::new();
         path.add_circle(center, radius);
 
-        self.draw_path(&path, paint);
+        self.draw_path(&path, paint)
+    }
+
+    /// Draw `image` at its size with its top left corner at `left_top`.
+    ///
+    /// See [`draw_image_rect`](Canvas::draw_image_rect) for how `paint` applies.
+    pub fn draw_image(
+        &mut self,
+        image: &Image,
+        left_top: Point,
+        sampling: &SamplingOptions,
+        paint: &Paint,
+    ) -> Result<&mut Self> {
+        let (w, h) = (image.width() as f32, image.height() as f32);
+
+        self.draw_image_rect(
+            image,
+            &Rect::from_ltrb(0., 0., w, h),
+            &Rect::from_xywh(left_top.x, left_top.y, w, h),
+            sampling,
+            paint,
+        )
+    }
+
+    /// Draw the `src` part of `image` scaled into `dst`, sampling nothing outside of `src`.
+    ///
+    /// The alpha of the paint color, its blend mode and anti-aliasing apply,
+    /// its style and shader are ignored.
+    #[inline]
+    pub fn draw_image_rect(
+        &mut self,
+        image: &Image,
+        src: &Rect,
+        dst: &Rect,
+        sampling: &SamplingOptions,
+        paint: &Paint,
+    ) -> Result<&mut Self> {
+        self.device
+            .draw_image_rect(image, src, dst, sampling, paint)?;
+
+        Ok(self)
+    }
+
+    /// Shape `text` and draw it with the left end of its baseline at `origin`.
+    ///
+    /// Shaping applies ligatures, kerning and mark positioning, and reorders mixed
+    /// directions. Use a [`TextBlob`] to draw the same text repeatedly.
+    #[cfg(feature = "text")]
+    pub fn draw_text(
+        &mut self,
+        text: &str,
+        origin: Point,
+        font: &Font,
+        paint: &Paint,
+    ) -> Result<&mut Self> {
+        self.draw_text_blob(&TextBlob::from_text(text, font), origin, paint)
+    }
+
+    /// Draw the glyphs of `blob` with the left end of its baseline at `origin`.
+    ///
+    /// Color glyphs, like emoji, are drawn as described by
+    /// [`Font::draw_color_glyph`].
+    #[cfg(feature = "text")]
+    pub fn draw_text_blob(
+        &mut self,
+        blob: &TextBlob,
+        origin: Point,
+        paint: &Paint,
+    ) -> Result<&mut Self> {
+        for run in blob.runs() {
+            self.draw_shaped_run(run, origin, paint)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Draw a [laid out](Paragraph::layout) paragraph with its top left corner at `left_top`,
+    /// each span with the paint of its style.
+    #[cfg(feature = "text")]
+    pub fn draw_paragraph(&mut self, paragraph: &Paragraph, left_top: Point) -> Result<&mut Self> {
+        for (run, style) in paragraph.runs() {
+            self.draw_shaped_run(run, left_top, &style.paint)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Draw color glyphs one by one and the glyphs between them as glyph runs, in the
+    /// order of `run`.
+    #[cfg(feature = "text")]
+    fn draw_shaped_run(&mut self, run: &ShapedRun, origin: Point, paint: &Paint) -> Result<()> {
+        let font = run.font();
+        if !font.typeface().has_color_glyphs() {
+            return self.device.draw_glyph_run(&run.glyph_run(), origin, paint);
+        }
+
+        let (glyphs, positions) = (run.glyphs(), run.positions());
+        // the first glyph of the span drawn as one run
+        let mut start = 0;
+        for (i, (&glyph, &position)) in glyphs.iter().zip(positions).enumerate() {
+            if !font.is_color_glyph(glyph) {
+                continue;
+            }
+
+            if start < i {
+                let span = GlyphRun::new(font, &glyphs[start..i], &positions[start..i]);
+                self.device.draw_glyph_run(&span, origin, paint)?;
+            }
+            let at = Point::new(origin.x + position.x, origin.y + position.y);
+            start = if font.draw_color_glyph(glyph, at, paint, &mut *self.device)? {
+                i + 1
+            } else {
+                i
+            };
+        }
+
+        if start < glyphs.len() {
+            let span = GlyphRun::new(font, &glyphs[start..], &positions[start..]);
+            self.device.draw_glyph_run(&span, origin, paint)?;
+        }
+
+        Ok(())
+    }
+
+    /// Translate everything drawn afterwards.
+    #[inline]
+    pub fn translate(&mut self, dx: f32, dy: f32) -> &mut Self {
+        self.concat(&Matrix::translate(dx, dy))
+    }
+
+    #[inline]
+    pub fn scale(&mut self, sx: f32, sy: f32) -> &mut Self {
+        self.concat(&Matrix::scale(sx, sy))
+    }
+
+    /// Rotate clockwise by `degrees` around the origin.
+    #[inline]
+    pub fn rotate(&mut self, degrees: f32) -> &mut Self {
+        self.concat(&Matrix::rotate(degrees))
+    }
+
+    #[inline]
+    pub fn skew(&mut self, kx: f32, ky: f32) -> &mut Self {
+        self.concat(&Matrix::skew(kx, ky))
+    }
+
+    /// Apply `matrix` before the current matrix.
+    pub fn concat(&mut self, matrix: &Matrix) -> &mut Self {
+        let matrix = self.device.matrix().concat(matrix);
+        self.device.set_matrix(matrix);
+
+        self
+    }
+
+    #[inline]
+    pub fn set_matrix(&mut self, matrix: Matrix) -> &mut Self {
+        self.device.set_matrix(matrix);
 
         self
     }
 
+    #[inline]
+    pub fn reset_matrix(&mut self) -> &mut Self {
+        self.set_matrix(Matrix::IDENTITY)
+    }
+
+    #[inline]
+    pub fn matrix(&self) -> &Matrix {
+        self.device.matrix()
+    }
+
+    /// Restrict drawing to `rect`, transformed by the current matrix.
+    pub fn clip_rect(&mut self, rect: &Rect, op: ClipOp, anti_alias: bool) -> Result<&mut Self> {
+        let mut path = Path::new();
+        path.add_rect(rect);
+
+        self.clip_path(&path, op, anti_alias)
+    }
+
+    /// Restrict drawing to `rrect`, transformed by the current matrix.
+    pub fn clip_rrect(&mut self, rrect: &RRect, op: ClipOp, anti_alias: bool) -> Result<&mut Self> {
+        let mut path = Path::new();
+        path.add_rrect(rrect);
+
+        self.clip_path(&path, op, anti_alias)
+    }
+
+    /// Restrict drawing to the area `path` fills, honoring its fill type.
+    #[inline]
+    pub fn clip_path(&mut self, path: &Path, op: ClipOp, anti_alias: bool) -> Result<&mut Self> {
+        self.device.clip_path(path, op, anti_alias)?;
+
+        Ok(self)
+    }
+
+    /// Save the current matrix and clip,
+    /// returns the save count to pass to [`restore_to_count`](Canvas::restore_to_count).
+    #[inline]
+    pub fn save(&mut self) -> usize {
+        let count = self.save_count();
+        self.device.save();
+
+        count
+    }
+
+    /// Restore the state of the last [`save`](Canvas::save), does nothing if there is none.
+    #[inline]
+    pub fn restore(&mut self) -> &mut Self {
+        self.device.restore();
+
+        self
+    }
+
+    /// Restore until [`save_count`](Canvas::save_count) is `count`, at least one.
+    pub fn restore_to_count(&mut self, count: usize) -> &mut Self {
+        let count = count.max(1);
+        while self.save_count() > count {
+            self.device.restore();
+        }
+
+        self
+    }
+
+    /// The number of saved states plus one, the state of a new canvas is one.
+    #[inline]
+    pub fn save_count(&self) -> usize {
+        self.device.save_count()
+    }
+
+    /// Save the current state and restore it when the returned guard is dropped.
+    ///
+    /// The guard dereferences to the canvas, so it can be drawn on directly.
+    #[inline]
+    pub fn auto_restore(&mut self) -> AutoRestore<'_, 'a, D> {
+        let count = self.save();
+
+        AutoRestore {
+            canvas: self,
+            count,
+        }
+    }
+
     #[inline]
     pub fn device(&self) -> &D {
         self.device
     }
 }
+
+/// Restores the canvas to the state of [`Canvas::auto_restore`] on drop.
+pub struct AutoRestore<'c, 'a, D: Device> {
+    canvas: &'c mut Canvas<'a, D>,
+    count: usize,
+}
+
+impl<'a, D: Device> Deref for AutoRestore<'_, 'a, D> {
+    type Target = Canvas<'a, D>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.canvas
+    }
+}
+
+impl<D: Device> DerefMut for AutoRestore<'_, '_, D> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.canvas
+    }
+}
+
+impl<D: Device> Drop for AutoRestore<'_, '_, D> {
+    fn drop(&mut self) {
+        self.canvas.restore_to_count(self.count);
+    }
+}
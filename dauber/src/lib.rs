@@ -2,3 +2,12 @@ pub mod canvas;
 pub mod surface;
 
 pub use dauber_core::*;
+
+#[cfg(feature = "software")]
+pub use dauber_software as software;
+
+#[cfg(feature = "gpu")]
+pub use dauber_gpu as gpu;
+
+#[cfg(feature = "text")]
+pub use dauber_text as text;
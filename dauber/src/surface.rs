@@ -1,5 +1,9 @@
 use crate::canvas::Canvas;
 use crate::device::Device;
+use crate::image::Image;
+use crate::image_info::ImageInfo;
+use crate::pixmap::PixmapMut;
+use crate::Result;
 
 pub struct Surface<D> {
     device: D,
@@ -20,11 +24,11 @@ impl<D: Device> Surface<D> {
 
     #[inline]
     pub fn height(&self) -> u32 {
-        self.device.image_info().width
+        self.device.image_info().height
     }
 
     #[inline]
-    pub fn canvas(&mut self) -> Canvas<D> {
+    pub fn canvas(&mut self) -> Canvas<'_, D> {
         Canvas::new(&mut self.device)
     }
 
@@ -32,4 +36,30 @@ impl<D: Device> Surface<D> {
     pub fn device(&self) -> &D {
         &self.device
     }
+
+    /// Copy the pixels starting at (`src_x`, `src_y`) into `pixels`, laid out by `info`
+    /// with `row_bytes` from one row to the next.
+    ///
+    /// The pixels are converted to the color type, alpha type and color space of `info`.
+    /// Only the part overlapping the surface is written, `false` if nothing overlaps.
+    pub fn read_pixels(
+        &mut self,
+        info: &ImageInfo,
+        pixels: &mut [u8],
+        row_bytes: usize,
+        src_x: i32,
+        src_y: i32,
+    ) -> Result<bool> {
+        let mut dst = PixmapMut::new(*info, pixels, row_bytes)?;
+        self.device.read_pixels(&mut dst, src_x, src_y)
+    }
+
+    /// An immutable copy of everything drawn so far.
+    pub fn snapshot(&mut self) -> Result<Image> {
+        let info = *self.device.image_info();
+        let mut pixels = vec![0; info.min_row_bytes() * info.height as usize];
+        self.read_pixels(&info, &mut pixels, info.min_row_bytes(), 0, 0)?;
+
+        Image::from_pixels(info, pixels)
+    }
 }
@@ -0,0 +1,272 @@
+use dauber::clip::ClipOp;
+use dauber::color::Color;
+use dauber::device::{Device, DeviceFactory, Sink};
+use dauber::geom::matrix::Matrix;
+use dauber::geom::point;
+use dauber::geom::rect::Rect;
+use dauber::geom::rrect::RRect;
+use dauber::image_info::{AlphaType, ColorType, ImageInfo};
+use dauber::paint::Paint;
+use dauber::path::Path;
+use dauber::surface::{DynSurface, Surface};
+use dauber::Result;
+use dauber_software::device::Raster;
+
+#[test]
+fn raster_surface() -> Result<()> {
+    let mut surface = Surface::new_from_device(Raster::new(ImageInfo::new_wh(32, 16)));
+    assert_eq!((surface.width(), surface.height()), (32, 16));
+
+    let mut paint = Paint::new();
+    paint.set_color(Color::RED);
+
+    surface
+        .canvas()
+        .draw_circle(point(8., 8.), 6., &paint)?
+        .draw_circle(point(24., 8.), 6., &paint)?;
+
+    let pixels = surface.device().pixels();
+    let at = |x: usize, y: usize| &pixels[(y * 32 + x) * 4..][..4];
+
+    assert_eq!(at(8, 8), [255, 0, 0, 255]);
+    assert_eq!(at(24, 8), [255, 0, 0, 255]);
+    assert_eq!(at(16, 8), [0, 0, 0, 0]);
+
+    Ok(())
+}
+
+#[test]
+fn transform() -> Result<()> {
+    let mut surface = Surface::new_from_device(Raster::new(ImageInfo::new_wh(32, 32)));
+
+    let mut paint = Paint::new();
+    paint.set_color(Color::WHITE);
+
+    let mut path = Path::new();
+    path.move_to(point(0., 0.));
+    path.line_to(point(4., 0.));
+    path.line_to(point(4., 2.));
+    path.line_to(point(0., 2.));
+    path.close();
+
+    let mut canvas = surface.canvas();
+    canvas
+        .translate(16., 16.)
+        .scale(2., 2.)
+        .draw_path(&path, &paint)?;
+    assert_eq!(
+        *canvas.matrix(),
+        Matrix::translate(16., 16.) * Matrix::scale(2., 2.)
+    );
+
+    canvas.reset_matrix().rotate(90.).translate(8., -8.);
+    canvas.draw_path(&path, &paint)?;
+
+    let pixels = surface.device().pixels();
+    let at = |x: usize, y: usize| pixels[(y * 32 + x) * 4];
+
+    // (16, 16) to (24, 20)
+    assert_eq!(at(16, 16), 255);
+    assert_eq!(at(23, 19), 255);
+    assert_eq!(at(24, 19), 0);
+    assert_eq!(at(23, 20), 0);
+    // rotated: (6, 8) to (8, 12)
+    assert_eq!(at(6, 8), 255);
+    assert_eq!(at(7, 11), 255);
+    assert_eq!(at(5, 8), 0);
+    assert_eq!(at(6, 12), 0);
+
+    Ok(())
+}
+
+#[test]
+fn save_restore() {
+    let mut surface = Surface::new_from_device(Raster::new(ImageInfo::new_wh(8, 8)));
+    let mut canvas = surface.canvas();
+    assert_eq!(canvas.save_count(), 1);
+
+    canvas.translate(1., 2.);
+    assert_eq!(canvas.save(), 1);
+    canvas.scale(3., 3.);
+    assert_eq!(canvas.save(), 2);
+    canvas.reset_matrix();
+    assert_eq!(canvas.save_count(), 3);
+
+    canvas.restore();
+    assert_eq!(
+        *canvas.matrix(),
+        Matrix::translate(1., 2.) * Matrix::scale(3., 3.)
+    );
+
+    canvas.restore().restore();
+    assert_eq!(canvas.save_count(), 1);
+    assert_eq!(*canvas.matrix(), Matrix::translate(1., 2.));
+
+    canvas.save();
+    canvas.save();
+    canvas.rotate(45.);
+    canvas.restore_to_count(1);
+    assert_eq!(canvas.save_count(), 1);
+    assert_eq!(*canvas.matrix(), Matrix::translate(1., 2.));
+
+    {
+        let mut guard = canvas.auto_restore();
+        guard.translate(10., 0.);
+        guard.save();
+        assert_eq!(guard.save_count(), 3);
+    }
+    assert_eq!(canvas.save_count(), 1);
+    assert_eq!(*canvas.matrix(), Matrix::translate(1., 2.));
+
+    // the state lives in the device, not in the borrowed canvas
+    canvas.save();
+    assert_eq!(surface.canvas().save_count(), 2);
+}
+
+#[test]
+fn clip() -> Result<()> {
+    let mut surface = Surface::new_from_device(Raster::new(ImageInfo::new_wh(32, 32)));
+
+    let mut paint = Paint::new();
+    paint.set_color(Color::WHITE);
+
+    let mut full = Path::new();
+    full.add_rect(&Rect::from_xywh(0., 0., 32., 32.));
+
+    let mut canvas = surface.canvas();
+    canvas.save();
+    canvas
+        .clip_rect(&Rect::from_xywh(4., 4., 20., 20.), ClipOp::Intersect, false)?
+        .clip_rect(&Rect::from_xywh(8., 8., 4., 4.), ClipOp::Difference, false)?
+        .draw_path(&full, &paint)?;
+    canvas.restore();
+
+    // the clip is transformed by the matrix and gone after restore
+    paint.set_color(Color::RED);
+    canvas
+        .translate(24., 24.)
+        .clip_rrect(
+            &RRect::from_rect_xy(Rect::from_xywh(0., 0., 8., 8.), 4., 4.),
+            ClipOp::Intersect,
+            true,
+        )?
+        .draw_path(&full, &paint)?;
+
+    let pixels = surface.device().pixels();
+    let at = |x: usize, y: usize| &pixels[(y * 32 + x) * 4..][..4];
+
+    assert_eq!(at(4, 4), [255, 255, 255, 255]);
+    assert_eq!(at(23, 23), [255, 255, 255, 255]);
+    assert_eq!(at(3, 4), [0, 0, 0, 0]);
+    assert_eq!(at(24, 4), [0, 0, 0, 0]);
+    assert_eq!(at(10, 10), [0, 0, 0, 0]);
+    assert_eq!(at(12, 10), [255, 255, 255, 255]);
+
+    assert_eq!(at(28, 28), [255, 0, 0, 255]);
+    assert_eq!(at(24, 24), [0, 0, 0, 0]);
+    assert_eq!(at(23, 28), [0, 0, 0, 0]);
+
+    Ok(())
+}
+
+#[test]
+fn dyn_surface() -> Result<()> {
+    // the backend is only known at runtime
+    let device = |software: bool| -> Box<dyn Device> {
+        if software {
+            Box::new(Raster::new(ImageInfo::new_wh(16, 8)))
+        } else {
+            Box::new(Sink::new(ImageInfo::new_wh(0, 0)))
+        }
+    };
+
+    let mut surfaces: Vec<DynSurface> = [true, false]
+        .map(|software| Surface::new_from_device(device(software)))
+        .into();
+
+    for surface in &mut surfaces {
+        let mut canvas = surface.canvas();
+        canvas.save();
+        canvas.translate(4., 4.);
+        canvas.draw_circle(point(0., 0.), 2., &Paint::new())?;
+        assert_eq!(canvas.save_count(), 2);
+    }
+
+    assert_eq!((surfaces[0].width(), surfaces[0].height()), (16, 8));
+    assert_eq!(surfaces[1].width(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn borrowed_device() -> Result<()> {
+    let mut raster = Raster::new(ImageInfo::new_wh(8, 8));
+
+    let mut paint = Paint::new();
+    paint.set_color(Color::WHITE);
+
+    Surface::new_from_device(&mut raster)
+        .canvas()
+        .draw_circle(point(4., 4.), 3., &paint)?;
+
+    assert_eq!(&raster.pixels()[(4 * 8 + 4) * 4..][..4], [255; 4]);
+
+    Ok(())
+}
+
+#[test]
+fn read_pixels() -> Result<()> {
+    let mut surface = Surface::new_from_device(Raster::new(ImageInfo::new_wh(8, 8)));
+
+    let mut paint = Paint::new();
+    paint.set_color(Color::RED.with_a(0.5));
+    let mut rect = Path::new();
+    rect.add_rect(&Rect::from_xywh(2., 2., 4., 4.));
+    surface.canvas().draw_path(&rect, &paint)?;
+
+    // a padded BGRA destination, unpremultiplied
+    let info = ImageInfo::new_wh(4, 4)
+        .with_color_type(ColorType::Bgra8888)
+        .with_alpha_type(AlphaType::Unpremul);
+    let row_bytes = 20;
+    let mut pixels = vec![7; row_bytes * 4];
+    assert!(surface.read_pixels(&info, &mut pixels, row_bytes, 4, 4)?);
+
+    assert_eq!(&pixels[..4], [0, 0, 255, 128]);
+    assert_eq!(&pixels[8..12], [0; 4]);
+    // the padding is left alone
+    assert_eq!(&pixels[16..20], [7; 4]);
+
+    // only the overlapping part is written
+    let mut pixels = vec![7; 16 * 4];
+    assert!(surface.read_pixels(&info, &mut pixels, 16, -2, -2)?);
+    assert_eq!(&pixels[..4], [7; 4]);
+    assert_eq!(&pixels[2 * 16 + 2 * 4..][..4], [0; 4]);
+    assert!(!surface.read_pixels(&info, &mut pixels, 16, 8, 0)?);
+
+    // too small for the rows
+    assert!(surface.read_pixels(&info, &mut pixels, 15, 0, 0).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn snapshot() -> Result<()> {
+    let mut surface = Surface::new_from_device(Raster::new(ImageInfo::new_wh(8, 8)));
+
+    let mut paint = Paint::new();
+    paint.set_color(Color::GREEN);
+    let mut canvas = surface.canvas();
+    canvas.draw_circle(point(4., 4.), 3., &paint)?;
+
+    let image = surface.snapshot()?;
+    assert_eq!((image.width(), image.height()), (8, 8));
+    assert_eq!(image.pixels(), surface.device().pixels());
+
+    // later draws do not change the snapshot
+    paint.set_color(Color::BLUE);
+    surface.canvas().draw_circle(point(4., 4.), 3., &paint)?;
+    assert_eq!(&image.pixels()[(4 * 8 + 4) * 4..][..4], [0, 255, 0, 255]);
+
+    Ok(())
+}
@@ -0,0 +1,191 @@
+#![cfg(feature = "text")]
+
+use dauber::clip::ClipOp;
+use dauber::color::Color;
+use dauber::device::{Device, DeviceFactory, Sink};
+use dauber::geom::matrix::Matrix;
+use dauber::geom::point;
+use dauber::geom::point::Point;
+use dauber::geom::rect::Rect;
+use dauber::glyph::GlyphRun;
+use dauber::image::Image;
+use dauber::image_info::ImageInfo;
+use dauber::paint::Paint;
+use dauber::path::Path;
+use dauber::pixmap::PixmapMut;
+use dauber::sampling::SamplingOptions;
+use dauber::surface::Surface;
+use dauber::text::font::Font;
+use dauber::text::paragraph::{ParagraphBuilder, ParagraphStyle, TextAlign, TextStyle};
+use dauber::text::text_blob::TextBlob;
+use dauber::text::typeface::Typeface;
+use dauber::Result;
+use dauber_software::device::Raster;
+
+const LAYOUT: &[u8] = include_bytes!("../../dauber-text/tests/fonts/test-layout.ttf");
+const COLOR: &[u8] = include_bytes!("../../dauber-text/tests/fonts/test-color.ttf");
+
+#[test]
+fn draw_text() -> Result<()> {
+    let font = Font::new(Typeface::from_data(LAYOUT.to_vec(), 0)?, 20.);
+    let mut paint = Paint::new();
+    paint.set_color(Color::RED);
+
+    let mut text = Surface::new_from_device(Raster::new(ImageInfo::new_wh(32, 20)));
+    text.canvas()
+        .draw_text("AV", point(2., 16.), &font, &paint)?;
+
+    // kerned, 'V' starts 10 pixels after 'A' instead of 12
+    let pixels = text.device().pixels();
+    let at = |x: usize, y: usize| &pixels[(y * 32 + x) * 4..][..4];
+    assert_eq!(at(12, 10), [255, 0, 0, 255]);
+    assert_eq!(at(22, 10), [255, 0, 0, 255]);
+    assert_eq!(at(24, 10), [0, 0, 0, 0]);
+
+    let mut blob = Surface::new_from_device(Raster::new(ImageInfo::new_wh(32, 20)));
+    blob.canvas()
+        .draw_text_blob(&TextBlob::from_text("AV", &font), point(2., 16.), &paint)?;
+    assert_eq!(blob.device().pixels(), text.device().pixels());
+
+    Ok(())
+}
+
+#[test]
+fn draw_paragraph() -> Result<()> {
+    let font = Font::new(Typeface::from_data(LAYOUT.to_vec(), 0)?, 10.);
+    let mut red = TextStyle::new(font.clone());
+    red.paint.set_color(Color::RED);
+
+    let style = ParagraphStyle {
+        align: TextAlign::Right,
+        ..Default::default()
+    };
+    let mut builder = ParagraphBuilder::new(style, TextStyle::new(font));
+    builder.add_text("A ").push_style(red).add_text("B");
+    let mut paragraph = builder.build();
+    paragraph.layout(10.);
+
+    let mut surface = Surface::new_from_device(Raster::new(ImageInfo::new_wh(12, 24)));
+    surface.canvas().draw_paragraph(&paragraph, point(1., 1.))?;
+
+    // 'B' wraps, both lines end at the right with baselines at 9.45 and 20.35
+    let pixels = surface.device().pixels();
+    let at = |x: usize, y: usize| &pixels[(y * 12 + x) * 4..][..4];
+    assert_eq!(at(8, 6), [0, 0, 0, 255]);
+    assert_eq!(at(8, 17), [255, 0, 0, 255]);
+    assert_eq!(at(2, 6), [0, 0, 0, 0]);
+    assert_eq!(at(8, 21), [0, 0, 0, 0]);
+
+    Ok(())
+}
+
+#[test]
+fn draw_color_text() -> Result<()> {
+    let font = Font::new(Typeface::from_data(COLOR.to_vec(), 0)?, 10.);
+    let mut paint = Paint::new();
+    paint.set_color(Color::BLUE);
+
+    let mut surface = Surface::new_from_device(Raster::new(ImageInfo::new_wh(20, 10)));
+    surface
+        .canvas()
+        .draw_text("xA", point(0., 10.), &font, &paint)?;
+
+    // 'x' is an outline in the paint color, 'A' red with the paint color on top
+    let pixels = surface.device().pixels();
+    let at = |x: usize, y: usize| &pixels[(y * 20 + x) * 4..][..4];
+    assert_eq!(at(5, 5), [0, 0, 255, 255]);
+    assert_eq!(at(15, 8), [255, 0, 0, 255]);
+    assert_eq!(at(15, 2), [0, 0, 255, 255]);
+
+    Ok(())
+}
+
+/// Records which kind of draw each call was.
+struct Recorder {
+    sink: Sink,
+    calls: Vec<&'static str>,
+}
+
+impl Device for Recorder {
+    fn image_info(&self) -> &ImageInfo {
+        self.sink.image_info()
+    }
+
+    fn matrix(&self) -> &Matrix {
+        self.sink.matrix()
+    }
+
+    fn set_matrix(&mut self, matrix: Matrix) {
+        self.sink.set_matrix(matrix);
+    }
+
+    fn save(&mut self) {
+        self.sink.save();
+    }
+
+    fn restore(&mut self) {
+        self.sink.restore();
+    }
+
+    fn save_count(&self) -> usize {
+        self.sink.save_count()
+    }
+
+    fn clip_path(&mut self, path: &Path, op: ClipOp, anti_alias: bool) -> Result<()> {
+        self.sink.clip_path(path, op, anti_alias)
+    }
+
+    fn draw_path(&mut self, _path: &Path, _paint: &Paint) -> Result<()> {
+        self.calls.push("path");
+        Ok(())
+    }
+
+    fn draw_image_rect(
+        &mut self,
+        _image: &Image,
+        _src: &Rect,
+        _dst: &Rect,
+        _sampling: &SamplingOptions,
+        _paint: &Paint,
+    ) -> Result<()> {
+        self.calls.push("image");
+        Ok(())
+    }
+
+    fn draw_glyph_run(&mut self, run: &GlyphRun<'_>, _origin: Point, _paint: &Paint) -> Result<()> {
+        self.calls.push(if run.glyphs().len() == 1 {
+            "glyph"
+        } else {
+            "glyphs"
+        });
+        Ok(())
+    }
+
+    fn read_pixels(&mut self, dst: &mut PixmapMut<'_>, src_x: i32, src_y: i32) -> Result<bool> {
+        self.sink.read_pixels(dst, src_x, src_y)
+    }
+}
+
+#[test]
+fn color_glyph_order() -> Result<()> {
+    let font = Font::new(Typeface::from_data(COLOR.to_vec(), 0)?, 10.);
+    let mut recorder = Recorder {
+        sink: Sink::new(ImageInfo::new_wh(40, 10)),
+        calls: vec![],
+    };
+
+    // outlines before a color glyph are drawn before it
+    Surface::new_from_device(&mut recorder).canvas().draw_text(
+        "xxAx",
+        point(0., 10.),
+        &font,
+        &Paint::new(),
+    )?;
+    assert_eq!(recorder.calls.first(), Some(&"glyphs"));
+    assert_eq!(recorder.calls.last(), Some(&"glyph"));
+    assert!(recorder.calls[1..recorder.calls.len() - 1]
+        .iter()
+        .all(|&call| call == "path"));
+
+    Ok(())
+}